@@ -0,0 +1,255 @@
+//! 画面読み上げ（スクリーンリーダー）向けの canvas の写し
+//!
+//! canvas の絵は読み上げられないので、ゲームは今の画面の内容と行える操作を `Mirror` として返す。
+//! engine は変化があったときだけ `DomMirror` に反映する。`DomMirror` は画面外に置いた
+//! ARIA ライブリージョン（内容の読み上げ）と、操作ごとのボタン（Tab で移動して押せる）を持つ。
+//! ボタンは `Action::button_code` のキーを押したことにするので、キー割り当てに関係なく使える。
+
+use crate::browser;
+use crate::etymology::Etymology;
+use crate::keymap::Action;
+use crate::ruby::parse_ruby;
+use anyhow::{Result, anyhow};
+use futures::channel::mpsc::{UnboundedReceiver, unbounded};
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlElement};
+
+/// 読み上げ用の今の画面の内容
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mirror {
+    pub progress: Option<String>, // プログレスカウンター（例: "カード 2/5"）
+    pub content: String,          // カードの面や、開いているウィンドウの内容
+    pub actions: Vec<Action>,     // 今行える操作（ボタンとして表示する）
+}
+
+/// 操作ボタンに表示・読み上げる名前
+pub fn label(action: Action) -> &'static str {
+    match action {
+        Action::Flip => "めくる",
+        Action::Again => "もう一度（後回しにする）",
+        Action::Hard => "難しい（後回しにする）",
+        Action::Good => "覚えた",
+        Action::Easy => "簡単（覚えた）",
+        Action::SwipeLeft => "値を減らす", // 設定画面でだけボタンにする
+        Action::SwipeRight => "値を増やす",
+        Action::Undo => "取り消す",
+        Action::ReplayAudio => "もう一度読み上げる",
+        Action::OpenStats => "学習状況",
+        Action::ToggleFurigana => "ふりがなの表示を切り替える",
+        Action::WordFamily => "ワードファミリー",
+        Action::OpenSettings => "設定",
+        Action::Confirm => "決定",
+        Action::Cancel => "閉じる",
+        Action::Up => "前へ",
+        Action::Down => "次へ",
+    }
+}
+
+/// ルビ記法と強調の [ ] を取り除いた読み上げ用の文
+pub fn plain_text(text: &str) -> String {
+    parse_ruby(text)
+        .into_iter()
+        .map(|segment| segment.base)
+        .collect::<String>()
+        .replace(['[', ']'], "")
+}
+
+/// 語源の読み上げ用の文（例: "語源: dūrābilis（ラテン語）、長持ちする。派生語: endure（耐える）"）
+pub fn describe_etymology(etymology: &Etymology) -> String {
+    if etymology.root.is_empty() {
+        return String::new();
+    }
+    let mut text = format!("語源: {}", etymology.root);
+    if let Some(language) = &etymology.language {
+        text += &format!("（{}）", language);
+    }
+    if !etymology.gloss.is_empty() {
+        text += &format!("、{}", etymology.gloss);
+    }
+    let derived: Vec<String> = etymology
+        .derived
+        .iter()
+        .map(|d| {
+            if d.gloss.is_empty() {
+                d.word.clone()
+            } else {
+                format!("{}（{}）", d.word, d.gloss)
+            }
+        })
+        .collect();
+    if !derived.is_empty() {
+        text += &format!("。派生語: {}", derived.join("、"));
+    }
+    text
+}
+
+/// ページに置く読み上げ用の要素（画面外に置き、キーボードで操作ボタンに移動したときだけ見える）
+pub struct DomMirror {
+    live: HtmlElement, // ライブリージョン（ボタンがなくなったときのフォーカスの移し先）
+    progress: Element,
+    content: Element,
+    buttons: Vec<(Action, HtmlElement)>, // Action::ALL の順のボタン（行えない操作は隠す）
+}
+impl DomMirror {
+    /// 要素を作って body に加える。ボタンが押された操作を受け取るチャンネルも返す
+    pub fn new() -> Result<(Self, UnboundedReceiver<Action>)> {
+        let document = browser::document()?;
+        let create = |tag: &str| {
+            document
+                .create_element(tag)
+                .map_err(|err| anyhow!("Could not create {} {:#?}", tag, err))
+        };
+        let set = |element: &Element, name: &str, value: &str| {
+            element
+                .set_attribute(name, value)
+                .map_err(|err| anyhow!("Could not set {} {:#?}", name, err))
+        };
+        let append = |parent: &Element, child: &Element| {
+            parent
+                .append_child(child)
+                .map(|_| ())
+                .map_err(|err| anyhow!("Could not append {:#?}", err))
+        };
+
+        // 見た目は index.html の #a11y で指定する
+        let root = create("div")?;
+        set(&root, "id", "a11y")?;
+        let live = create("div")?
+            .dyn_into::<HtmlElement>()
+            .map_err(|element| anyhow!("Error converting {:#?} to HtmlElement", element))?;
+        set(&live, "tabindex", "-1")?;
+        set(&live, "role", "status")?;
+        set(&live, "aria-live", "polite")?;
+        set(&live, "aria-atomic", "true")?;
+        let progress = create("p")?;
+        let content = create("p")?;
+        append(&live, &progress)?;
+        append(&live, &content)?;
+        append(&root, &live)?;
+
+        let toolbar = create("div")?;
+        set(&toolbar, "role", "toolbar")?;
+        set(&toolbar, "aria-label", "操作")?;
+        let (sender, receiver) = unbounded();
+        let mut buttons = Vec::new();
+        for action in Action::ALL {
+            let button = create("button")?
+                .dyn_into::<HtmlElement>()
+                .map_err(|element| anyhow!("Error converting {:#?} to HtmlElement", element))?;
+            set(&button, "type", "button")?;
+            button.set_text_content(Some(label(action)));
+            button.set_hidden(true);
+            let sender = sender.clone();
+            let onclick = browser::closure_wrap(Box::new(move || {
+                let _ = sender.unbounded_send(action);
+            }) as Box<dyn FnMut()>);
+            button.set_onclick(Some(onclick.as_ref().unchecked_ref()));
+            onclick.forget();
+            append(&toolbar, &button)?;
+            buttons.push((action, button));
+        }
+        append(&root, &toolbar)?;
+        browser::document()?
+            .body()
+            .ok_or_else(|| anyhow!("No Body Found"))?
+            .append_child(&root)
+            .map_err(|err| anyhow!("Could not append #a11y {:#?}", err))?;
+
+        Ok((
+            DomMirror {
+                live,
+                progress,
+                content,
+                buttons,
+            },
+            receiver,
+        ))
+    }
+    /// 内容とボタンを mirror に合わせる（変わったところだけ書き換えて、読み上げの繰り返しを避ける）
+    pub fn update(&self, previous: &Mirror, mirror: &Mirror) {
+        if previous.progress != mirror.progress {
+            self.progress
+                .set_text_content(mirror.progress.as_deref());
+        }
+        if previous.content != mirror.content {
+            self.content.set_text_content(Some(&mirror.content));
+        }
+        let shown: Vec<bool> = self
+            .buttons
+            .iter()
+            .map(|(action, _)| mirror.actions.contains(action))
+            .collect();
+        let focused = browser::document()
+            .ok()
+            .and_then(|document| document.active_element())
+            .and_then(|active| {
+                self.buttons
+                    .iter()
+                    .position(|(_, button)| button.is_same_node(Some(&active)))
+            });
+        for (i, (_, button)) in self.buttons.iter().enumerate() {
+            if Some(i) != focused {
+                button.set_hidden(!shown[i]);
+            }
+        }
+        // フォーカスのあるボタンをそのまま隠すとフォーカスが body に落ちるので、
+        // 先に次に見えるボタン（なければライブリージョン）へ移してから隠す
+        if let Some(focused) = focused
+            && !shown[focused]
+        {
+            let _ = match next_shown(&shown, focused) {
+                Some(next) => self.buttons[next].1.focus(),
+                None => self.live.focus(),
+            };
+            self.buttons[focused].1.set_hidden(true);
+        }
+    }
+}
+
+/// from の次から順に（末尾の次は先頭に戻って）最初に見えるボタンの位置
+fn next_shown(shown: &[bool], from: usize) -> Option<usize> {
+    (1..shown.len())
+        .map(|k| (from + k) % shown.len())
+        .find(|&i| shown[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text_drops_readings_and_highlights() {
+        assert_eq!(
+            plain_text("ハイキング用の[丈夫{じょうぶ}な]靴{くつ}が必要です"),
+            "ハイキング用の丈夫な靴が必要です"
+        );
+        assert_eq!(plain_text("I need [durable] shoes"), "I need durable shoes");
+    }
+
+    #[test]
+    fn etymology_is_described_in_one_sentence() {
+        let etymology = Etymology::parse(&[
+            "dūrābilis/ラテン語: 長持ちする",
+            "endure（耐える）: en- + dūrus",
+        ]);
+        assert_eq!(
+            describe_etymology(&etymology),
+            "語源: dūrābilis（ラテン語）、長持ちする。派生語: endure（耐える）"
+        );
+        assert_eq!(describe_etymology(&Etymology::default()), "");
+
+        // 意味のない派生語は括弧を付けない
+        let etymology = Etymology::parse(&["dūrābilis/ラテン語: 長持ちする", "durable:"]);
+        assert_eq!(
+            describe_etymology(&etymology),
+            "語源: dūrābilis（ラテン語）、長持ちする。派生語: durable"
+        );
+    }
+
+    #[test]
+    fn focus_moves_to_the_next_shown_button() {
+        assert_eq!(next_shown(&[true, false, false, true], 1), Some(3));
+        assert_eq!(next_shown(&[true, false, false, false], 2), Some(0));
+        assert_eq!(next_shown(&[false, true, false], 1), None);
+    }
+}
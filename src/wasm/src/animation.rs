@@ -0,0 +1,110 @@
+//! 経過時間で進むアニメーション（トゥイーン）とイージング
+//!
+//! update の間隔（ティック）に関係なく、同じ時間で同じ動きになるように
+//! 「何ミリ秒経ったか」で値を決める。
+
+/// イージング（0.0〜1.0 の進み具合を変形する）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseInCubic,    // ゆっくり始まり加速する
+    EaseInOutCubic, // ゆっくり始まり、ゆっくり終わる
+}
+impl Easing {
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInCubic => t * t * t,
+            Easing::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// from から to へ duration ミリ秒かけて変化する値
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tween {
+    from: f32,
+    to: f32,
+    duration: f64, // ms
+    elapsed: f64,  // ms
+    easing: Easing,
+}
+impl Tween {
+    pub fn new(from: f32, to: f32, duration: f64, easing: Easing) -> Self {
+        Tween {
+            from,
+            to,
+            duration,
+            elapsed: 0.0,
+            easing,
+        }
+    }
+    /// dt ミリ秒進めて、その時点の値を返す
+    pub fn advance(&mut self, dt: f64) -> f32 {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+        self.value()
+    }
+    /// 進み具合（0.0〜1.0、イージング前）
+    pub fn progress(&self) -> f64 {
+        if self.duration <= 0.0 {
+            1.0
+        } else {
+            self.elapsed / self.duration
+        }
+    }
+    pub fn value(&self) -> f32 {
+        let eased = self.easing.apply(self.progress()) as f32;
+        self.from + (self.to - self.from) * eased
+    }
+    pub fn is_finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easings_start_at_zero_and_end_at_one() {
+        // 1/4 の時点の値: t、t³、4t³（後半は 1 - (2 - 2t)³ / 2）
+        for (easing, quarter, three_quarters) in [
+            (Easing::Linear, 0.25, 0.75),
+            (Easing::EaseInCubic, 0.015625, 0.421875),
+            (Easing::EaseInOutCubic, 0.0625, 0.9375),
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-12, "{:?}", easing);
+            assert!((easing.apply(0.25) - quarter).abs() < 1e-12, "{:?}", easing);
+            assert!((easing.apply(0.75) - three_quarters).abs() < 1e-12, "{:?}", easing);
+        }
+        assert_eq!(Easing::EaseInOutCubic.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn tween_depends_on_elapsed_time_not_step_count() {
+        let mut coarse = Tween::new(0.0, 10.0, 400.0, Easing::EaseInOutCubic);
+        let mut fine = coarse;
+        coarse.advance(200.0);
+        for _ in 0..12 {
+            fine.advance(200.0 / 12.0);
+        }
+        assert!((coarse.value() - fine.value()).abs() < 1e-4);
+        assert_eq!(coarse.value(), 5.0);
+    }
+
+    #[test]
+    fn tween_clamps_at_the_end() {
+        let mut tween = Tween::new(1.0, 0.0, 100.0, Easing::Linear);
+        assert!(!tween.is_finished());
+        assert_eq!(tween.advance(250.0), 0.0);
+        assert!(tween.is_finished());
+    }
+}
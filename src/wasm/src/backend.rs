@@ -0,0 +1,479 @@
+//! 描画先（バックエンド）
+//!
+//! `Renderer` は 2D の描画命令をすべて `RenderBackend` に出す。
+//! ブラウザでは `CanvasBackend`（CanvasRenderingContext2d）を使い、
+//! ネイティブのテストでは描画命令を記録する `RecordingBackend` と、
+//! CPU で PNG に描く `skia::SkiaBackend`（スナップショットテスト用）を使う。
+
+#[cfg(test)]
+pub mod skia;
+
+use std::any::Any;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+/// 2D の描画命令（CanvasRenderingContext2d の必要な部分と同じ意味）
+pub trait RenderBackend {
+    fn as_any(&self) -> &dyn Any;
+    /// 描画バッファの大きさ（px）
+    fn size(&self) -> (u32, u32);
+    /// 描画バッファの大きさを変える（内容は消える）
+    fn resize(&self, width: u32, height: u32);
+    /// 同じ種類の裏のバッファ（draw_image の元にできる）を作る
+    fn create_offscreen(&self) -> Option<Rc<dyn RenderBackend>>;
+
+    fn save(&self);
+    fn restore(&self);
+    fn set_transform(&self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64);
+    fn translate(&self, x: f64, y: f64);
+    fn rotate(&self, angle: f64);
+    fn scale(&self, x: f64, y: f64);
+
+    fn set_global_alpha(&self, alpha: f64);
+    fn set_global_composite_operation(&self, operation: &str);
+    fn set_fill_style_str(&self, color: &str);
+    fn set_stroke_style_str(&self, color: &str);
+    /// (x0, y0) から (x1, y1) への線形グラデーションを塗りの色にする（stops: 位置 0.0〜1.0 と色）
+    fn set_fill_linear_gradient(&self, x0: f64, y0: f64, x1: f64, y1: f64, stops: &[(f64, String)]);
+    fn set_line_width(&self, width: f64);
+    fn set_shadow_blur(&self, blur: f64);
+    fn set_shadow_color(&self, color: &str);
+    fn set_shadow_offset_x(&self, offset: f64);
+    fn set_shadow_offset_y(&self, offset: f64);
+
+    fn begin_path(&self);
+    fn close_path(&self);
+    fn move_to(&self, x: f64, y: f64);
+    fn line_to(&self, x: f64, y: f64);
+    fn bezier_curve_to(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64);
+    fn arc(&self, x: f64, y: f64, radius: f64, start_angle: f64, end_angle: f64);
+    fn round_rect(&self, x: f64, y: f64, width: f64, height: f64, radius: f64);
+    fn fill(&self);
+    fn stroke(&self);
+    fn fill_rect(&self, x: f64, y: f64, width: f64, height: f64);
+    fn clear_rect(&self, x: f64, y: f64, width: f64, height: f64);
+
+    fn set_font(&self, font: &str);
+    fn set_text_align(&self, align: &str);
+    fn set_text_baseline(&self, baseline: &str);
+    fn fill_text(&self, text: &str, x: f64, y: f64);
+    fn fill_text_with_max_width(&self, text: &str, x: f64, y: f64, max_width: f64);
+    /// 今のフォントで描いたときの幅
+    fn measure_text(&self, text: &str) -> f64;
+
+    /// source の (sx, sy, sw, sh) の範囲を (dx, dy, dw, dh) に描く
+    /// source は create_offscreen で作った同じ種類のバックエンド
+    #[allow(clippy::too_many_arguments)]
+    fn draw_image(
+        &self,
+        source: &dyn RenderBackend,
+        sx: f64,
+        sy: f64,
+        sw: f64,
+        sh: f64,
+        dx: f64,
+        dy: f64,
+        dw: f64,
+        dh: f64,
+    );
+}
+
+/// ブラウザの canvas に描く
+pub struct CanvasBackend {
+    canvas: HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+}
+impl CanvasBackend {
+    pub fn new(canvas: HtmlCanvasElement, context: CanvasRenderingContext2d) -> Self {
+        CanvasBackend { canvas, context }
+    }
+}
+impl RenderBackend for CanvasBackend {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn size(&self) -> (u32, u32) {
+        (self.canvas.width(), self.canvas.height())
+    }
+    fn resize(&self, width: u32, height: u32) {
+        self.canvas.set_width(width);
+        self.canvas.set_height(height);
+    }
+    fn create_offscreen(&self) -> Option<Rc<dyn RenderBackend>> {
+        let canvas = self
+            .canvas
+            .owner_document()?
+            .create_element("canvas")
+            .ok()?
+            .dyn_into::<HtmlCanvasElement>()
+            .ok()?;
+        let context = canvas
+            .get_context("2d")
+            .ok()??
+            .dyn_into::<CanvasRenderingContext2d>()
+            .ok()?;
+        Some(Rc::new(CanvasBackend { canvas, context }))
+    }
+
+    fn save(&self) {
+        self.context.save();
+    }
+    fn restore(&self) {
+        self.context.restore();
+    }
+    fn set_transform(&self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) {
+        let _ = self.context.set_transform(a, b, c, d, e, f);
+    }
+    fn translate(&self, x: f64, y: f64) {
+        let _ = self.context.translate(x, y);
+    }
+    fn rotate(&self, angle: f64) {
+        let _ = self.context.rotate(angle);
+    }
+    fn scale(&self, x: f64, y: f64) {
+        let _ = self.context.scale(x, y);
+    }
+
+    fn set_global_alpha(&self, alpha: f64) {
+        self.context.set_global_alpha(alpha);
+    }
+    fn set_global_composite_operation(&self, operation: &str) {
+        let _ = self.context.set_global_composite_operation(operation);
+    }
+    fn set_fill_style_str(&self, color: &str) {
+        self.context.set_fill_style_str(color);
+    }
+    fn set_stroke_style_str(&self, color: &str) {
+        self.context.set_stroke_style_str(color);
+    }
+    fn set_fill_linear_gradient(&self, x0: f64, y0: f64, x1: f64, y1: f64, stops: &[(f64, String)]) {
+        let gradient = self.context.create_linear_gradient(x0, y0, x1, y1);
+        for (offset, color) in stops {
+            let _ = gradient.add_color_stop(*offset as f32, color);
+        }
+        self.context.set_fill_style_canvas_gradient(&gradient);
+    }
+    fn set_line_width(&self, width: f64) {
+        self.context.set_line_width(width);
+    }
+    fn set_shadow_blur(&self, blur: f64) {
+        self.context.set_shadow_blur(blur);
+    }
+    fn set_shadow_color(&self, color: &str) {
+        self.context.set_shadow_color(color);
+    }
+    fn set_shadow_offset_x(&self, offset: f64) {
+        self.context.set_shadow_offset_x(offset);
+    }
+    fn set_shadow_offset_y(&self, offset: f64) {
+        self.context.set_shadow_offset_y(offset);
+    }
+
+    fn begin_path(&self) {
+        self.context.begin_path();
+    }
+    fn close_path(&self) {
+        self.context.close_path();
+    }
+    fn move_to(&self, x: f64, y: f64) {
+        self.context.move_to(x, y);
+    }
+    fn line_to(&self, x: f64, y: f64) {
+        self.context.line_to(x, y);
+    }
+    fn bezier_curve_to(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+        self.context.bezier_curve_to(cp1x, cp1y, cp2x, cp2y, x, y);
+    }
+    fn arc(&self, x: f64, y: f64, radius: f64, start_angle: f64, end_angle: f64) {
+        let _ = self.context.arc(x, y, radius, start_angle, end_angle);
+    }
+    fn round_rect(&self, x: f64, y: f64, width: f64, height: f64, radius: f64) {
+        let _ = self
+            .context
+            .round_rect_with_f64(x, y, width, height, radius);
+    }
+    fn fill(&self) {
+        self.context.fill();
+    }
+    fn stroke(&self) {
+        self.context.stroke();
+    }
+    fn fill_rect(&self, x: f64, y: f64, width: f64, height: f64) {
+        self.context.fill_rect(x, y, width, height);
+    }
+    fn clear_rect(&self, x: f64, y: f64, width: f64, height: f64) {
+        self.context.clear_rect(x, y, width, height);
+    }
+
+    fn set_font(&self, font: &str) {
+        self.context.set_font(font);
+    }
+    fn set_text_align(&self, align: &str) {
+        self.context.set_text_align(align);
+    }
+    fn set_text_baseline(&self, baseline: &str) {
+        self.context.set_text_baseline(baseline);
+    }
+    fn fill_text(&self, text: &str, x: f64, y: f64) {
+        let _ = self.context.fill_text(text, x, y);
+    }
+    fn fill_text_with_max_width(&self, text: &str, x: f64, y: f64, max_width: f64) {
+        let _ = self
+            .context
+            .fill_text_with_max_width(text, x, y, max_width);
+    }
+    fn measure_text(&self, text: &str) -> f64 {
+        self.context
+            .measure_text(text)
+            .map(|metrics| metrics.width())
+            .unwrap_or(0.0)
+    }
+
+    fn draw_image(
+        &self,
+        source: &dyn RenderBackend,
+        sx: f64,
+        sy: f64,
+        sw: f64,
+        sh: f64,
+        dx: f64,
+        dy: f64,
+        dw: f64,
+        dh: f64,
+    ) {
+        if let Some(source) = source.as_any().downcast_ref::<CanvasBackend>() {
+            let _ = self
+                .context
+                .draw_image_with_html_canvas_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                    &source.canvas,
+                    sx,
+                    sy,
+                    sw,
+                    sh,
+                    dx,
+                    dy,
+                    dw,
+                    dh,
+                );
+        }
+    }
+}
+
+/// 描画命令を文字列として記録する（ネイティブのテスト用）
+/// 例: `fill_text("1/5", 225, 58)`。文字の幅は「文字数 × フォントの px × 0.5」とみなす
+#[cfg(test)]
+pub struct RecordingBackend {
+    calls: std::cell::RefCell<Vec<String>>,
+    font_px: std::cell::Cell<f64>,
+    size: std::cell::Cell<(u32, u32)>,
+}
+#[cfg(test)]
+impl RecordingBackend {
+    pub fn new(width: u32, height: u32) -> Self {
+        RecordingBackend {
+            calls: std::cell::RefCell::new(Vec::new()),
+            font_px: std::cell::Cell::new(10.0),
+            size: std::cell::Cell::new((width, height)),
+        }
+    }
+    /// これまでに記録した描画命令
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.borrow().clone()
+    }
+    /// 記録した描画命令のうち、名前が op のもの
+    pub fn calls_named(&self, op: &str) -> Vec<String> {
+        let prefix = format!("{}(", op);
+        self.calls
+            .borrow()
+            .iter()
+            .filter(|call| call.starts_with(&prefix))
+            .cloned()
+            .collect()
+    }
+    fn record(&self, op: &str, args: std::fmt::Arguments) {
+        self.calls.borrow_mut().push(format!("{}({})", op, args));
+    }
+}
+#[cfg(test)]
+impl RenderBackend for RecordingBackend {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn size(&self) -> (u32, u32) {
+        self.size.get()
+    }
+    fn resize(&self, width: u32, height: u32) {
+        self.size.set((width, height));
+        self.record("resize", format_args!("{}, {}", width, height));
+    }
+    fn create_offscreen(&self) -> Option<Rc<dyn RenderBackend>> {
+        Some(Rc::new(RecordingBackend::new(0, 0)))
+    }
+
+    fn save(&self) {
+        self.record("save", format_args!(""));
+    }
+    fn restore(&self) {
+        self.record("restore", format_args!(""));
+    }
+    fn set_transform(&self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) {
+        self.record(
+            "set_transform",
+            format_args!("{}, {}, {}, {}, {}, {}", a, b, c, d, e, f),
+        );
+    }
+    fn translate(&self, x: f64, y: f64) {
+        self.record("translate", format_args!("{}, {}", x, y));
+    }
+    fn rotate(&self, angle: f64) {
+        self.record("rotate", format_args!("{}", angle));
+    }
+    fn scale(&self, x: f64, y: f64) {
+        self.record("scale", format_args!("{}, {}", x, y));
+    }
+
+    fn set_global_alpha(&self, alpha: f64) {
+        self.record("set_global_alpha", format_args!("{}", alpha));
+    }
+    fn set_global_composite_operation(&self, operation: &str) {
+        self.record("set_global_composite_operation", format_args!("{:?}", operation));
+    }
+    fn set_fill_style_str(&self, color: &str) {
+        self.record("set_fill_style", format_args!("{:?}", color));
+    }
+    fn set_stroke_style_str(&self, color: &str) {
+        self.record("set_stroke_style", format_args!("{:?}", color));
+    }
+    fn set_fill_linear_gradient(&self, x0: f64, y0: f64, x1: f64, y1: f64, stops: &[(f64, String)]) {
+        self.record(
+            "set_fill_linear_gradient",
+            format_args!("{}, {}, {}, {}, {:?}", x0, y0, x1, y1, stops),
+        );
+    }
+    fn set_line_width(&self, width: f64) {
+        self.record("set_line_width", format_args!("{}", width));
+    }
+    fn set_shadow_blur(&self, blur: f64) {
+        self.record("set_shadow_blur", format_args!("{}", blur));
+    }
+    fn set_shadow_color(&self, color: &str) {
+        self.record("set_shadow_color", format_args!("{:?}", color));
+    }
+    fn set_shadow_offset_x(&self, offset: f64) {
+        self.record("set_shadow_offset_x", format_args!("{}", offset));
+    }
+    fn set_shadow_offset_y(&self, offset: f64) {
+        self.record("set_shadow_offset_y", format_args!("{}", offset));
+    }
+
+    fn begin_path(&self) {
+        self.record("begin_path", format_args!(""));
+    }
+    fn close_path(&self) {
+        self.record("close_path", format_args!(""));
+    }
+    fn move_to(&self, x: f64, y: f64) {
+        self.record("move_to", format_args!("{}, {}", x, y));
+    }
+    fn line_to(&self, x: f64, y: f64) {
+        self.record("line_to", format_args!("{}, {}", x, y));
+    }
+    fn bezier_curve_to(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+        self.record(
+            "bezier_curve_to",
+            format_args!("{}, {}, {}, {}, {}, {}", cp1x, cp1y, cp2x, cp2y, x, y),
+        );
+    }
+    fn arc(&self, x: f64, y: f64, radius: f64, start_angle: f64, end_angle: f64) {
+        self.record(
+            "arc",
+            format_args!("{}, {}, {}, {}, {}", x, y, radius, start_angle, end_angle),
+        );
+    }
+    fn round_rect(&self, x: f64, y: f64, width: f64, height: f64, radius: f64) {
+        self.record(
+            "round_rect",
+            format_args!("{}, {}, {}, {}, {}", x, y, width, height, radius),
+        );
+    }
+    fn fill(&self) {
+        self.record("fill", format_args!(""));
+    }
+    fn stroke(&self) {
+        self.record("stroke", format_args!(""));
+    }
+    fn fill_rect(&self, x: f64, y: f64, width: f64, height: f64) {
+        self.record("fill_rect", format_args!("{}, {}, {}, {}", x, y, width, height));
+    }
+    fn clear_rect(&self, x: f64, y: f64, width: f64, height: f64) {
+        self.record("clear_rect", format_args!("{}, {}, {}, {}", x, y, width, height));
+    }
+
+    fn set_font(&self, font: &str) {
+        if let Some(px) = font
+            .split_whitespace()
+            .find_map(|part| part.strip_suffix("px")?.parse().ok())
+        {
+            self.font_px.set(px);
+        }
+        self.record("set_font", format_args!("{:?}", font));
+    }
+    fn set_text_align(&self, align: &str) {
+        self.record("set_text_align", format_args!("{:?}", align));
+    }
+    fn set_text_baseline(&self, baseline: &str) {
+        self.record("set_text_baseline", format_args!("{:?}", baseline));
+    }
+    fn fill_text(&self, text: &str, x: f64, y: f64) {
+        self.record("fill_text", format_args!("{:?}, {}, {}", text, x, y));
+    }
+    fn fill_text_with_max_width(&self, text: &str, x: f64, y: f64, max_width: f64) {
+        self.record(
+            "fill_text",
+            format_args!("{:?}, {}, {}, max {}", text, x, y, max_width),
+        );
+    }
+    fn measure_text(&self, text: &str) -> f64 {
+        text.chars().count() as f64 * self.font_px.get() * 0.5
+    }
+
+    fn draw_image(
+        &self,
+        _source: &dyn RenderBackend,
+        sx: f64,
+        sy: f64,
+        sw: f64,
+        sh: f64,
+        dx: f64,
+        dy: f64,
+        dw: f64,
+        dh: f64,
+    ) {
+        self.record(
+            "draw_image",
+            format_args!(
+                "{}, {}, {}, {}, {}, {}, {}, {}",
+                sx, sy, sw, sh, dx, dy, dw, dh
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_backend_logs_calls_in_order() {
+        let backend = RecordingBackend::new(450, 600);
+        backend.set_font("18px MyFont");
+        backend.fill_text("1/5", 225.0, 58.0);
+        assert_eq!(
+            backend.calls(),
+            ["set_font(\"18px MyFont\")", "fill_text(\"1/5\", 225, 58)"]
+        );
+        assert_eq!(backend.measure_text("abcd"), 36.0);
+        assert_eq!(backend.calls_named("fill_text").len(), 1);
+    }
+}
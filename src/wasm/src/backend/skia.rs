@@ -0,0 +1,599 @@
+//! CPU で描く `RenderBackend`（tiny-skia）
+//!
+//! ネイティブのテストで描画結果を PNG にして、コミットしてある画像（ゴールデン）と比べる。
+//! 文字はインストールされているフォントで結果が変わらないように、文字の位置と幅の帯として描く。
+//! 影（shadow）は描かない。
+
+use super::RenderBackend;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+use std::rc::Rc;
+use tiny_skia::{
+    BlendMode, Color, FillRule, FilterQuality, GradientStop, LinearGradient, Paint, PathBuilder,
+    Pattern, Pixmap, Point, Rect, Shader, SpreadMode, Stroke, Transform,
+};
+
+/// 塗りの指定
+#[derive(Clone)]
+enum Fill {
+    Solid(Color),
+    Linear {
+        start: (f64, f64),
+        end: (f64, f64),
+        stops: Vec<(f64, Color)>,
+    },
+}
+
+/// save/restore で保存する描画の状態
+#[derive(Clone)]
+struct DrawState {
+    transform: Transform,
+    fill: Fill,
+    stroke: Color,
+    line_width: f64,
+    global_alpha: f64,
+    blend_mode: BlendMode,
+    font_px: f64,
+    text_align: String,
+    text_baseline: String,
+}
+impl Default for DrawState {
+    fn default() -> Self {
+        DrawState {
+            transform: Transform::identity(),
+            fill: Fill::Solid(Color::BLACK),
+            stroke: Color::BLACK,
+            line_width: 1.0,
+            global_alpha: 1.0,
+            blend_mode: BlendMode::SourceOver,
+            font_px: 10.0,
+            text_align: "start".to_string(),
+            text_baseline: "alphabetic".to_string(),
+        }
+    }
+}
+
+pub struct SkiaBackend {
+    pixmap: RefCell<Pixmap>,
+    state: RefCell<DrawState>,
+    stack: RefCell<Vec<DrawState>>,
+    path: RefCell<PathBuilder>,
+    subpath_start: Cell<Option<(f32, f32)>>,
+}
+impl SkiaBackend {
+    pub fn new(width: u32, height: u32) -> Self {
+        SkiaBackend {
+            pixmap: RefCell::new(Pixmap::new(width.max(1), height.max(1)).expect("pixmap size")),
+            state: RefCell::new(DrawState::default()),
+            stack: RefCell::new(Vec::new()),
+            path: RefCell::new(PathBuilder::new()),
+            subpath_start: Cell::new(None),
+        }
+    }
+    /// 背景を塗りつぶす（CSS の背景の代わり）
+    pub fn fill_background(&self, color: &str) {
+        self.pixmap.borrow_mut().fill(parse_color(color));
+    }
+    pub fn encode_png(&self) -> Vec<u8> {
+        self.pixmap.borrow().encode_png().expect("encode png")
+    }
+
+    fn paint(&self, shader: Shader<'static>) -> Paint<'static> {
+        let state = self.state.borrow();
+        let mut shader = shader;
+        shader.apply_opacity(state.global_alpha as f32);
+        Paint {
+            shader,
+            blend_mode: state.blend_mode,
+            anti_alias: true,
+            ..Paint::default()
+        }
+    }
+    fn fill_shader(&self) -> Shader<'static> {
+        match &self.state.borrow().fill {
+            Fill::Solid(color) => Shader::SolidColor(*color),
+            Fill::Linear { start, end, stops } => LinearGradient::new(
+                Point::from_xy(start.0 as f32, start.1 as f32),
+                Point::from_xy(end.0 as f32, end.1 as f32),
+                stops
+                    .iter()
+                    .map(|(offset, color)| GradientStop::new(*offset as f32, *color))
+                    .collect(),
+                SpreadMode::Pad,
+                Transform::identity(),
+            )
+            .unwrap_or(Shader::SolidColor(Color::TRANSPARENT)),
+        }
+    }
+    fn point_to(&self, x: f64, y: f64) {
+        let mut path = self.path.borrow_mut();
+        if path.last_point().is_some() {
+            path.line_to(x as f32, y as f32);
+        } else {
+            path.move_to(x as f32, y as f32);
+            self.subpath_start.set(Some((x as f32, y as f32)));
+        }
+    }
+    fn fill_local_rect(&self, x: f64, y: f64, width: f64, height: f64, paint: &Paint) {
+        let transform = self.state.borrow().transform;
+        if let Some(rect) = Rect::from_xywh(x as f32, y as f32, width as f32, height as f32) {
+            self.pixmap
+                .borrow_mut()
+                .fill_rect(rect, paint, transform, None);
+        }
+    }
+    /// 文字の帯の左端・上端・高さ
+    fn text_block(&self, text: &str, x: f64, y: f64, max_width: Option<f64>) -> (f64, f64, f64, f64) {
+        let state = self.state.borrow();
+        let em = state.font_px;
+        let width = max_width.map_or(text_width(text, em), |max| text_width(text, em).min(max));
+        let left = match state.text_align.as_str() {
+            "center" => x - width / 2.0,
+            "right" | "end" => x - width,
+            _ => x,
+        };
+        let top = match state.text_baseline.as_str() {
+            "top" | "hanging" => y + em * 0.1,
+            "middle" => y - em * 0.26,
+            "bottom" | "ideographic" => y - em * 0.72,
+            _ => y - em * 0.62,
+        };
+        (left, top, width, em * 0.52)
+    }
+    fn draw_text_block(&self, text: &str, x: f64, y: f64, max_width: Option<f64>) {
+        let (left, top, width, height) = self.text_block(text, x, y, max_width);
+        let paint = self.paint(self.fill_shader());
+        self.fill_local_rect(left, top, width, height, &paint);
+    }
+}
+
+/// 文字の幅（半角は 0.55em、全角は 1em とみなす）
+fn text_width(text: &str, em: f64) -> f64 {
+    text.chars()
+        .map(|c| if c.is_ascii() { 0.55 } else { 1.0 })
+        .sum::<f64>()
+        * em
+}
+
+/// CSS の色（#rrggbb・#rrggbbaa・rgb()・rgba()・white・black）
+fn parse_color(text: &str) -> Color {
+    let text = text.trim();
+    match text {
+        "white" => return Color::WHITE,
+        "black" => return Color::BLACK,
+        _ => {}
+    }
+    if let Some(hex) = text.strip_prefix('#') {
+        let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2).unwrap_or("ff"), 16).unwrap_or(255);
+        return Color::from_rgba8(byte(0), byte(2), byte(4), if hex.len() >= 8 { byte(6) } else { 255 });
+    }
+    if let Some(args) = text
+        .strip_prefix("rgba(")
+        .or_else(|| text.strip_prefix("rgb("))
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let values: Vec<f64> = args
+            .split(',')
+            .filter_map(|v| v.trim().parse().ok())
+            .collect();
+        if let [r, g, b, rest @ ..] = values.as_slice() {
+            let alpha = rest.first().copied().unwrap_or(1.0);
+            return Color::from_rgba8(*r as u8, *g as u8, *b as u8, (alpha.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+    }
+    Color::BLACK
+}
+
+impl RenderBackend for SkiaBackend {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn size(&self) -> (u32, u32) {
+        let pixmap = self.pixmap.borrow();
+        (pixmap.width(), pixmap.height())
+    }
+    fn resize(&self, width: u32, height: u32) {
+        *self.pixmap.borrow_mut() = Pixmap::new(width.max(1), height.max(1)).expect("pixmap size");
+    }
+    fn create_offscreen(&self) -> Option<Rc<dyn RenderBackend>> {
+        Some(Rc::new(SkiaBackend::new(1, 1)))
+    }
+
+    fn save(&self) {
+        let state = self.state.borrow().clone();
+        self.stack.borrow_mut().push(state);
+    }
+    fn restore(&self) {
+        if let Some(state) = self.stack.borrow_mut().pop() {
+            *self.state.borrow_mut() = state;
+        }
+    }
+    fn set_transform(&self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) {
+        self.state.borrow_mut().transform =
+            Transform::from_row(a as f32, b as f32, c as f32, d as f32, e as f32, f as f32);
+    }
+    fn translate(&self, x: f64, y: f64) {
+        let mut state = self.state.borrow_mut();
+        state.transform = state.transform.pre_translate(x as f32, y as f32);
+    }
+    fn rotate(&self, angle: f64) {
+        let mut state = self.state.borrow_mut();
+        state.transform = state.transform.pre_rotate(angle.to_degrees() as f32);
+    }
+    fn scale(&self, x: f64, y: f64) {
+        let mut state = self.state.borrow_mut();
+        state.transform = state.transform.pre_scale(x as f32, y as f32);
+    }
+
+    fn set_global_alpha(&self, alpha: f64) {
+        self.state.borrow_mut().global_alpha = alpha.clamp(0.0, 1.0);
+    }
+    fn set_global_composite_operation(&self, operation: &str) {
+        self.state.borrow_mut().blend_mode = match operation {
+            "source-atop" => BlendMode::SourceAtop,
+            "destination-out" => BlendMode::DestinationOut,
+            "copy" => BlendMode::Source,
+            _ => BlendMode::SourceOver,
+        };
+    }
+    fn set_fill_style_str(&self, color: &str) {
+        self.state.borrow_mut().fill = Fill::Solid(parse_color(color));
+    }
+    fn set_stroke_style_str(&self, color: &str) {
+        self.state.borrow_mut().stroke = parse_color(color);
+    }
+    fn set_fill_linear_gradient(&self, x0: f64, y0: f64, x1: f64, y1: f64, stops: &[(f64, String)]) {
+        self.state.borrow_mut().fill = Fill::Linear {
+            start: (x0, y0),
+            end: (x1, y1),
+            stops: stops
+                .iter()
+                .map(|(offset, color)| (*offset, parse_color(color)))
+                .collect(),
+        };
+    }
+    fn set_line_width(&self, width: f64) {
+        self.state.borrow_mut().line_width = width;
+    }
+    fn set_shadow_blur(&self, _blur: f64) {}
+    fn set_shadow_color(&self, _color: &str) {}
+    fn set_shadow_offset_x(&self, _offset: f64) {}
+    fn set_shadow_offset_y(&self, _offset: f64) {}
+
+    fn begin_path(&self) {
+        *self.path.borrow_mut() = PathBuilder::new();
+        self.subpath_start.set(None);
+    }
+    fn close_path(&self) {
+        self.path.borrow_mut().close();
+        // 閉じたあとの現在位置はサブパスの始点
+        if let Some((x, y)) = self.subpath_start.get() {
+            self.path.borrow_mut().move_to(x, y);
+        }
+    }
+    fn move_to(&self, x: f64, y: f64) {
+        self.path.borrow_mut().move_to(x as f32, y as f32);
+        self.subpath_start.set(Some((x as f32, y as f32)));
+    }
+    fn line_to(&self, x: f64, y: f64) {
+        self.point_to(x, y);
+    }
+    fn bezier_curve_to(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+        let mut path = self.path.borrow_mut();
+        if path.last_point().is_none() {
+            path.move_to(cp1x as f32, cp1y as f32);
+        }
+        path.cubic_to(cp1x as f32, cp1y as f32, cp2x as f32, cp2y as f32, x as f32, y as f32);
+    }
+    fn arc(&self, x: f64, y: f64, radius: f64, start_angle: f64, end_angle: f64) {
+        let sweep = end_angle - start_angle;
+        let sweep = if sweep >= TAU { TAU } else { sweep.rem_euclid(TAU) };
+        let segments = ((sweep / (PI / 32.0)).ceil() as usize).max(1);
+        for i in 0..=segments {
+            let angle = start_angle + sweep * i as f64 / segments as f64;
+            self.point_to(x + radius * angle.cos(), y + radius * angle.sin());
+        }
+    }
+    fn round_rect(&self, x: f64, y: f64, width: f64, height: f64, radius: f64) {
+        let r = radius.min(width.abs() / 2.0).min(height.abs() / 2.0).max(0.0);
+        self.move_to(x + r, y);
+        // 右上・右下・左下・左上の角を時計回りに
+        let corners = [
+            (x + width - r, y + r, -FRAC_PI_2),
+            (x + width - r, y + height - r, 0.0),
+            (x + r, y + height - r, FRAC_PI_2),
+            (x + r, y + r, PI),
+        ];
+        for (cx, cy, start) in corners {
+            self.arc(cx, cy, r, start, start + FRAC_PI_2);
+        }
+        self.close_path();
+    }
+    fn fill(&self) {
+        let Some(path) = self.path.borrow().clone().finish() else {
+            return;
+        };
+        let paint = self.paint(self.fill_shader());
+        let transform = self.state.borrow().transform;
+        self.pixmap
+            .borrow_mut()
+            .fill_path(&path, &paint, FillRule::Winding, transform, None);
+    }
+    fn stroke(&self) {
+        let Some(path) = self.path.borrow().clone().finish() else {
+            return;
+        };
+        let (color, width, transform) = {
+            let state = self.state.borrow();
+            (state.stroke, state.line_width, state.transform)
+        };
+        let paint = self.paint(Shader::SolidColor(color));
+        let stroke = Stroke {
+            width: width as f32,
+            ..Stroke::default()
+        };
+        self.pixmap
+            .borrow_mut()
+            .stroke_path(&path, &paint, &stroke, transform, None);
+    }
+    fn fill_rect(&self, x: f64, y: f64, width: f64, height: f64) {
+        let paint = self.paint(self.fill_shader());
+        self.fill_local_rect(x, y, width, height, &paint);
+    }
+    fn clear_rect(&self, x: f64, y: f64, width: f64, height: f64) {
+        let paint = Paint {
+            blend_mode: BlendMode::Clear,
+            ..Paint::default()
+        };
+        self.fill_local_rect(x, y, width, height, &paint);
+    }
+
+    fn set_font(&self, font: &str) {
+        if let Some(px) = font
+            .split_whitespace()
+            .find_map(|part| part.strip_suffix("px")?.parse().ok())
+        {
+            self.state.borrow_mut().font_px = px;
+        }
+    }
+    fn set_text_align(&self, align: &str) {
+        self.state.borrow_mut().text_align = align.to_string();
+    }
+    fn set_text_baseline(&self, baseline: &str) {
+        self.state.borrow_mut().text_baseline = baseline.to_string();
+    }
+    fn fill_text(&self, text: &str, x: f64, y: f64) {
+        self.draw_text_block(text, x, y, None);
+    }
+    fn fill_text_with_max_width(&self, text: &str, x: f64, y: f64, max_width: f64) {
+        self.draw_text_block(text, x, y, Some(max_width));
+    }
+    fn measure_text(&self, text: &str) -> f64 {
+        text_width(text, self.state.borrow().font_px)
+    }
+
+    fn draw_image(
+        &self,
+        source: &dyn RenderBackend,
+        sx: f64,
+        sy: f64,
+        sw: f64,
+        sh: f64,
+        dx: f64,
+        dy: f64,
+        dw: f64,
+        dh: f64,
+    ) {
+        let Some(source) = source.as_any().downcast_ref::<SkiaBackend>() else {
+            return;
+        };
+        if sw <= 0.0 || sh <= 0.0 {
+            return;
+        }
+        let source = source.pixmap.borrow().clone();
+        // 元の (sx, sy, sw, sh) を描き先の (dx, dy, dw, dh) に合わせる
+        let pattern_transform = Transform::from_translate(dx as f32, dy as f32)
+            .pre_scale((dw / sw) as f32, (dh / sh) as f32)
+            .pre_translate(-sx as f32, -sy as f32);
+        let (global_alpha, blend_mode, transform) = {
+            let state = self.state.borrow();
+            (state.global_alpha, state.blend_mode, state.transform)
+        };
+        let paint = Paint {
+            shader: Pattern::new(
+                source.as_ref(),
+                SpreadMode::Pad,
+                FilterQuality::Bilinear,
+                global_alpha as f32,
+                pattern_transform,
+            ),
+            blend_mode,
+            anti_alias: true,
+            ..Paint::default()
+        };
+        if let Some(rect) = Rect::from_xywh(dx as f32, dy as f32, dw as f32, dh as f32) {
+            self.pixmap
+                .borrow_mut()
+                .fill_rect(rect, &paint, transform, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::*;
+    use crate::engine::{CardFace, Point as CardPoint, Renderer};
+    use crate::etymology::Etymology;
+    use crate::theme::{Face, Theme};
+    use std::path::PathBuf;
+
+    /// 違うとみなす1チャンネルの差
+    const CHANNEL_TOLERANCE: u8 = 16;
+    /// 違うピクセルがこの割合までなら同じ画像とみなす（アンチエイリアスの揺れ）
+    const PIXEL_TOLERANCE: f64 = 0.002;
+
+    fn snapshot_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots")
+    }
+
+    /// 描画結果をゴールデン画像と比べる
+    /// `UPDATE_SNAPSHOTS=1 cargo test` でゴールデン画像を書き直す
+    /// 違っていたら target/snapshots に今回の結果を書き出す
+    fn assert_snapshot(name: &str, backend: &SkiaBackend) {
+        let golden_path = snapshot_dir().join(format!("{}.png", name));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::create_dir_all(snapshot_dir()).unwrap();
+            std::fs::write(&golden_path, backend.encode_png()).unwrap();
+            return;
+        }
+        let actual = Pixmap::decode_png(&backend.encode_png()).unwrap();
+        let golden = Pixmap::load_png(&golden_path).unwrap_or_else(|_| {
+            panic!(
+                "{} がありません。UPDATE_SNAPSHOTS=1 cargo test で作成してください",
+                golden_path.display()
+            )
+        });
+        assert_eq!(
+            (actual.width(), actual.height()),
+            (golden.width(), golden.height()),
+            "{}: 大きさが違います",
+            name
+        );
+        let different = actual
+            .data()
+            .chunks(4)
+            .zip(golden.data().chunks(4))
+            .filter(|(a, g)| a.iter().zip(g.iter()).any(|(a, g)| a.abs_diff(*g) > CHANNEL_TOLERANCE))
+            .count();
+        let ratio = different as f64 / (actual.width() * actual.height()) as f64;
+        if ratio > PIXEL_TOLERANCE {
+            let out = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/snapshots");
+            std::fs::create_dir_all(&out).unwrap();
+            let actual_path = out.join(format!("{}.png", name));
+            std::fs::write(&actual_path, backend.encode_png()).unwrap();
+            panic!(
+                "{}: {:.2}% のピクセルがゴールデン画像と違います（今回の結果: {}）",
+                name,
+                ratio * 100.0,
+                actual_path.display()
+            );
+        }
+    }
+
+    fn screen(theme: Theme) -> (Rc<SkiaBackend>, Renderer) {
+        let backend = Rc::new(SkiaBackend::new(SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32));
+        backend.fill_background("#14141e");
+        let renderer = Renderer::new(backend.clone(), theme);
+        (backend, renderer)
+    }
+
+    fn etymology() -> Etymology {
+        Etymology::parse(&[
+            "dūrābilis/ラテン語: 長持ちする,耐久性のある",
+            "endure（耐える）: en- + dūrus",
+        ])
+    }
+
+    fn draw_card(renderer: &Renderer, face: Face, flip_angle: f32) {
+        let text = match face {
+            Face::Front => "ハイキング用の[丈夫{じょうぶ}な]靴{くつ}が必要です",
+            Face::Back => "I need [durable] shoes for hiking",
+        };
+        renderer.fill_round_rect_rotate_with_flip(
+            &CardPoint::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0),
+            0.0,
+            FLASH_CARD_WIDTH,
+            FLASH_CARD_HEIGHT,
+            FLASH_CARD_CORNER_RADIUS,
+            1.0,
+            flip_angle,
+            &CardFace {
+                face,
+                text,
+                etymology: &etymology(),
+                decorations: None,
+                show_ruby: true,
+            },
+        );
+    }
+
+    fn draw_counter(renderer: &Renderer, face: Face) {
+        renderer.celtic_progress_counter(
+            &CardPoint::new(SCREEN_WIDTH / 2.0, PROGRESS_COUNTER_Y),
+            "1/5",
+            &renderer.theme().palette(face).accent,
+        );
+    }
+
+    #[test]
+    fn parses_css_colours() {
+        assert_eq!(parse_color("#008000ff"), Color::from_rgba8(0, 128, 0, 255));
+        assert_eq!(parse_color("#72F285"), Color::from_rgba8(0x72, 0xf2, 0x85, 255));
+        assert_eq!(parse_color("rgba(255, 255, 255, 0.5)"), Color::from_rgba8(255, 255, 255, 128));
+        assert_eq!(parse_color("white"), Color::WHITE);
+    }
+
+    #[test]
+    fn snapshot_card_front() {
+        let (backend, renderer) = screen(Theme::celtic());
+        draw_card(&renderer, Face::Front, 0.0);
+        draw_counter(&renderer, Face::Front);
+        assert_snapshot("card_front", &backend);
+    }
+
+    #[test]
+    fn snapshot_card_back() {
+        let (backend, renderer) = screen(Theme::celtic());
+        draw_card(&renderer, Face::Back, 0.0);
+        draw_counter(&renderer, Face::Back);
+        assert_snapshot("card_back", &backend);
+    }
+
+    #[test]
+    fn snapshot_card_flip_mid_frames() {
+        let (backend, renderer) = screen(Theme::celtic());
+        draw_card(&renderer, Face::Front, 1.0);
+        assert_snapshot("card_flip_front", &backend);
+
+        let (backend, renderer) = screen(Theme::celtic());
+        draw_card(&renderer, Face::Back, 2.2);
+        assert_snapshot("card_flip_back", &backend);
+    }
+
+    #[test]
+    fn snapshot_message_window() {
+        let (backend, renderer) = screen(Theme::celtic());
+        renderer.draw_message_window(
+            &CardPoint::new(SCREEN_WIDTH / 2.0, STATS_MESSAGE_Y),
+            "学習状況\n覚えた 2/5\n残り 3 枚\nもう一度 1 回",
+        );
+        assert_snapshot("message_window", &backend);
+    }
+
+    #[test]
+    fn snapshot_settings_window() {
+        use crate::settings::{Item, Settings};
+        let (backend, renderer) = screen(Theme::celtic());
+        let settings = Settings {
+            audio: true,
+            ..Settings::default()
+        };
+        let rows: Vec<_> = Item::ALL
+            .iter()
+            .map(|item| (item.label(), item.value(&settings), item.control(&settings)))
+            .collect();
+        renderer.draw_settings_window(&CardPoint::new(SCREEN_WIDTH / 2.0, SETTINGS_MESSAGE_Y), &rows, 3);
+        assert_snapshot("settings_window", &backend);
+    }
+
+    #[test]
+    fn snapshot_high_contrast_theme() {
+        let (backend, renderer) = screen(Theme::high_contrast());
+        renderer.clear();
+        draw_card(&renderer, Face::Back, 0.0);
+        draw_counter(&renderer, Face::Back);
+        assert_snapshot("card_back_high_contrast", &backend);
+    }
+}
@@ -2,7 +2,10 @@ use anyhow::{Result, anyhow};
 use std::future::Future;
 use wasm_bindgen::{JsCast, closure::WasmClosure, closure::WasmClosureFnOnce, prelude::Closure};
 
-use web_sys::{CanvasRenderingContext2d, Document, HtmlCanvasElement, HtmlImageElement, Window};
+use web_sys::{
+    CanvasRenderingContext2d, Document, Gamepad, HtmlCanvasElement, HtmlImageElement, Storage,
+    Window,
+};
 
 macro_rules! log {
     ( $( $t:tt )* ) => {
@@ -87,3 +90,76 @@ pub fn now() -> Result<f64> {
         .ok_or_else(|| anyhow!("Performance object not found"))?
         .now())
 }
+
+pub fn local_storage() -> Result<Storage> {
+    window()?
+        .local_storage()
+        .map_err(|err| anyhow!("Error getting localStorage {:#?}", err))?
+        .ok_or_else(|| anyhow!("No localStorage found"))
+}
+
+/// localStorage から設定を読み込む（保存されていなければ None）
+pub fn load_setting(key: &str) -> Result<Option<String>> {
+    local_storage()?
+        .get_item(key)
+        .map_err(|err| anyhow!("Could not read {} from localStorage {:#?}", key, err))
+}
+
+/// localStorage に設定を保存する
+pub fn save_setting(key: &str, value: &str) -> Result<()> {
+    local_storage()?
+        .set_item(key, value)
+        .map_err(|err| anyhow!("Could not write {} to localStorage {:#?}", key, err))
+}
+
+/// localStorage から設定を消す
+pub fn remove_setting(key: &str) -> Result<()> {
+    local_storage()?
+        .remove_item(key)
+        .map_err(|err| anyhow!("Could not remove {} from localStorage {:#?}", key, err))
+}
+
+/// メディアクエリに一致するか（例: `"(prefers-reduced-motion: reduce)"`、調べられなければ false）
+pub fn media_matches(query: &str) -> bool {
+    window()
+        .ok()
+        .and_then(|window| window.match_media(query).ok().flatten())
+        .is_some_and(|list| list.matches())
+}
+
+/// 文を読み上げる（読み上げ中の文があれば止めてから）
+pub fn speak(text: &str, lang: &str) -> Result<()> {
+    let synthesis = window()?
+        .speech_synthesis()
+        .map_err(|err| anyhow!("Could not get speechSynthesis {:#?}", err))?;
+    let utterance = web_sys::SpeechSynthesisUtterance::new_with_text(text)
+        .map_err(|err| anyhow!("Could not create utterance {:#?}", err))?;
+    utterance.set_lang(lang);
+    synthesis.cancel();
+    synthesis.speak(&utterance);
+    Ok(())
+}
+
+/// 接続中のゲームパッド（空きスロットの null は除く）
+pub fn gamepads() -> Result<Vec<Gamepad>> {
+    let gamepads = window()?
+        .navigator()
+        .get_gamepads()
+        .map_err(|err| anyhow!("Could not get gamepads {:#?}", err))?;
+    Ok(gamepads
+        .iter()
+        .filter_map(|gamepad| gamepad.dyn_into::<Gamepad>().ok())
+        .filter(|gamepad| gamepad.connected())
+        .collect())
+}
+
+/// ページの URL のクエリパラメータ（`?tick=30&debug` の `tick` → `Some("30")`、`debug` → `Some("")`）
+pub fn query_param(name: &str) -> Option<String> {
+    let search = window().ok()?.location().search().ok()?;
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
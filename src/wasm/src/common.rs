@@ -1,5 +1,8 @@
 /* <-- CONSTANT VALUE */
-pub const FRAME_SIZE: f64 = 1.0 / 60.0 * 10000.0;
+pub const TICK_RATE: f64 = 60.0; // 1秒あたりの update 回数（URL の ?tick= で変更できる）
+pub const MAX_FRAME_DELTA: f64 = 250.0; // 1フレームで積算する経過時間の上限（ms）
+pub const MAX_UPDATES_PER_FRAME: u32 = 5; // 1フレームで呼ぶ update の上限
+pub const DEBUG_OVERLAY_KEY: &str = "F3"; // FPS/ティックのデバッグ表示の切り替え（URL の ?debug でも表示）
 pub const SCREEN_WIDTH: f32 = 450.0;
 pub const SCREEN_HEIGHT: f32 = 600.0;
 //pub const OPENING_MESSAGE_Y: f32 = 250.0;
@@ -13,57 +16,98 @@ pub const FLASH_CARD_NUMBERS: i32 = 5;
 pub const FLASH_CARD_WIDTH: f32 = 350.0;
 pub const FLASH_CARD_HEIGHT: f32 = 480.0;
 pub const FLASH_CARD_CORNER_RADIUS: f32 = 10.0;
-pub const FLASH_CARD_ROTATE_SPEED: f32 = 0.15;
-pub const FLASH_CARD_REMOVING_POINT_ROTATE: f32 = 0.2;
+pub const FLASH_CARD_FLIP_DURATION: f64 = 500.0; // 裏返すアニメーションの時間（ms）
+pub const STACK_VISIBLE: usize = 3; // 先頭のカードの後ろに見せるカードの枚数
+pub const STACK_OFFSET_Y: f32 = 18.0; // 1枚後ろのカードを上にずらす量（論理px）
+pub const STACK_SCALE_STEP: f32 = 0.04; // 1枚後ろのカードを小さくする割合
+pub const STACK_ADVANCE_DURATION: f64 = 300.0; // 後ろのカードが1枚分手前に出てくる時間（ms）
+pub const FLIP_SLICES: usize = 48; // フリップ中のカードを分ける縦の短冊の数
+pub const FLIP_PERSPECTIVE_DISTANCE: f64 = 900.0; // 視点からカードまでの距離（論理px、小さいほど遠近感が強い）
+pub const FLIP_BUFFER_SCALE: f64 = 2.0; // フリップ用の裏の canvas の解像度（論理pxに対する倍率）
+pub const FLIP_SHADE: f64 = 0.45; // 真横を向いたときの陰影の濃さ
+pub const FLASH_CARD_SWIPE_OUT_DURATION: f64 = 600.0; // スワイプしたカードが飛んでいく時間（ms）
+pub const FLASH_CARD_SWIPE_OUT_ROTATE: f32 = 1.6; // 飛んでいくカードの最終的な傾き（画面外）
 pub const FLASH_CARD_ERASE_POINT_ROTATE: f32 = 1.05;
-pub const SWIPING_JUDEGE_DISTANCE: i32 = 20;
+pub const FLASH_CARD_PIVOT_DISTANCE: f32 = 350.0; // 回転中心からカード中心までの距離
+pub const FLASH_CARD_DRAG_ROTATE: f32 = 0.0025; // ドラッグ量1pxあたりの傾き（SWIPE_COMMIT_DISTANCE で自動回転の閾値に届く）
+pub const FLASH_CARD_DRAG_LIFT: f32 = 0.3; // 縦方向のドラッグ量に対するカードの移動量の比
+pub const FLASH_CARD_KEY_DRAG_SPEED: f32 = 240.0; // 矢印キーを押している間のドラッグの速さ（論理px/秒）
+pub const FLASH_CARD_SPRING_FREQUENCY: f32 = 12.0; // 元の位置に戻るばねの固有角振動数（rad/s）
+pub const SWIPE_JUDGE_DISTANCE: f64 = 20.0; // ドラッグ開始と判定する移動量（論理px・タッチ/マウス共通）
+pub const SWIPE_COMMIT_DISTANCE: f64 = 80.0; // 離したときにスワイプと判定する横方向の移動量（論理px）
+pub const FLICK_VELOCITY: f64 = 0.5; // フリックと判定する横方向の速度（論理px/ms）
+pub const FLICK_VELOCITY_WINDOW: f64 = 100.0; // 速度の計算に使う直近の履歴の長さ（ms）
+pub const TAP_JUDGE_DISTANCE: f64 = 10.0; // タップと判定する移動量の上限（論理px）
+pub const DOUBLE_TAP_INTERVAL: f64 = 300.0; // ダブルタップと判定する間隔の上限（ms）
+pub const DOUBLE_TAP_DISTANCE: f64 = 20.0; // ダブルタップと判定する2回のタップの距離の上限（論理px）
+pub const LONG_PRESS_DURATION: f64 = 500.0; // 長押しと判定するまでの時間（ms）
+pub const LONG_PRESS_TOLERANCE: f64 = 10.0; // 長押し中に許容する指・マウスの移動量（論理px）
+pub const GAMEPAD_DEADZONE: f64 = 0.25; // スティックの遊び（中心からの距離）
+pub const GAMEPAD_STICK_THRESHOLD: f64 = 0.5; // デッドゾーン補正後、方向キーとして扱う傾き
+pub const GAMEPAD_NOTICE_DURATION: f64 = 3000.0; // 接続・切断の通知を表示する時間（ms）
+pub const GAMEPAD_NOTICE_Y: f32 = 560.0;
+pub const SHAKE_ACCELERATION: f64 = 15.0; // シェイクと判定する横方向の加速度（m/s²）
+pub const SHAKE_REVERSALS: usize = 3; // シェイクと判定する向きの切り替わり回数
+pub const SHAKE_WINDOW: f64 = 800.0; // 向きの切り替わりを数える時間（ms）
+pub const SHAKE_COOLDOWN: f64 = 1000.0; // シェイクを判定したあと次を判定しない時間（ms）
+pub const SHAKE_CODE: &str = "DeviceShake"; // シェイクを KeyState に伝えるときの名前
+pub const UNDO_STACK_SIZE: usize = 20; // 取り消せるスワイプの数
+pub const UNDO_BUTTON_X: f32 = 40.0;
+pub const UNDO_BUTTON_Y: f32 = 50.0; // プログレスカウンターと同じ高さ
+pub const UNDO_BUTTON_RADIUS: f32 = 20.0;
 pub const PROGRESS_COUNTER_Y: f32 = 50.0;
+pub const FURIGANA_FONT_SCALE: f64 = 0.5; // 親文字に対するルビの文字サイズ比
+pub const FURIGANA_OFFSET_SCALE: f64 = 1.0; // 親文字のベースラインからルビまでの距離（文字サイズ比）
+pub const KEY_BINDINGS_STORAGE_KEY: &str = "card.key_bindings"; // キー割り当ての保存先（localStorage）
+pub const DECK_STORAGE_KEY: &str = "card.deck"; // エディタで保存したデッキの保存先（localStorage）
+pub const SETTINGS_STORAGE_KEY: &str = "card.settings"; // 利用者の設定（テーマを含む）の保存先（localStorage）
+pub const LEGACY_THEME_STORAGE_KEY: &str = "card.theme"; // 以前のテーマ名だけの保存先（起動時に利用者の設定へ移して消す）
+pub const SESSION_SIZE_MIN: i32 = 1; // 1回のセッションのカードの枚数の下限
+pub const SESSION_SIZE_MAX: i32 = 20; // 1回のセッションのカードの枚数の上限
+pub const SCHEDULER_SOON_GAP: usize = 3; // 「3枚後に回す」設定で後回しにしたカードを入れる位置
+pub const SWIPE_SENSITIVITY_MAX: i32 = 5; // スワイプの感度の段階の数
+pub const SWIPE_SENSITIVITY_DEFAULT: i32 = 3; // 既定の感度（SWIPE_COMMIT_DISTANCE で判定）
+pub const SWIPE_SENSITIVITY_STEP: f64 = 20.0; // 感度1段あたりのスワイプの判定距離の差（論理px）
+pub const SPEECH_LANG: &str = "en-US"; // 裏面（英文）を読み上げる言語
+pub const TEXT_SCALE_MIN: f64 = 0.75; // 文字の大きさの倍率の下限
+pub const TEXT_SCALE_MAX: f64 = 2.0; // 文字の大きさの倍率の上限
+pub const TEXT_SCALE_STEP: f64 = 0.25; // 設定画面で文字の大きさを変える刻み
+pub const DYSLEXIA_FONT_FAMILY: &str = "'OpenDyslexic', 'Lexend', 'Noto Sans JP', sans-serif"; // 読みやすいフォント（ディスレクシア向け）
+pub const STATS_MESSAGE_Y: f32 = 300.0; // 学習状況の中心Y（カードと同じ位置）
+pub const WORD_FAMILY_MESSAGE_Y: f32 = 300.0; // ワードファミリー画面の中心Y（カードと同じ位置）
+pub const SETTINGS_MESSAGE_Y: f32 = 300.0; // 設定画面の中心Y（カードと同じ位置）
+pub const SETTINGS_ROW_TOP: f64 = 64.0; // 設定画面のウィンドウ上端から最初の行までの距離
+pub const SETTINGS_ROW_HEIGHT: f64 = 30.0; // 設定画面の1行の高さ
+pub const SETTINGS_SLIDER_WIDTH: f64 = 110.0; // 設定画面のスライダーの長さ（行の右端に置く）
+pub const SETTINGS_BUTTON_X: f32 = 410.0; // 設定を開くボタン（取り消しボタンと左右対称）
+pub const SETTINGS_BUTTON_Y: f32 = 50.0;
+pub const SETTINGS_BUTTON_RADIUS: f32 = 20.0;
+pub const ETYMOLOGY_TREE_TOP: f64 = -300.0; // 語源ツリー図の最上段のY座標（カードの回転座標系）
+pub const ETYMOLOGY_TREE_WIDTH: f64 = 300.0;
+pub const ETYMOLOGY_TREE_ROW_HEIGHT: f64 = 52.0;
+pub const ETYMOLOGY_NODE_HEIGHT: f64 = 18.0;
 /* CONSTANT VALUE --> */
 
-#[derive(Clone, Copy, Default)]
-pub enum Color {
-    Black,
-    DarkGreen,
-    DeepGreen,
-    MiddleGreen,
-    #[default]
-    Green,
-    LightGreen,
-    MintGreen,
-    PaleGreen,
-    White,
-    DarkBlue,  // 裏面用の濃い青
-    RoyalBlue, // 裏面用のロイヤルブルー
-}
-impl Color {
-    pub fn get(&self) -> String {
-        match self {
-            Color::White => "#ffffff".to_string(),
-            Color::Black => "#000000".to_string(),
-            Color::DarkGreen => "#1b271bff".to_string(),
-            Color::DeepGreen => "#293d29ff".to_string(),
-            Color::Green => "#008000ff".to_string(),
-            Color::LightGreen => "#395c39ff".to_string(),
-            Color::MintGreen => "#72F285".to_string(),
-            Color::DarkBlue => "#1e3a5f".to_string(),
-            Color::RoyalBlue => "#4169e1".to_string(),
-            _ => "#008000ff".to_string(),
-        }
-    }
-}
-
+pub const DECK_THEME: &str = "celtic"; // このデッキ（ITEMS）の既定のテーマ（theme::Theme::NAMES のいずれか）
+// カードの装飾（decoration::builtin の名前）。タグの指定 → デッキの指定 → テーマの既定の順に使う
+pub const DECK_DECORATIONS: Option<(&str, &str)> = None; // このデッキの (表面, 裏面) の装飾
+pub const TAG_DECORATIONS: &[(&str, &str, &str)] = &[
+    // (タグ, 表面, 裏面)。例: ("business", "celtic", "plain")
+];
 pub const ITEM_SIZE: usize = 5;
-pub const ITEMS: [(&str, &str, &[&str]); ITEM_SIZE] = [
+// (表面, 裏面, 語源, タグ)
+pub const ITEMS: [(&str, &str, &[&str], &[&str]); ITEM_SIZE] = [
     (
-        "ハイキング用の[丈夫な]靴が必要です",
+        "ハイキング用の[丈夫{じょうぶ}な]靴{くつ}が必要です",
         "I need [durable] shoes for hiking",
         &[
             "dūrābilis/ラテン語: 長持ちする,耐久性のある",
             "endure（耐える）: en- + dūrus",
         ],
+        &["daily"],
     ),
     (
-        "教職はやりがいのある [職業] です",
+        "教職{きょうしょく}はやりがいのある [職業{しょくぎょう}] です",
         "Teaching is rewarding [profession].",
         &[
             "prōfessiō/ラテン語: 公の宣言,告白,誓約",
@@ -71,32 +115,36 @@ pub const ITEMS: [(&str, &str, &[&str]); ITEM_SIZE] = [
             "fatērī: 認める,告白する,宣言する",
             "profess: 公言する、告白する",
         ],
+        &["business"],
     ),
     (
-        "あなたはこの奨学金の [資格があり] ます",
+        "あなたはこの奨学金{しょうがくきん}の [資格{しかく}があり] ます",
         "You are [eligible] for this shcolarship.",
         &[
             "eligere/ラテン語: e-/ex-外へ + legere 選ぶ",
             "⇒ 選ばれるに値する、選ばれる資格がある",
             "election: 選挙",
         ],
+        &["academic"],
     ),
     (
-        "このホテルでは朝食は [無料] です",
+        "このホテルでは朝食{ちょうしょく}は [無料{むりょう}] です",
         "Breakfast is [complimentary] at this hotel",
         &[
             "complēreラテン語: 満たす,完成させる",
             "褒める、賛辞の = 元来の意味",
             "無料の,サービスの = 派生的意味",
         ],
+        &["daily"],
     ),
     (
-        "会社は売上予測を [修正] しました",
+        "会社{かいしゃ}は売上予測{うりあげよそく}を [修正{しゅうせい}] しました",
         "The company [revised] its salses forecast",
         &[
             "revidēre/ラテン語: 再び見る,見直す",
             "もう一度見る → 見直す → 改訂する",
             "revision: 改訂、復習",
         ],
+        &["business"],
     ),
 ];
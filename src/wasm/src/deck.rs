@@ -0,0 +1,209 @@
+//! デッキ（カードの元になるノートの一覧）と保存形式
+//!
+//! 組み込みのデッキは common.rs の ITEMS。エディタで保存したデッキは localStorage に保存し、
+//! 次のセッションからはそちらを使う。保存形式（1行に1項目、空行でノートを区切る、`#` で始まる行はコメント）:
+//!
+//! ```text
+//! front ハイキング用の[丈夫{じょうぶ}な]靴{くつ}が必要です
+//! back I need [durable] shoes for hiking
+//! etymology dūrābilis/ラテン語: 長持ちする,耐久性のある
+//! etymology endure（耐える）: en- + dūrus
+//! tags daily
+//! ```
+
+use crate::common::*;
+use anyhow::{Result, anyhow, bail};
+
+/// 1枚のカードの元になるノート
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Note {
+    pub front: String,          // 表面（ルビ {よみ} と強調 [ ] を書ける）
+    pub back: String,           // 裏面
+    pub etymology: Vec<String>, // 語源の行（etymology::Etymology::parse の形式）
+    pub tags: Vec<String>,      // タグ（装飾の指定に使う）
+}
+impl Note {
+    /// 問題があれば、編集画面に表示するメッセージ
+    pub fn validate(&self) -> Result<()> {
+        for (name, text) in [("表面", &self.front), ("裏面", &self.back)] {
+            if text.trim().is_empty() {
+                bail!("{}が空です", name);
+            }
+            if !balanced(text, '{', '}') || !balanced(text, '[', ']') {
+                bail!("{}の {{ }} または [ ] の対応が取れていません", name);
+            }
+        }
+        let lines = [&self.front, &self.back].into_iter().chain(self.etymology.iter());
+        if lines.chain(self.tags.iter()).any(|text| text.contains('\n')) {
+            bail!("1つの項目の中では改行できません（語源は1行に1つ）");
+        }
+        if let Some(tag) = self
+            .tags
+            .iter()
+            .find(|tag| tag.is_empty() || tag.contains(|c: char| c.is_whitespace() || c == ','))
+        {
+            bail!("タグ「{}」に空白やカンマは使えません", tag);
+        }
+        Ok(())
+    }
+}
+
+/// 開きと閉じが入れ子にならずに順に対応しているか（ルビ {よみ} と強調 [ ] の書き方）
+fn balanced(text: &str, open: char, close: char) -> bool {
+    let mut inside = false;
+    for c in text.chars() {
+        if c == open {
+            if inside {
+                return false;
+            }
+            inside = true;
+        } else if c == close {
+            if !inside {
+                return false;
+            }
+            inside = false;
+        }
+    }
+    !inside
+}
+
+/// ノートの一覧
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deck {
+    pub notes: Vec<Note>,
+}
+impl Deck {
+    /// common.rs の ITEMS のデッキ
+    pub fn builtin() -> Self {
+        let to_strings = |texts: &[&str]| texts.iter().map(|t| t.to_string()).collect();
+        Deck {
+            notes: ITEMS
+                .iter()
+                .map(|(front, back, etymology, tags)| Note {
+                    front: front.to_string(),
+                    back: back.to_string(),
+                    etymology: to_strings(etymology),
+                    tags: to_strings(tags),
+                })
+                .collect(),
+        }
+    }
+
+    /// 保存形式の文字列を読み込む（壊れた行があればその行番号を返す）
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut notes = Vec::new();
+        let mut note: Option<Note> = None;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            if line.is_empty() {
+                notes.extend(note.take());
+                continue;
+            }
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim().to_string();
+            let current = note.get_or_insert_with(Note::default);
+            match key {
+                "front" => current.front = value,
+                "back" => current.back = value,
+                "etymology" => current.etymology.push(value),
+                "tags" => current.tags = split_tags(&value),
+                _ => return Err(anyhow!("deck line {}: {}", number + 1, line)),
+            }
+        }
+        notes.extend(note);
+        if notes.is_empty() {
+            bail!("deck has no notes");
+        }
+        Ok(Deck { notes })
+    }
+
+    /// 保存されたデッキを読み込み、カードにできるか確かめる（手で書き換えたり古かったりして壊れていればエラー）
+    pub fn load(text: &str) -> Result<Self> {
+        let deck = Deck::parse(text)?;
+        deck.validate()
+            .map_err(|(index, err)| anyhow!("deck note {}: {}", index + 1, err))?;
+        Ok(deck)
+    }
+
+    /// 保存形式の文字列にする
+    pub fn serialize(&self) -> String {
+        self.notes
+            .iter()
+            .map(|note| {
+                let mut lines = vec![format!("front {}", note.front), format!("back {}", note.back)];
+                lines.extend(note.etymology.iter().map(|line| format!("etymology {}", line)));
+                if !note.tags.is_empty() {
+                    lines.push(format!("tags {}", note.tags.join(",")));
+                }
+                lines.join("\n") + "\n"
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// 最初に問題のあるノートの位置とメッセージ（ノートが1つもなければ位置は 0）
+    pub fn validate(&self) -> std::result::Result<(), (usize, anyhow::Error)> {
+        if self.notes.is_empty() {
+            return Err((0, anyhow!("カードが1枚もありません")));
+        }
+        for (i, note) in self.notes.iter().enumerate() {
+            note.validate().map_err(|err| (i, err))?;
+        }
+        Ok(())
+    }
+}
+
+/// カンマまたは空白で区切ったタグ（空のタグは除く）
+pub fn split_tags(text: &str) -> Vec<String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| tag.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_deck_round_trips() {
+        let deck = Deck::builtin();
+        assert_eq!(deck.notes.len(), ITEM_SIZE);
+        assert_eq!(deck.notes[0].tags, ["daily"]);
+        assert!(deck.validate().is_ok());
+        assert_eq!(Deck::parse(&deck.serialize()).unwrap(), deck);
+    }
+
+    #[test]
+    fn broken_lines_and_notes_are_reported() {
+        let err = Deck::parse("# deck\nfront 表\nback back\n\nfrnot typo\n").unwrap_err();
+        assert!(err.to_string().contains("line 5"), "{}", err);
+        assert!(Deck::parse("# only comments\n\n").is_err());
+
+        let deck = Deck::parse("front 靴{くつ}\nback shoes\n\nfront [丈夫{じょうぶ}\nback durable\ntags a b,c\n")
+            .unwrap();
+        assert_eq!(deck.notes[1].tags, ["a", "b", "c"]);
+        let (index, err) = deck.validate().unwrap_err();
+        assert_eq!(index, 1);
+        assert_eq!(err.to_string(), "表面の { } または [ ] の対応が取れていません");
+
+        let note = Note {
+            back: "shoes".to_string(),
+            ..Note::default()
+        };
+        assert_eq!(note.validate().unwrap_err().to_string(), "表面が空です");
+    }
+
+    #[test]
+    fn loading_rejects_notes_that_cannot_become_cards() {
+        let text = "front 靴{くつ}\nback shoes\n\nfront 丈夫{じょうぶ\nback durable\n";
+        assert!(Deck::parse(text).is_ok());
+        let err = Deck::load(text).unwrap_err();
+        assert_eq!(err.to_string(), "deck note 2: 表面の { } または [ ] の対応が取れていません");
+        assert!(Deck::load("back shoes\n").is_err());
+        assert_eq!(Deck::load(&Deck::builtin().serialize()).unwrap(), Deck::builtin());
+    }
+}
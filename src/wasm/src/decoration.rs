@@ -0,0 +1,529 @@
+//! カードの装飾（面の地の上に重ねる模様）
+//!
+//! 装飾は `CardDecoration` を実装した型で、名前（`celtic` など）で選ぶ。
+//! テーマごとに表裏の既定の装飾があり、デッキ（`DECK_DECORATIONS`）や
+//! カードのタグ（`TAG_DECORATIONS`）で差し替えられる。
+
+use crate::backend::RenderBackend;
+use crate::theme::{Face, Palette};
+use std::fmt;
+
+/// 装飾を描く範囲（カードの回転座標系）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CardFrame {
+    pub left: f64,
+    pub top: f64,
+    pub width: f64,
+    pub height: f64,
+    pub center_x: f64, // 中央の模様の中心（テキストの上）
+    pub center_y: f64,
+}
+impl CardFrame {
+    /// 回転中心から見たカードの範囲（カードの中心は回転中心の 350 上）
+    pub fn around_pivot(width: f32, height: f32) -> Self {
+        let left = -(width / 2.0) as f64;
+        let top = -(height / 2.0 + 350.0) as f64;
+        CardFrame {
+            left,
+            top,
+            width: width as f64,
+            height: height as f64,
+            center_x: left + (width / 2.0) as f64,
+            center_y: top + (height / 2.0 - 25.0) as f64 - 80.0,
+        }
+    }
+}
+
+/// カードの装飾の描き方
+pub trait CardDecoration {
+    /// 設定やデッキで指定するときの名前
+    fn name(&self) -> &'static str;
+    /// palette の色で frame の範囲に装飾を描く（呼び出し側で save/restore する）
+    fn draw(&self, context: &dyn RenderBackend, palette: &Palette, frame: &CardFrame);
+}
+
+/// 表面用のケルト文様（二重線の枠・組紐の結び目・四隅のスパイラル）
+pub struct Celtic;
+/// 裏面用の円とトリケトラ（四隅にケルト結び目）
+pub struct Constellation;
+/// 細い枠線だけ
+pub struct Plain;
+
+static CELTIC: Celtic = Celtic;
+static CONSTELLATION: Constellation = Constellation;
+static PLAIN: Plain = Plain;
+
+/// 組み込みの装飾
+pub fn builtin() -> [&'static dyn CardDecoration; 3] {
+    [&CELTIC, &CONSTELLATION, &PLAIN]
+}
+
+pub fn by_name(name: &str) -> Option<&'static dyn CardDecoration> {
+    builtin().into_iter().find(|d| d.name() == name.trim())
+}
+
+/// 表裏それぞれの装飾
+#[derive(Clone, Copy)]
+pub struct Decorations {
+    pub front: &'static dyn CardDecoration,
+    pub back: &'static dyn CardDecoration,
+}
+impl Decorations {
+    pub fn celtic() -> Self {
+        Decorations {
+            front: &CELTIC,
+            back: &CONSTELLATION,
+        }
+    }
+    pub fn plain() -> Self {
+        Decorations {
+            front: &PLAIN,
+            back: &PLAIN,
+        }
+    }
+    /// 表面・裏面の装飾の名前から（どちらかが未知なら None）
+    pub fn by_names(front: &str, back: &str) -> Option<Self> {
+        Some(Decorations {
+            front: by_name(front)?,
+            back: by_name(back)?,
+        })
+    }
+    pub fn for_face(&self, face: Face) -> &'static dyn CardDecoration {
+        match face {
+            Face::Front => self.front,
+            Face::Back => self.back,
+        }
+    }
+    /// カードのタグ → デッキの指定の順に装飾を選ぶ（どちらもなければ None でテーマの既定を使う）
+    /// tag_table: (タグ, 表面, 裏面)、deck: (表面, 裏面)
+    pub fn resolve(
+        tags: &[&str],
+        tag_table: &[(&str, &str, &str)],
+        deck: Option<(&str, &str)>,
+    ) -> Option<Self> {
+        tags.iter()
+            .find_map(|tag| {
+                tag_table
+                    .iter()
+                    .find(|(t, _, _)| t == tag)
+                    .and_then(|(_, front, back)| Decorations::by_names(front, back))
+            })
+            .or_else(|| deck.and_then(|(front, back)| Decorations::by_names(front, back)))
+    }
+}
+impl fmt::Debug for Decorations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Decorations({}/{})", self.front.name(), self.back.name())
+    }
+}
+impl PartialEq for Decorations {
+    fn eq(&self, other: &Self) -> bool {
+        self.front.name() == other.front.name() && self.back.name() == other.back.name()
+    }
+}
+
+impl CardDecoration for Celtic {
+    fn name(&self) -> &'static str {
+        "celtic"
+    }
+    fn draw(&self, context: &dyn RenderBackend, palette: &Palette, frame: &CardFrame) {
+        let CardFrame {
+            left: card_left,
+            top: card_top,
+            width,
+            height,
+            center_x,
+            center_y,
+        } = *frame;
+
+        context.set_fill_style_str(&palette.ink(0.3));
+        // 背景全体にケルト文様の枠線を描画
+        context.set_line_width(1.5);
+        context.set_stroke_style_str(&palette.ink(0.2));
+
+        // カード周囲にケルト風の二重線
+        let border_offset = 10.0;
+        context.begin_path();
+        context.round_rect(
+            card_left + border_offset,
+            card_top + border_offset,
+            width - border_offset * 2.0,
+            height - border_offset * 2.0,
+            8.0,
+        );
+        context.stroke();
+
+        let border_offset2 = 15.0;
+        context.begin_path();
+        context.round_rect(
+            card_left + border_offset2,
+            card_top + border_offset2,
+            width - border_offset2 * 2.0,
+            height - border_offset2 * 2.0,
+            6.0,
+        );
+        context.stroke();
+
+        // 背景に繊細なケルト結び目パターンを散りばめる
+        context.set_line_width(1.0);
+        context.set_stroke_style_str(&palette.ink(0.15));
+
+        // 上部と下部にケルト風の連続模様
+        let positions = [
+            (center_x - 60.0, card_top + 40.0),
+            (center_x, card_top + 40.0),
+            (center_x + 60.0, card_top + 40.0),
+            (center_x - 60.0, card_top + height - 40.0),
+            (center_x, card_top + height - 40.0),
+            (center_x + 60.0, card_top + height - 40.0),
+        ];
+
+        for &(px, py) in positions.iter() {
+            // 小さなケルト結び目
+            context.begin_path();
+            context.arc(px, py, 6.0, 0.0, 2.0 * std::f64::consts::PI);
+            context.stroke();
+
+            // 交差する曲線
+            context.begin_path();
+            context.move_to(px - 8.0, py - 8.0);
+            context.bezier_curve_to(px, py, px, py, px + 8.0, py + 8.0);
+            context.stroke();
+
+            context.begin_path();
+            context.move_to(px - 8.0, py + 8.0);
+            context.bezier_curve_to(px, py, px, py, px + 8.0, py - 8.0);
+            context.stroke();
+        }
+
+        // 中央部分の装飾を強調
+        context.set_line_width(2.5);
+        context.set_stroke_style_str(&palette.ink(0.5));
+
+        // ケルト文様：組紐（インターレース）パターンを描画
+        // 中央の三つ編み結び目
+        let knot_size = 30.0;
+
+        // 中央の円形ノット
+        context.begin_path();
+        context.arc(
+            center_x,
+            center_y,
+            knot_size / 2.0,
+            0.0,
+            2.0 * std::f64::consts::PI,
+        );
+        context.stroke();
+
+        // 組紐パターン：3つの曲線で構成
+        let curve_offset = knot_size * 0.8;
+
+        // 左上から右下への曲線
+        context.begin_path();
+        context.move_to(center_x - curve_offset, center_y - curve_offset);
+        context.bezier_curve_to(
+            center_x - curve_offset / 2.0,
+            center_y,
+            center_x,
+            center_y - curve_offset / 2.0,
+            center_x + curve_offset,
+            center_y + curve_offset,
+        );
+        context.stroke();
+
+        // 右上から左下への曲線
+        context.begin_path();
+        context.move_to(center_x + curve_offset, center_y - curve_offset);
+        context.bezier_curve_to(
+            center_x + curve_offset / 2.0,
+            center_y,
+            center_x,
+            center_y - curve_offset / 2.0,
+            center_x - curve_offset,
+            center_y + curve_offset,
+        );
+        context.stroke();
+
+        // 上下の曲線（縦方向の組紐）
+        context.begin_path();
+        context.move_to(center_x, center_y - curve_offset * 1.2);
+        context.bezier_curve_to(
+            center_x + curve_offset / 3.0,
+            center_y - curve_offset / 2.0,
+            center_x - curve_offset / 3.0,
+            center_y + curve_offset / 2.0,
+            center_x,
+            center_y + curve_offset * 1.2,
+        );
+        context.stroke();
+
+        // 四隅のケルト三つ巴模様
+        let corner_offset = 20.0;
+        let spiral_size = 10.0;
+
+        let corners = [
+            (card_left + corner_offset, card_top + corner_offset),
+            (card_left + width - corner_offset, card_top + corner_offset),
+            (card_left + corner_offset, card_top + height - corner_offset),
+            (
+                card_left + width - corner_offset,
+                card_top + height - corner_offset,
+            ),
+        ];
+
+        for &(cx, cy) in corners.iter() {
+            // スパイラル状のケルト模様
+            context.begin_path();
+            context.move_to(cx, cy);
+            context.bezier_curve_to(
+                cx + spiral_size,
+                cy - spiral_size / 2.0,
+                cx + spiral_size / 2.0,
+                cy + spiral_size,
+                cx,
+                cy,
+            );
+            context.stroke();
+
+            // 小さな円
+            context.begin_path();
+            context.arc(cx, cy, 4.0, 0.0, 2.0 * std::f64::consts::PI);
+            context.fill();
+        }
+
+        // 左右の装飾：ケルトノット
+        let side_offset = 45.0;
+        for offset in [-side_offset, side_offset].iter() {
+            let x = center_x + offset;
+
+            // 小さな組紐結び目
+            context.begin_path();
+            context.move_to(x - 8.0, center_y - 10.0);
+            context.bezier_curve_to(
+                x,
+                center_y - 12.0,
+                x,
+                center_y + 12.0,
+                x - 8.0,
+                center_y + 10.0,
+            );
+            context.stroke();
+
+            context.begin_path();
+            context.move_to(x + 8.0, center_y - 10.0);
+            context.bezier_curve_to(
+                x,
+                center_y - 12.0,
+                x,
+                center_y + 12.0,
+                x + 8.0,
+                center_y + 10.0,
+            );
+            context.stroke();
+        }
+    }
+}
+
+impl CardDecoration for Constellation {
+    fn name(&self) -> &'static str {
+        "constellation"
+    }
+    fn draw(&self, context: &dyn RenderBackend, palette: &Palette, frame: &CardFrame) {
+        let CardFrame {
+            left: card_left,
+            top: card_top,
+            width,
+            height,
+            center_x,
+            center_y,
+        } = *frame;
+
+        // 二重枠（ケルト風）
+        context.set_line_width(2.0);
+        context.set_stroke_style_str(&palette.ink(0.4));
+
+        let border_offset = 10.0;
+        context.begin_path();
+        context.round_rect(
+            card_left + border_offset,
+            card_top + border_offset,
+            width - border_offset * 2.0,
+            height - border_offset * 2.0,
+            8.0,
+        );
+        context.stroke();
+
+        // 中央の円形ケルトノット
+        context.set_line_width(3.0);
+        context.set_stroke_style_str(&palette.ink(0.6));
+
+        // 外側の円
+        context.begin_path();
+        context.arc(center_x, center_y, 70.0, 0.0, 2.0 * std::f64::consts::PI);
+        context.stroke();
+
+        // 内側の円
+        context.begin_path();
+        context.arc(center_x, center_y, 50.0, 0.0, 2.0 * std::f64::consts::PI);
+        context.stroke();
+
+        // トリケトラ（3つの葉のケルトシンボル）を中央に描画
+        triquetra(context, palette, center_x, center_y, 40.0);
+
+        // 四隅にケルト結び目を配置
+        let corner_positions = [
+            (card_left + 35.0, card_top + 35.0),
+            (card_left + width - 35.0, card_top + 35.0),
+            (card_left + 35.0, card_top + height - 35.0),
+            (card_left + width - 35.0, card_top + height - 35.0),
+        ];
+
+        for &(cx, cy) in corner_positions.iter() {
+            celtic_knot_small(context, palette, cx, cy, 15.0);
+        }
+    }
+}
+
+impl CardDecoration for Plain {
+    fn name(&self) -> &'static str {
+        "plain"
+    }
+    fn draw(&self, context: &dyn RenderBackend, palette: &Palette, frame: &CardFrame) {
+        let border_offset = 10.0;
+        context.set_line_width(1.5);
+        context.set_stroke_style_str(&palette.ink(0.5));
+        context.begin_path();
+        context.round_rect(
+            frame.left + border_offset,
+            frame.top + border_offset,
+            frame.width - border_offset * 2.0,
+            frame.height - border_offset * 2.0,
+            8.0,
+        );
+        context.stroke();
+    }
+}
+
+/// トリケトラ（三位一体のケルトシンボル）を描画
+fn triquetra(context: &dyn RenderBackend, palette: &Palette, cx: f64, cy: f64, radius: f64) {
+    context.set_line_width(2.5);
+    context.set_stroke_style_str(&palette.ink(0.8));
+    context.set_fill_style_str(&palette.ink(0.2));
+
+    // 3つの円弧でトリケトラを形成
+    for i in 0..3 {
+        let angle = (i as f64) * 2.0 * std::f64::consts::PI / 3.0 - std::f64::consts::PI / 2.0;
+        let arc_cx = cx + radius * 0.5 * angle.cos();
+        let arc_cy = cy + radius * 0.5 * angle.sin();
+
+        context.begin_path();
+        context.arc(
+            arc_cx,
+            arc_cy,
+            radius * 0.8,
+            angle + std::f64::consts::PI * 0.6,
+            angle + std::f64::consts::PI * 1.4,
+        );
+        context.stroke();
+    }
+
+    // 中央の小さな三角形
+    context.begin_path();
+    for i in 0..3 {
+        let angle = (i as f64) * 2.0 * std::f64::consts::PI / 3.0 - std::f64::consts::PI / 2.0;
+        let x = cx + radius * 0.25 * angle.cos();
+        let y = cy + radius * 0.25 * angle.sin();
+
+        if i == 0 {
+            context.move_to(x, y);
+        } else {
+            context.line_to(x, y);
+        }
+    }
+    context.close_path();
+    context.fill();
+    context.stroke();
+}
+
+/// 小さなケルト結び目を描画（カードと同じ雰囲気のウィンドウの四隅にも使う）
+pub fn celtic_knot_small(
+    context: &dyn RenderBackend,
+    palette: &Palette,
+    cx: f64,
+    cy: f64,
+    size: f64,
+) {
+    context.set_line_width(1.5);
+    context.set_stroke_style_str(&palette.ink(0.5));
+
+    // 4つの円弧で結び目を形成
+    for i in 0..4 {
+        let angle = (i as f64) * std::f64::consts::PI / 2.0;
+        let start_x = cx + size * angle.cos();
+        let start_y = cy + size * angle.sin();
+
+        context.begin_path();
+        context.arc(
+            start_x,
+            start_y,
+            size * 0.6,
+            angle + std::f64::consts::PI,
+            angle + std::f64::consts::PI * 1.5,
+        );
+        context.stroke();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{DECK_DECORATIONS, ITEMS, TAG_DECORATIONS};
+
+    #[test]
+    fn builtin_names_are_unique_and_resolvable() {
+        for decoration in builtin() {
+            assert_eq!(
+                by_name(decoration.name()).map(|d| d.name()),
+                Some(decoration.name())
+            );
+        }
+        assert!(by_name("hexagon").is_none());
+        assert_eq!(
+            Decorations::celtic().for_face(Face::Back).name(),
+            "constellation"
+        );
+    }
+
+    #[test]
+    fn tag_wins_over_deck() {
+        let table = [
+            ("chemistry", "plain", "plain"),
+            ("law", "celtic", "unknown"),
+        ];
+        let deck = Some(("constellation", "celtic"));
+        assert_eq!(
+            Decorations::resolve(&["verb", "chemistry"], &table, deck),
+            Some(Decorations::plain())
+        );
+        // 未知の装飾名を指定したタグは無視してデッキの指定を使う
+        assert_eq!(
+            Decorations::resolve(&["law"], &table, deck),
+            Decorations::by_names("constellation", "celtic")
+        );
+        assert_eq!(Decorations::resolve(&["law"], &table, None), None);
+    }
+
+    #[test]
+    fn tag_table_restyles_only_the_tagged_sample_cards() {
+        // 組み込みのデッキの business のカードだけを差し替える例
+        let table = [("business", "celtic", "plain")];
+        let resolved: Vec<Option<Decorations>> = ITEMS
+            .iter()
+            .map(|item| Decorations::resolve(item.3, &table, DECK_DECORATIONS))
+            .collect();
+        let business = Decorations::by_names("celtic", "plain");
+        assert_eq!(resolved, [None, business, None, None, business]);
+        // 表が空なら（組み込みのデッキの既定）どのカードもテーマの装飾を使う
+        assert!(ITEMS.iter().all(|item| Decorations::resolve(item.3, TAG_DECORATIONS, DECK_DECORATIONS).is_none()));
+    }
+}
@@ -0,0 +1,237 @@
+//! デッキの編集フォーム
+//!
+//! canvas では文字を入力できないので、編集画面ではページに DOM のフォームを重ねる。
+//! フォームの操作は `EditorEvent` としてゲームに渡し、ゲームはフォームに見せる内容を `EditorView` として返す。
+//! engine は内容が変わったときだけ `DomEditor` に反映する（入力中の欄は書き換えない）。
+//! カードのプレビューはゲームが canvas に描く。
+
+use crate::browser;
+use crate::deck::{Note, split_tags};
+use anyhow::{Result, anyhow};
+use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender, unbounded};
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlElement, HtmlInputElement, HtmlTextAreaElement};
+
+/// フォームでの操作
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditorEvent {
+    Change(Note), // 入力欄が変わった（フォーム全体の内容）
+    Previous,     // 前のカード
+    Next,         // 次のカード
+    Add,          // 選択中のカードの後ろに新しいカードを加える
+    Delete,       // 選択中のカードを削除する
+    Save,         // デッキを検証して保存する
+    Close,        // 編集画面を閉じる（保存していない変更は捨てる）
+}
+
+/// フォームに見せる内容
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EditorView {
+    pub note: Note,              // 選択中のカード
+    pub position: String,        // 例: "カード 2/5"
+    pub message: Option<String>, // 入力の問題、または保存したことの通知
+}
+
+/// 入力欄の文字からノートを作る（語源は1行に1つ、タグはカンマか空白で区切る）
+pub fn note_from_fields(front: &str, back: &str, etymology: &str, tags: &str) -> Note {
+    Note {
+        front: front.trim().to_string(),
+        back: back.trim().to_string(),
+        etymology: etymology
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| line.to_string())
+            .collect(),
+        tags: split_tags(tags),
+    }
+}
+
+/// ページに重ねる編集フォーム（見た目は index.html の #editor で指定する）
+pub struct DomEditor {
+    root: HtmlElement,
+    position: Element,
+    front: HtmlInputElement,
+    back: HtmlInputElement,
+    etymology: HtmlTextAreaElement,
+    tags: HtmlInputElement,
+    message: Element,
+}
+impl DomEditor {
+    /// フォームを作って body に加える（最初は隠す）。フォームでの操作を受け取るチャンネルも返す
+    pub fn new() -> Result<(Self, UnboundedReceiver<EditorEvent>)> {
+        let document = browser::document()?;
+        let create = |tag: &str| {
+            document
+                .create_element(tag)
+                .map_err(|err| anyhow!("Could not create {} {:#?}", tag, err))
+        };
+        let set = |element: &Element, name: &str, value: &str| {
+            element
+                .set_attribute(name, value)
+                .map_err(|err| anyhow!("Could not set {} {:#?}", name, err))
+        };
+        let append = |parent: &Element, child: &Element| {
+            parent
+                .append_child(child)
+                .map(|_| ())
+                .map_err(|err| anyhow!("Could not append {:#?}", err))
+        };
+        // ラベルの中に入力欄を置く（ラベルの文字が入力欄の名前として読み上げられる）
+        let field = |parent: &Element, label: &str, tag: &str| -> Result<Element> {
+            let wrapper = create("label")?;
+            wrapper.set_text_content(Some(label));
+            let input = create(tag)?;
+            append(&wrapper, &input)?;
+            append(parent, &wrapper)?;
+            Ok(input)
+        };
+
+        let root = create("div")?
+            .dyn_into::<HtmlElement>()
+            .map_err(|element| anyhow!("Error converting {:#?} to HtmlElement", element))?;
+        set(&root, "id", "editor")?;
+        set(&root, "role", "dialog")?;
+        set(&root, "aria-label", "デッキの編集")?;
+        root.set_hidden(true);
+
+        let position = create("p")?;
+        append(&root, &position)?;
+        let front = field(&root, "表面（ルビは 漢字{よみ}、強調は [ ]）", "input")?
+            .dyn_into::<HtmlInputElement>()
+            .map_err(|element| anyhow!("Error converting {:#?} to HtmlInputElement", element))?;
+        let back = field(&root, "裏面", "input")?
+            .dyn_into::<HtmlInputElement>()
+            .map_err(|element| anyhow!("Error converting {:#?} to HtmlInputElement", element))?;
+        let etymology = field(&root, "語源（1行に1つ。例: dūrābilis/ラテン語: 長持ちする）", "textarea")?
+            .dyn_into::<HtmlTextAreaElement>()
+            .map_err(|element| anyhow!("Error converting {:#?} to HtmlTextAreaElement", element))?;
+        etymology.set_rows(4);
+        let tags = field(&root, "タグ（カンマ区切り）", "input")?
+            .dyn_into::<HtmlInputElement>()
+            .map_err(|element| anyhow!("Error converting {:#?} to HtmlInputElement", element))?;
+        let message = create("p")?;
+        set(&message, "role", "alert")?;
+        set(&message, "class", "message")?;
+        append(&root, &message)?;
+
+        let (sender, receiver) = unbounded();
+
+        // 入力のたびにフォーム全体の内容を送る
+        let oninput = {
+            let (sender, front, back, etymology, tags) =
+                (sender.clone(), front.clone(), back.clone(), etymology.clone(), tags.clone());
+            browser::closure_wrap(Box::new(move || {
+                let note = note_from_fields(&front.value(), &back.value(), &etymology.value(), &tags.value());
+                let _ = sender.unbounded_send(EditorEvent::Change(note));
+            }) as Box<dyn FnMut()>)
+        };
+        for input in [front.unchecked_ref::<HtmlElement>(), &back, &etymology, &tags] {
+            input.set_oninput(Some(oninput.as_ref().unchecked_ref()));
+        }
+        oninput.forget();
+
+        let buttons = create("div")?;
+        set(&buttons, "class", "buttons")?;
+        for (label, event) in [
+            ("前へ", EditorEvent::Previous),
+            ("次へ", EditorEvent::Next),
+            ("追加", EditorEvent::Add),
+            ("削除", EditorEvent::Delete),
+            ("保存", EditorEvent::Save),
+            ("閉じる", EditorEvent::Close),
+        ] {
+            append(&buttons, &button(&create("button")?, label, event, &sender)?)?;
+        }
+        append(&root, &buttons)?;
+
+        browser::document()?
+            .body()
+            .ok_or_else(|| anyhow!("No Body Found"))?
+            .append_child(&root)
+            .map_err(|err| anyhow!("Could not append #editor {:#?}", err))?;
+
+        Ok((
+            DomEditor {
+                root,
+                position,
+                front,
+                back,
+                etymology,
+                tags,
+                message,
+            },
+            receiver,
+        ))
+    }
+    /// フォームを view に合わせる（None なら隠して canvas にフォーカスを戻す）
+    /// 入力欄は、今の入力から作ったノートが view と違うとき（別のカードを選んだときなど）だけ書き換える
+    pub fn update(&self, previous: &Option<EditorView>, view: &Option<EditorView>) {
+        let Some(view) = view else {
+            self.root.set_hidden(true);
+            if let Ok(canvas) = browser::canvas() {
+                let _ = canvas.focus();
+            }
+            return;
+        };
+        let typed = note_from_fields(
+            &self.front.value(),
+            &self.back.value(),
+            &self.etymology.value(),
+            &self.tags.value(),
+        );
+        if typed != view.note {
+            self.front.set_value(&view.note.front);
+            self.back.set_value(&view.note.back);
+            self.etymology.set_value(&view.note.etymology.join("\n"));
+            self.tags.set_value(&view.note.tags.join(", "));
+        }
+        self.position.set_text_content(Some(&view.position));
+        self.message.set_text_content(view.message.as_deref());
+        if previous.is_none() {
+            self.root.set_hidden(false);
+            let _ = self.front.focus();
+        }
+    }
+}
+
+/// 押すと event を送るボタン
+fn button(
+    element: &Element,
+    label: &str,
+    event: EditorEvent,
+    sender: &UnboundedSender<EditorEvent>,
+) -> Result<Element> {
+    element
+        .set_attribute("type", "button")
+        .map_err(|err| anyhow!("Could not set type {:#?}", err))?;
+    element.set_text_content(Some(label));
+    let sender = sender.clone();
+    let onclick = browser::closure_wrap(Box::new(move || {
+        let _ = sender.unbounded_send(event.clone());
+    }) as Box<dyn FnMut()>);
+    element
+        .dyn_ref::<HtmlElement>()
+        .ok_or_else(|| anyhow!("Error converting {:#?} to HtmlElement", element))?
+        .set_onclick(Some(onclick.as_ref().unchecked_ref()));
+    onclick.forget();
+    Ok(element.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_become_a_note() {
+        let note = note_from_fields(
+            " 靴{くつ} ",
+            "shoes",
+            "calceus/ラテン語: 靴\n\n  shoe（靴）: 古英語 scōh  \n",
+            "daily, business  travel,",
+        );
+        assert_eq!(note.front, "靴{くつ}");
+        assert_eq!(note.etymology, ["calceus/ラテン語: 靴", "shoe（靴）: 古英語 scōh"]);
+        assert_eq!(note.tags, ["daily", "business", "travel"]);
+    }
+}
@@ -1,5 +1,20 @@
+use crate::a11y::{DomMirror, Mirror};
+use crate::backend::{CanvasBackend, RenderBackend};
 use crate::browser::{self, LoopClosure};
 use crate::common::*;
+use crate::etymology::{Etymology, Morpheme, MorphemeKind};
+use crate::gamepad::{GamepadSnapshot, held_codes};
+use crate::gesture::ShakeDetector;
+use crate::input::{InputEvent, KeyInput, KeyState, PointerAction, PointerState};
+use crate::keymap::Action;
+use crate::replay::Recorder;
+use crate::ruby::{RubySegment, parse_ruby};
+use crate::decoration::{CardFrame, Decorations, celtic_knot_small};
+use crate::deck::Deck;
+use crate::editor::{DomEditor, EditorEvent, EditorView};
+use crate::gesture::GestureConfig;
+use crate::settings::{Control, Motion, Settings, SystemPreferences};
+use crate::theme::{Face, Theme};
 use anyhow::{Result, anyhow};
 use async_trait::async_trait;
 use futures::channel::{
@@ -7,9 +22,13 @@ use futures::channel::{
     oneshot::channel,
 };
 
-use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::Mutex};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    sync::Mutex,
+};
 use wasm_bindgen::{JsCast, JsValue, prelude::Closure};
-use web_sys::{CanvasRenderingContext2d, HtmlImageElement, Touch, TouchEvent, TouchList};
+use web_sys::{HtmlImageElement, PointerEvent};
 
 #[derive(Clone, Copy, Default)]
 pub struct Point {
@@ -27,14 +46,17 @@ pub enum Font {
     Smaller,
 }
 impl Font {
-    pub fn get(&self) -> String {
+    pub fn px(&self) -> f64 {
         match self {
-            Font::Larger => "24px MyFont".to_string(),
-            Font::Middle => "18px MyFont".to_string(),
-            Font::Smaller => "14px MyFont".to_string(),
-            _ => "18px Myfont".to_string(),
+            Font::Larger => 24.0,
+            Font::Middle => 18.0,
+            Font::Smaller => 14.0,
         }
     }
+    /// テーマのフォントでの canvas の font 指定
+    pub fn get(&self, theme: &Theme) -> String {
+        theme.font(self.px())
+    }
 }
 pub enum Align {
     Center,
@@ -57,26 +79,27 @@ impl Message {
         Message {}
     }
     pub fn draw(&self, renderer: &Renderer, msg: &[String]) {
+        let color = renderer.theme().front.card.as_str();
         let _ = renderer.polygon(
             &Point { x: 400.0, y: 275.0 },
             &Point { x: 525.0, y: 150.0 },
             &Point { x: 526.0, y: 750.0 },
             &Point { x: 402.0, y: 625.0 },
-            Color::Green,
+            color,
         );
         let _ = renderer.polygon(
             &Point { x: -75.0, y: 150.0 },
             &Point { x: 50.0, y: 275.0 },
             &Point { x: 50.0, y: 625.0 },
             &Point { x: -75.0, y: 750.0 },
-            Color::Green,
+            color,
         );
         let _ = renderer.polygon(
             &Point { x: 325.0, y: 350.0 },
             &Point { x: 400.0, y: 275.0 },
             &Point { x: 400.0, y: 625.0 },
             &Point { x: 325.0, y: 550.0 },
-            Color::Green,
+            color,
         );
 
         let _ = renderer.polygon(
@@ -84,7 +107,7 @@ impl Message {
             &Point { x: 125.0, y: 350.0 },
             &Point { x: 125.0, y: 550.0 },
             &Point { x: 50.0, y: 625.0 },
-            Color::Green,
+            color,
         );
         let mut _interval = 0.0;
         for _m in msg {
@@ -96,36 +119,280 @@ impl Message {
                 _m,
                 Align::Left,
                 Font::Middle,
-                Color::Green,
+                color,
             );
             _interval += 20.0;
         }
     }
 }
 
+/// canvas のレイアウト情報（すべて CSS px）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CanvasLayout {
+    pub rect_left: f64,     // getBoundingClientRect().left（スクロール位置を反映したビューポート座標）
+    pub rect_top: f64,      // getBoundingClientRect().top
+    pub rect_width: f64,    // getBoundingClientRect().width（CSS transform 適用後・枠線込み）
+    pub rect_height: f64,   // getBoundingClientRect().height
+    pub offset_width: f64,  // offsetWidth（CSS transform 適用前・枠線込み）
+    pub offset_height: f64, // offsetHeight
+    pub border_left: f64,   // clientLeft（左の枠線の太さ）
+    pub border_top: f64,    // clientTop（上の枠線の太さ）
+    pub client_width: f64,  // clientWidth（枠線を除く描画領域の幅）
+    pub client_height: f64, // clientHeight（枠線を除く描画領域の高さ）
+}
+
+/// ポインタの座標（clientX/clientY）を canvas の描画領域内の座標（CSS px、枠線の内側が原点）に変換
+/// transform: scale() などで表示が拡大縮小されていても、拡大縮小前の CSS px で返す
+pub fn client_to_content(layout: &CanvasLayout, client_x: f64, client_y: f64) -> (f64, f64) {
+    let css_scale_x = if layout.offset_width > 0.0 {
+        layout.rect_width / layout.offset_width
+    } else {
+        1.0
+    };
+    let css_scale_y = if layout.offset_height > 0.0 {
+        layout.rect_height / layout.offset_height
+    } else {
+        1.0
+    };
+    (
+        (client_x - layout.rect_left) / css_scale_x - layout.border_left,
+        (client_y - layout.rect_top) / css_scale_y - layout.border_top,
+    )
+}
+
+/// 描画領域の大きさに対する論理座標（SCREEN_WIDTH × SCREEN_HEIGHT）の拡大率
+/// 縦横比を保ったまま収まる最大の倍率（描画領域が 0 のときは 1.0）
+pub fn logical_scale(content_width: f64, content_height: f64) -> f64 {
+    let scale =
+        (content_width / SCREEN_WIDTH as f64).min(content_height / SCREEN_HEIGHT as f64);
+    if scale > 0.0 { scale } else { 1.0 }
+}
+
+/// 論理座標の原点の位置（描画領域の左上からの CSS px、余白を中央揃えにする）
+pub fn logical_origin(content_width: f64, content_height: f64) -> (f64, f64) {
+    let scale = logical_scale(content_width, content_height);
+    (
+        ((content_width - SCREEN_WIDTH as f64 * scale) / 2.0).max(0.0),
+        ((content_height - SCREEN_HEIGHT as f64 * scale) / 2.0).max(0.0),
+    )
+}
+
+/// 描画領域内の座標（CSS px）を論理座標に変換
+pub fn content_to_logical(
+    content_width: f64,
+    content_height: f64,
+    x: f64,
+    y: f64,
+) -> (f64, f64) {
+    let scale = logical_scale(content_width, content_height);
+    let (origin_x, origin_y) = logical_origin(content_width, content_height);
+    ((x - origin_x) / scale, (y - origin_y) / scale)
+}
+
+/// canvas の表示サイズと論理座標（SCREEN_WIDTH × SCREEN_HEIGHT）の対応
+/// 論理座標の画面は縦横比を保ったまま canvas の中央に収まるように拡大縮小する
+#[derive(Clone, Copy)]
+pub struct Viewport {
+    layout: CanvasLayout,
+    dpr: f64, // devicePixelRatio
+}
+impl Viewport {
+    /// 現在の canvas のレイアウトから Viewport を作成
+    fn measure() -> Result<Self> {
+        let canvas = browser::canvas()?;
+        let rect = canvas.get_bounding_client_rect();
+        Ok(Viewport {
+            layout: CanvasLayout {
+                rect_left: rect.left(),
+                rect_top: rect.top(),
+                rect_width: rect.width(),
+                rect_height: rect.height(),
+                offset_width: canvas.offset_width() as f64,
+                offset_height: canvas.offset_height() as f64,
+                border_left: canvas.client_left() as f64,
+                border_top: canvas.client_top() as f64,
+                client_width: canvas.client_width() as f64,
+                client_height: canvas.client_height() as f64,
+            },
+            dpr: browser::window()?.device_pixel_ratio().max(1.0),
+        })
+    }
+    /// 論理座標 1px あたりの CSS px
+    pub fn scale(&self) -> f64 {
+        logical_scale(self.layout.client_width, self.layout.client_height)
+    }
+    /// 論理座標の原点の位置（描画領域の左上からの CSS px）
+    fn origin(&self) -> (f64, f64) {
+        logical_origin(self.layout.client_width, self.layout.client_height)
+    }
+    /// ポインタの座標（clientX/clientY）を論理座標に変換
+    pub fn to_logical(self, client_x: i32, client_y: i32) -> (i32, i32) {
+        let (content_x, content_y) =
+            client_to_content(&self.layout, client_x as f64, client_y as f64);
+        let (x, y) = content_to_logical(
+            self.layout.client_width,
+            self.layout.client_height,
+            content_x,
+            content_y,
+        );
+        (x.round() as i32, y.round() as i32)
+    }
+    /// canvas のバッファサイズを devicePixelRatio に合わせる
+    fn resize_canvas(&self) -> Result<()> {
+        let canvas = browser::canvas()?;
+        let width = (self.layout.client_width * self.dpr).round().max(1.0) as u32;
+        let height = (self.layout.client_height * self.dpr).round().max(1.0) as u32;
+        if canvas.width() != width || canvas.height() != height {
+            canvas.set_width(width);
+            canvas.set_height(height);
+        }
+        Ok(())
+    }
+}
+
 pub struct Renderer {
-    context: CanvasRenderingContext2d,
+    context: Rc<dyn RenderBackend>,
+    face_buffer: Option<FaceBuffer>, // フリップ中のカードの面を描く裏のバッファ
+    theme: Rc<Theme>,
+    motion: Motion, // カードの動かし方（動きを減らす設定ならクロスフェード）
+}
+
+/// カードの1つの面に描く内容
+pub struct CardFace<'a> {
+    pub face: Face, // 描く面（配色・装飾・語源ツリーの有無が変わる）
+    pub text: &'a str,
+    pub etymology: &'a Etymology,
+    pub decorations: Option<Decorations>, // デッキ・タグで指定された装飾（None ならテーマの既定）
+    pub show_ruby: bool,                  // ルビ（ふりがな）を表示するかどうか
+}
+
+/// フリップ中のカードの面を一度描いておく裏のバッファ（遠近法で短冊に分けて描画する元）
+struct FaceBuffer {
+    backend: Rc<dyn RenderBackend>,
+}
+impl FaceBuffer {
+    /// カードの大きさに合わせて消去し、左上を原点とする論理座標にする
+    fn prepare(&self, width: f32, height: f32) {
+        let buffer_width = (width as f64 * FLIP_BUFFER_SCALE).ceil() as u32;
+        let buffer_height = (height as f64 * FLIP_BUFFER_SCALE).ceil() as u32;
+        if self.backend.size() != (buffer_width, buffer_height) {
+            self.backend.resize(buffer_width, buffer_height);
+        }
+        self.backend.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+        self.backend
+            .clear_rect(0.0, 0.0, buffer_width as f64, buffer_height as f64);
+        self.backend
+            .set_transform(FLIP_BUFFER_SCALE, 0.0, 0.0, FLIP_BUFFER_SCALE, 0.0, 0.0);
+    }
+    /// 傾いた面に陰影をつける（横を向くほど暗く、奥側の辺ほど暗い）
+    fn shade(&self, width: f32, height: f32, flip_angle: f32) {
+        let tilt = visible_flip_angle(flip_angle as f64).sin();
+        let shade = FLIP_SHADE * tilt.abs();
+        let far_right = tilt > 0.0;
+        let (left, right) = if far_right {
+            (shade * 0.5, shade)
+        } else {
+            (shade, shade * 0.5)
+        };
+
+        self.backend.set_transform(FLIP_BUFFER_SCALE, 0.0, 0.0, FLIP_BUFFER_SCALE, 0.0, 0.0);
+        self.backend.set_fill_linear_gradient(
+            0.0,
+            0.0,
+            width as f64,
+            0.0,
+            &[
+                (0.0, format!("rgba(0, 0, 0, {:.3})", left)),
+                (1.0, format!("rgba(0, 0, 0, {:.3})", right)),
+            ],
+        );
+        // カードの形（描いた部分）だけに重ねる
+        self.backend.set_global_composite_operation("source-atop");
+        self.backend.fill_rect(0.0, 0.0, width as f64, height as f64);
+        self.backend.set_global_composite_operation("source-over");
+    }
+}
+
+/// フリップ角度（0〜π）を、見えている面から見た角度（-π/2〜π/2）にする
+/// π/2 を過ぎると裏面が見えるので、裏面が鏡写しにならないように π 戻す
+fn visible_flip_angle(flip_angle: f64) -> f64 {
+    if flip_angle > std::f64::consts::FRAC_PI_2 {
+        flip_angle - std::f64::consts::PI
+    } else {
+        flip_angle
+    }
+}
+
+/// 縦軸まわりに angle 回したカード上の点（中心からの横位置 x）を、距離 distance から見たときの
+/// 画面上の横位置と縮尺
+fn perspective_project(x: f64, angle: f64, distance: f64) -> (f64, f64) {
+    let depth = x * angle.sin(); // 奥が正
+    let scale = distance / (distance + depth);
+    (x * angle.cos() * scale, scale)
 }
 
 impl Renderer {
+    pub fn new(context: Rc<dyn RenderBackend>, theme: Theme) -> Self {
+        Renderer {
+            // 裏のバッファを作れなければ、フリップは横方向の縮小だけで表現する
+            face_buffer: context
+                .create_offscreen()
+                .map(|backend| FaceBuffer { backend }),
+            context,
+            theme: Rc::new(theme),
+            motion: Motion::Full,
+        }
+    }
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+    pub fn motion(&self) -> Motion {
+        self.motion
+    }
+    pub fn set_motion(&mut self, motion: Motion) {
+        self.motion = motion;
+    }
+    /// テーマを替える（設定画面での変更をすぐに反映する）
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = Rc::new(theme);
+    }
+    /// canvas 全体（余白を含む）を消去し、論理座標で描画できるように変換を設定する
+    /// テーマに背景色があれば塗りつぶす
     pub fn clear(&self) {
-        self.context.clear_rect(
+        self.context.save();
+        self.context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+        let (width, height) = self.context.size();
+        let (width, height) = (width as f64, height as f64);
+        self.context.clear_rect(0.0, 0.0, width, height);
+        if let Some(background) = &self.theme.background {
+            self.context.set_fill_style_str(background);
+            self.context.fill_rect(0.0, 0.0, width, height);
+        }
+        self.context.restore();
+    }
+    /// 論理座標 → canvas のバッファ座標の変換を設定
+    fn set_viewport(&self, viewport: &Viewport) {
+        let scale = viewport.scale() * viewport.dpr;
+        let (origin_x, origin_y) = viewport.origin();
+        self.context.set_transform(
+            scale,
             0.0,
             0.0,
-            browser::canvas().unwrap().client_width().into(),
-            browser::canvas().unwrap().client_height().into(),
+            scale,
+            origin_x * viewport.dpr,
+            origin_y * viewport.dpr,
         );
     }
-    pub fn text(&self, point: &Point, text: &str, align: Align, font: Font, color: Color) {
-        self.context.set_fill_style_str(&color.get());
+    pub fn text(&self, point: &Point, text: &str, align: Align, font: Font, color: &str) {
+        self.context.set_fill_style_str(color);
         match align {
             Align::Center => self.context.set_text_align(&align.get()),
             Align::Left => self.context.set_text_align(&align.get()),
             Align::Right => self.context.set_text_align(&align.get()),
             _ => self.context.set_text_align(&align.get()),
         }
-        self.context.set_font(&font.get());
-        let _ = self.context.fill_text(text, point.x as f64, point.y as f64);
+        self.context.set_font(&font.get(&self.theme));
+        self.context.fill_text(text, point.x as f64, point.y as f64);
     }
 
     pub fn celtic_progress_counter(&self, point: &Point, text: &str, color: &str) {
@@ -146,16 +413,16 @@ impl Renderer {
         // テキストを描画
         self.context.set_fill_style_str(color);
         self.context.set_text_align("center");
-        self.context.set_font("32px MyFont");
-        let _ = self
+        self.context.set_font(&self.theme.font(32.0));
+        self
             .context
             .fill_text(text, point.x as f64, (point.y + 8.0) as f64);
 
         self.context.restore();
     }
-    pub fn line(&self, a: &Point, b: &Point, color: Color) {
-        self.context.set_stroke_style_str(&color.get());
-        self.context.set_fill_style_str(&color.get());
+    pub fn line(&self, a: &Point, b: &Point, color: &str) {
+        self.context.set_stroke_style_str(color);
+        self.context.set_fill_style_str(color);
         self.context.begin_path();
         self.context.move_to(a.x as f64, a.y as f64);
         self.context.line_to(b.x as f64, b.y as f64);
@@ -163,9 +430,9 @@ impl Renderer {
         self.context.stroke();
         self.context.fill();
     }
-    pub fn polygon(&self, a: &Point, b: &Point, c: &Point, d: &Point, color: Color) {
-        self.context.set_stroke_style_str(&color.get());
-        self.context.set_fill_style_str(&color.get());
+    pub fn polygon(&self, a: &Point, b: &Point, c: &Point, d: &Point, color: &str) {
+        self.context.set_stroke_style_str(color);
+        self.context.set_fill_style_str(color);
         self.context.begin_path();
         self.context.move_to(a.x as f64, a.y as f64);
         self.context.line_to(b.x as f64, b.y as f64);
@@ -177,426 +444,647 @@ impl Renderer {
         self.context.fill();
     }
 
-    fn draw_card_decoration(&self, width: f32, height: f32, is_front: bool) {
-        self.context.set_fill_style_str("rgba(255, 255, 255, 0.3)");
-        self.context
-            .set_stroke_style_str("rgba(255, 255, 255, 0.5)");
-        self.context.set_line_width(2.5);
+    pub fn fill_round_rect_rotate_with_flip(
+        &self,
+        cp: &Point, // 中心座標
+        rotate: f32,
+        width: f32,
+        height: f32,
+        radius: f32,
+        alpha: f32,
+        flip_angle: f32, // フリップ角度
+        content: &CardFace,
+    ) {
+        self.context.save();
+        self.context.set_global_alpha(alpha.into());
 
-        let card_left = -(width / 2.0) as f64;
-        let card_top = -(height / 2.0 + 350.0) as f64;
-        let center_x = card_left + (width / 2.0) as f64;
-        let center_y = card_top + (height / 2.0 - 25.0) as f64 - 80.0; // テキストの上
+        // 1. 回転中心(cp.x, cp.y + 350)に移動
+        self.context.translate(cp.x as f64, (cp.y + 380.0) as f64);
 
-        if !is_front {
-            // 裏面：星座風のデザイン
-            self.draw_constellation_pattern(card_left, card_top, center_x, center_y, width, height);
-            return;
+        // 2. 回転
+        self.context.rotate(rotate as f64);
+
+        // 3. フリップ中は遠近法で縦軸まわりに回す（裏の canvas が使えなければ横方向の縮小で代用）
+        match &self.face_buffer {
+            Some(buffer) if flip_angle != 0.0 => {
+                let buffer_renderer = Renderer {
+                    context: Rc::clone(&buffer.backend),
+                    face_buffer: None,
+                    theme: Rc::clone(&self.theme),
+                    motion: self.motion,
+                };
+                buffer.prepare(width, height);
+                buffer_renderer
+                    .context
+                    .translate((width / 2.0) as f64, (height / 2.0 + 350.0) as f64);
+                buffer_renderer.draw_card_face(width, height, radius, content);
+                buffer.shade(width, height, flip_angle);
+                self.draw_perspective_slices(buffer, width, height, flip_angle);
+            }
+            _ => {
+                let scale_x = (flip_angle.cos()).abs();
+                self.context.scale(scale_x as f64, 1.0);
+                self.draw_card_face(width, height, radius, content);
+            }
         }
 
-        // 表面：ケルト風のデザイン（以下は既存のコード）
-        // 背景全体にケルト文様の枠線を描画
-        self.context.set_line_width(1.5);
-        self.context
-            .set_stroke_style_str("rgba(255, 255, 255, 0.2)");
+        self.context.restore();
+    }
 
-        // カード周囲にケルト風の二重線
-        let border_offset = 10.0;
-        self.context.begin_path();
-        let _ = self.context.round_rect_with_f64(
-            card_left + border_offset,
-            card_top + border_offset,
-            width as f64 - border_offset * 2.0,
-            height as f64 - border_offset * 2.0,
-            8.0,
-        );
-        self.context.stroke();
+    /// カードの面（矩形・装飾・テキスト・語源ツリー）を回転中心から見た座標系で描画
+    fn draw_card_face(&self, width: f32, height: f32, radius: f32, content: &CardFace) {
+        let face = content.face;
+        let palette = self.theme.palette(face);
+        self.context.set_stroke_style_str(&palette.card);
+        self.context.set_fill_style_str(&palette.card);
 
-        let border_offset2 = 15.0;
+        // 矩形を描画（回転中心から見た相対位置で描画）
         self.context.begin_path();
-        let _ = self.context.round_rect_with_f64(
-            card_left + border_offset2,
-            card_top + border_offset2,
-            width as f64 - border_offset2 * 2.0,
-            height as f64 - border_offset2 * 2.0,
-            6.0,
+        self.context.round_rect(
+            -(width / 2.0) as f64,
+            -(height / 2.0 + 350.0) as f64,
+            width as f64,
+            height as f64,
+            radius as f64,
         );
-        self.context.stroke();
 
-        // 背景に繊細なケルト結び目パターンを散りばめる
-        self.context.set_line_width(1.0);
-        self.context
-            .set_stroke_style_str("rgba(255, 255, 255, 0.15)");
-
-        // 上部と下部にケルト風の連続模様
-        let positions = [
-            (center_x - 60.0, card_top + 40.0),
-            (center_x, card_top + 40.0),
-            (center_x + 60.0, card_top + 40.0),
-            (center_x - 60.0, card_top + height as f64 - 40.0),
-            (center_x, card_top + height as f64 - 40.0),
-            (center_x + 60.0, card_top + height as f64 - 40.0),
-        ];
+        self.context.close_path();
+        self.context.fill();
 
-        for &(px, py) in positions.iter() {
-            // 小さなケルト結び目
-            self.context.begin_path();
-            let _ = self
+        // 装飾パターンを描画（表裏で異なるデザイン）
+        let decoration = content
+            .decorations
+            .unwrap_or(self.theme.decorations)
+            .for_face(face);
+        self.context.save();
+        decoration.draw(&*self.context, palette, &CardFrame::around_pivot(width, height));
+        self.context.restore();
+
+        // テキストを描画（同じ回転座標系で・ルビ記法に対応）
+        self.context.set_fill_style_str(&palette.ink(1.0));
+        self.fill_ruby_text(&parse_ruby(content.text), 0.0, -340.0, 18.0, content.show_ruby);
+
+        // 裏面 語源をツリー図で描画
+        if face == Face::Back {
+            self.draw_etymology_tree(content.etymology);
+        }
+    }
+
+    /// 裏の canvas に描いたカードの面を縦の短冊に分け、遠近法で縮めながら並べる
+    /// 回転軸から遠い側ほど小さく、軸に近づくほど幅が詰まって見える
+    fn draw_perspective_slices(&self, buffer: &FaceBuffer, width: f32, height: f32, flip_angle: f32) {
+        let angle = visible_flip_angle(flip_angle as f64);
+        let (width, height) = (width as f64, height as f64);
+        let slice_width = width / FLIP_SLICES as f64;
+        let (buffer_width, buffer_height) = buffer.backend.size();
+        let source_width = buffer_width as f64 / FLIP_SLICES as f64;
+        let source_height = buffer_height as f64;
+        let center_y = -350.0;
+
+        for i in 0..FLIP_SLICES {
+            let x0 = -width / 2.0 + slice_width * i as f64;
+            let (left, scale_left) = perspective_project(x0, angle, FLIP_PERSPECTIVE_DISTANCE);
+            let (right, scale_right) =
+                perspective_project(x0 + slice_width, angle, FLIP_PERSPECTIVE_DISTANCE);
+            let slice_height = height * (scale_left + scale_right) / 2.0;
+            // 隣の短冊との隙間が見えないように少し重ねる
+            self
                 .context
-                .arc(px, py, 6.0, 0.0, 2.0 * std::f64::consts::PI);
-            self.context.stroke();
+                .draw_image(
+                    &*buffer.backend,
+                    source_width * i as f64,
+                    0.0,
+                    source_width,
+                    source_height,
+                    left.min(right),
+                    center_y - slice_height / 2.0,
+                    (right - left).abs() + 0.5,
+                    slice_height,
+                );
+        }
+    }
 
-            // 交差する曲線
-            self.context.begin_path();
-            self.context.move_to(px - 8.0, py - 8.0);
-            self.context
-                .bezier_curve_to(px, py, px, py, px + 8.0, py + 8.0);
-            self.context.stroke();
+    /// ルビ付きテキストを中央揃えで描画
+    /// 親文字の上に読みを小さい文字で描画する（show_reading が false なら読みは省略）
+    pub fn fill_ruby_text(
+        &self,
+        segments: &[RubySegment],
+        center_x: f64,
+        y: f64,
+        font_size: f64,
+        show_reading: bool,
+    ) {
+        let base_font = self.theme.font(font_size);
+        let ruby_font = self.theme.font(font_size * FURIGANA_FONT_SCALE);
+        let measure = |text: &str| self.context.measure_text(text);
 
-            self.context.begin_path();
-            self.context.move_to(px - 8.0, py + 8.0);
-            self.context
-                .bezier_curve_to(px, py, px, py, px + 8.0, py - 8.0);
-            self.context.stroke();
+        // 親文字の幅を合計して左端を求める
+        self.context.set_font(&base_font);
+        let widths: Vec<f64> = segments.iter().map(|s| measure(&s.base)).collect();
+        let total_width: f64 = widths.iter().sum();
+
+        self.context.set_text_align("center");
+        let mut x = center_x - total_width / 2.0;
+        for (segment, width) in segments.iter().zip(widths.iter()) {
+            let segment_center = x + width / 2.0;
+            self.context.set_font(&base_font);
+            self.context.fill_text(&segment.base, segment_center, y);
+
+            if let (true, Some(reading)) = (show_reading, &segment.reading) {
+                self.context.set_font(&ruby_font);
+                self
+                    .context
+                    .fill_text(reading, segment_center, y - font_size * FURIGANA_OFFSET_SCALE);
+            }
+            x += width;
         }
+    }
 
-        // 中央部分の装飾を強調
-        self.context.set_line_width(2.5);
+    /// 語源のツリー図を描画（カードの回転座標系で呼び出す）
+    /// 上段: 形態素（接頭辞・語基・接尾辞） → 中段: 語根 → 下段: 派生語、最後に補足を並べる
+    fn draw_etymology_tree(&self, etymology: &Etymology) {
+        let morphemes: Vec<&Morpheme> = etymology
+            .prefixes()
+            .chain(
+                etymology
+                    .morphemes
+                    .iter()
+                    .filter(|m| m.kind == MorphemeKind::Base),
+            )
+            .chain(etymology.suffixes())
+            .collect();
+        let palette = &self.theme.back;
+
+        self.context.save();
+        self.context.set_text_align("center");
+        self.context.set_line_width(1.0);
         self.context
-            .set_stroke_style_str("rgba(255, 255, 255, 0.5)");
+            .set_stroke_style_str(&palette.ink(0.6));
 
-        // ケルト文様：組紐（インターレース）パターンを描画
-        // 中央の三つ編み結び目
-        let knot_size = 30.0;
+        let mut y = ETYMOLOGY_TREE_TOP;
 
-        // 中央の円形ノット
+        // 上段: 形態素
+        let morpheme_xs = tree_row_positions(morphemes.len());
+        let morpheme_y = y;
+        for (m, x) in morphemes.iter().zip(morpheme_xs.iter()) {
+            self.draw_etymology_node(*x, y, &m.form, &m.gloss);
+        }
+        if !morphemes.is_empty() {
+            y += ETYMOLOGY_TREE_ROW_HEIGHT;
+        }
+
+        // 中段: 語根
+        let root_y = y;
+        let root_label = match &etymology.language {
+            Some(language) => format!("{}（{}）", etymology.root, language),
+            None => etymology.root.clone(),
+        };
+        self.draw_etymology_node(0.0, root_y, &root_label, &etymology.gloss);
+        for x in morpheme_xs.iter() {
+            self.tree_edge(*x, morpheme_y, 0.0, root_y);
+        }
+        y += ETYMOLOGY_TREE_ROW_HEIGHT;
+
+        // 下段: 派生語
+        let derived_xs = tree_row_positions(etymology.derived.len());
+        for (d, x) in etymology.derived.iter().zip(derived_xs.iter()) {
+            self.draw_etymology_node(*x, y, &d.word, &d.gloss);
+            self.tree_edge(0.0, root_y, *x, y);
+            if let Some(formation) = &d.formation {
+                self.context.set_font(&self.theme.font(11.0));
+                self.context
+                    .set_fill_style_str(&palette.ink(0.6));
+                self
+                    .context
+                    .fill_text(formation, *x, y + ETYMOLOGY_NODE_HEIGHT + 12.0);
+            }
+        }
+        if !etymology.derived.is_empty() {
+            y += ETYMOLOGY_TREE_ROW_HEIGHT;
+        }
+
+        // 補足
+        self.context.set_font(&self.theme.font(12.0));
+        self.context.set_fill_style_str(&palette.ink(0.8));
+        for note in etymology.notes.iter() {
+            self
+                .context
+                .fill_text_with_max_width(note, 0.0, y, ETYMOLOGY_TREE_WIDTH);
+            y += 16.0;
+        }
+
+        self.context.restore();
+    }
+
+    /// ツリー図のノード（枠付きの見出し＋下に意味）を描画
+    fn draw_etymology_node(&self, x: f64, y: f64, label: &str, gloss: &str) {
+        let palette = &self.theme.back;
+        self.context.set_font(&self.theme.font(13.0));
+        let label_width = self.context.measure_text(label)
+            .min(ETYMOLOGY_TREE_WIDTH);
+
+        self.context.set_fill_style_str(&palette.ink(0.15));
         self.context.begin_path();
-        let _ = self.context.arc(
-            center_x,
-            center_y,
-            knot_size / 2.0,
-            0.0,
-            2.0 * std::f64::consts::PI,
+        self.context.round_rect(
+            x - label_width / 2.0 - 6.0,
+            y - ETYMOLOGY_NODE_HEIGHT / 2.0,
+            label_width + 12.0,
+            ETYMOLOGY_NODE_HEIGHT,
+            4.0,
         );
+        self.context.fill();
         self.context.stroke();
 
-        // 組紐パターン：3つの曲線で構成
-        let curve_offset = knot_size * 0.8;
+        self.context.set_fill_style_str(&palette.ink(1.0));
+        self.context.fill_text_with_max_width(
+            label,
+            x,
+            y + 4.5,
+            ETYMOLOGY_TREE_WIDTH,
+        );
+
+        if !gloss.is_empty() {
+            self.context.set_font(&self.theme.font(11.0));
+            self.context
+                .set_fill_style_str(&palette.ink(0.8));
+            self.context.fill_text_with_max_width(
+                gloss,
+                x,
+                y + ETYMOLOGY_NODE_HEIGHT / 2.0 + 13.0,
+                ETYMOLOGY_TREE_WIDTH,
+            );
+        }
+    }
 
-        // 左上から右下への曲線
+    /// ツリー図の親子を結ぶ線（親ノードの下端から子ノードの上端へ）
+    fn tree_edge(&self, from_x: f64, from_y: f64, to_x: f64, to_y: f64) {
+        let (upper_x, upper_y, lower_x, lower_y) = if from_y < to_y {
+            (from_x, from_y, to_x, to_y)
+        } else {
+            (to_x, to_y, from_x, from_y)
+        };
         self.context.begin_path();
         self.context
-            .move_to(center_x - curve_offset, center_y - curve_offset);
-        self.context.bezier_curve_to(
-            center_x - curve_offset / 2.0,
-            center_y,
-            center_x,
-            center_y - curve_offset / 2.0,
-            center_x + curve_offset,
-            center_y + curve_offset,
-        );
+            .move_to(upper_x, upper_y + ETYMOLOGY_NODE_HEIGHT / 2.0 + 16.0);
+        self.context
+            .line_to(lower_x, lower_y - ETYMOLOGY_NODE_HEIGHT / 2.0);
         self.context.stroke();
+    }
 
-        // 右上から左下への曲線
+    /// ワードファミリー（同じ語根を持つカードの一覧）のウィンドウを描画
+    /// rows: (カードの英文, 語根の表示), selected: 選択中の行, derived: 派生語の一覧
+    pub fn draw_word_family_window(
+        &self,
+        point: &Point,
+        title: &str,
+        rows: &[(String, String)],
+        selected: usize,
+        derived: &[String],
+    ) {
+        self.context.save();
+
+        // カードと同じサイズの裏面色のウィンドウ
+        let box_width = FLASH_CARD_WIDTH as f64;
+        let box_height = FLASH_CARD_HEIGHT as f64;
+        let x = point.x as f64 - box_width / 2.0;
+        let y = point.y as f64 - box_height / 2.0;
+        let center_x = point.x as f64;
+        let palette = &self.theme.back;
+
+        self.context.set_fill_style_str(&palette.panel);
         self.context.begin_path();
-        self.context
-            .move_to(center_x + curve_offset, center_y - curve_offset);
-        self.context.bezier_curve_to(
-            center_x + curve_offset / 2.0,
-            center_y,
-            center_x,
-            center_y - curve_offset / 2.0,
-            center_x - curve_offset,
-            center_y + curve_offset,
+        self.context.round_rect(
+            x,
+            y,
+            box_width,
+            box_height,
+            FLASH_CARD_CORNER_RADIUS as f64,
         );
-        self.context.stroke();
+        self.context.fill();
 
-        // 上下の曲線（縦方向の組紐）
-        self.context.begin_path();
+        self.context.set_line_width(1.5);
         self.context
-            .move_to(center_x, center_y - curve_offset * 1.2);
-        self.context.bezier_curve_to(
-            center_x + curve_offset / 3.0,
-            center_y - curve_offset / 2.0,
-            center_x - curve_offset / 3.0,
-            center_y + curve_offset / 2.0,
-            center_x,
-            center_y + curve_offset * 1.2,
+            .set_stroke_style_str(&palette.ink(0.3));
+        self.context.begin_path();
+        self.context.round_rect(
+            x + 10.0,
+            y + 10.0,
+            box_width - 20.0,
+            box_height - 20.0,
+            8.0,
         );
         self.context.stroke();
 
-        // 四隅のケルト三つ巴模様
-        let corner_offset = 20.0;
-        let spiral_size = 10.0;
-
-        let corners = [
-            (card_left + corner_offset, card_top + corner_offset),
-            (
-                card_left + width as f64 - corner_offset,
-                card_top + corner_offset,
-            ),
-            (
-                card_left + corner_offset,
-                card_top + height as f64 - corner_offset,
-            ),
-            (
-                card_left + width as f64 - corner_offset,
-                card_top + height as f64 - corner_offset,
-            ),
-        ];
-
-        for &(cx, cy) in corners.iter() {
-            // スパイラル状のケルト模様
-            self.context.begin_path();
-            self.context.move_to(cx, cy);
-            self.context.bezier_curve_to(
-                cx + spiral_size,
-                cy - spiral_size / 2.0,
-                cx + spiral_size / 2.0,
-                cy + spiral_size,
-                cx,
-                cy,
-            );
-            self.context.stroke();
-
-            // 小さな円
-            self.context.begin_path();
-            let _ = self
-                .context
-                .arc(cx, cy, 4.0, 0.0, 2.0 * std::f64::consts::PI);
-            self.context.fill();
+        // 上部の四隅にケルト結び目
+        for &(cx, cy) in [(x + 35.0, y + 35.0), (x + box_width - 35.0, y + 35.0)].iter() {
+            celtic_knot_small(&*self.context, palette, cx, cy, 15.0);
         }
 
-        // 左右の装飾：ケルトノット
-        let side_offset = 45.0;
-        for offset in [-side_offset, side_offset].iter() {
-            let x = center_x + offset;
-
-            // 小さな組紐結び目
-            self.context.begin_path();
-            self.context.move_to(x - 8.0, center_y - 10.0);
-            self.context.bezier_curve_to(
-                x,
-                center_y - 12.0,
-                x,
-                center_y + 12.0,
-                x - 8.0,
-                center_y + 10.0,
+        // タイトル（語根）
+        self.context.set_text_align("center");
+        self.context.set_fill_style_str(&palette.ink(1.0));
+        self.context.set_font(&self.theme.font(14.0));
+        self.context.fill_text("Word family", center_x, y + 40.0);
+        self.context.set_font(&self.theme.font(22.0));
+        self
+            .context
+            .fill_text_with_max_width(title, center_x, y + 72.0, box_width - 90.0);
+
+        // 同じ語根を持つカードの一覧（選択中の行を強調）
+        let row_height = 48.0;
+        let list_top = y + 100.0;
+        for (i, (label, root)) in rows.iter().enumerate() {
+            let row_y = list_top + row_height * i as f64;
+            if i == selected {
+                self.context.set_fill_style_str(&palette.accent);
+                self.context.begin_path();
+                self.context.round_rect(
+                    x + 20.0,
+                    row_y,
+                    box_width - 40.0,
+                    row_height - 6.0,
+                    6.0,
+                );
+                self.context.fill();
+            }
+            self.context.set_fill_style_str(&palette.ink(1.0));
+            self.context.set_font(&self.theme.font(15.0));
+            self.context.fill_text_with_max_width(
+                label,
+                center_x,
+                row_y + 19.0,
+                box_width - 60.0,
             );
-            self.context.stroke();
+            self.context.set_font(&self.theme.font(12.0));
+            self.context
+                .set_fill_style_str(&palette.ink(0.7));
+            self.context.fill_text_with_max_width(
+                root,
+                center_x,
+                row_y + 36.0,
+                box_width - 60.0,
+            );
+        }
 
-            self.context.begin_path();
-            self.context.move_to(x + 8.0, center_y - 10.0);
-            self.context.bezier_curve_to(
-                x,
-                center_y - 12.0,
-                x,
-                center_y + 12.0,
-                x + 8.0,
-                center_y + 10.0,
+        // 派生語
+        self.context.set_font(&self.theme.font(13.0));
+        self.context.set_fill_style_str(&palette.ink(0.8));
+        let mut derived_y = list_top + row_height * rows.len() as f64 + 24.0;
+        for word in derived.iter() {
+            self.context.fill_text_with_max_width(
+                word,
+                center_x,
+                derived_y,
+                box_width - 60.0,
             );
-            self.context.stroke();
+            derived_y += 18.0;
         }
-    }
-    pub fn fill_round_rect_rotate_with_flip(
-        &self,
-        cp: &Point, // 中心座標
-        rotate: f32,
-        width: f32,
-        height: f32,
-        radius: f32,
-        color: Color,
-        alpha: f32,
-        text: &str,
-        flip_angle: f32, // フリップ角度
-        etymology: Vec<&str>,
-    ) {
-        self.context.save();
-        self.context.set_global_alpha(alpha.into());
-        self.context.set_stroke_style_str(&color.get());
-        self.context.set_fill_style_str(&color.get());
 
-        // 1. 回転中心(cp.x, cp.y + 350)に移動
-        let _ = self.context.translate(cp.x as f64, (cp.y + 380.0) as f64);
+        // 操作説明
+        self.context.set_font(&self.theme.font(12.0));
+        self.context.set_fill_style_str(&palette.ink(0.6));
+        self.context.fill_text(
+            "↑↓ / swipe: select   Enter / tap: jump",
+            center_x,
+            y + box_height - 40.0,
+        );
+        self.context.fill_text(
+            "Esc / F / long press: back",
+            center_x,
+            y + box_height - 22.0,
+        );
 
-        // 2. 回転
-        let _ = self.context.rotate(rotate as f64);
+        self.context.restore();
+    }
 
-        // 3. フリップ効果（Y軸方向のスケール変更）
-        let scale_x = (flip_angle.cos()).abs();
-        let _ = self.context.scale(scale_x as f64, 1.0);
+    /// 設定画面のウィンドウを描画
+    /// rows: (項目名, 値, 見た目), selected: 選択中の行
+    /// 行の位置は SETTINGS_ROW_TOP・SETTINGS_ROW_HEIGHT、スライダーは行の右端の SETTINGS_SLIDER_WIDTH（タップの判定と同じ）
+    pub fn draw_settings_window(&self, point: &Point, rows: &[(&str, String, Control)], selected: usize) {
+        self.context.save();
 
-        // 4. 矩形を描画（回転中心から見た相対位置で描画）
+        // カードと同じサイズの表面色のウィンドウ
+        let box_width = FLASH_CARD_WIDTH as f64;
+        let box_height = FLASH_CARD_HEIGHT as f64;
+        let x = point.x as f64 - box_width / 2.0;
+        let y = point.y as f64 - box_height / 2.0;
+        let center_x = point.x as f64;
+        let right = x + box_width - 30.0;
+        let palette = &self.theme.front;
+
+        self.context.set_fill_style_str(&palette.panel);
         self.context.begin_path();
-        let _ = self.context.round_rect_with_f64(
-            -(width / 2.0) as f64,
-            -(height / 2.0 + 350.0) as f64,
-            width as f64,
-            height as f64,
-            radius as f64,
+        self.context.round_rect(
+            x,
+            y,
+            box_width,
+            box_height,
+            FLASH_CARD_CORNER_RADIUS as f64,
         );
-
-        let _ = self.context.close_path();
         self.context.fill();
 
-        // 5. 装飾パターンを描画（表裏で異なるデザイン）
-        let is_front = color.get() == Color::Green.get();
-        self.draw_card_decoration(width, height, is_front);
+        self.context.set_line_width(1.5);
+        self.context.set_stroke_style_str(&palette.ink(0.3));
+        self.context.begin_path();
+        self.context.round_rect(
+            x + 10.0,
+            y + 10.0,
+            box_width - 20.0,
+            box_height - 20.0,
+            8.0,
+        );
+        self.context.stroke();
 
-        // 6. テキストを描画（同じ回転座標系で）
         self.context.set_text_align("center");
-        self.context.set_font("18px MyFont");
-        self.context.set_fill_style_str("white");
-        let _ = self.context.fill_text(&text, 0.0, -340.0);
-
-        // 7. 裏面 語源テキストを描画
-        if !is_front {
-            self.context.set_text_align("center");
-            self.context.set_font("14px MyFont");
-            self.context.set_fill_style_str("rgba(255, 255, 255, 0.8)");
-
-            // 各語源テキストを順番に描画（Y座標を下にずらしていく）
-            let start_y = -300.0; // 開始Y座標
-            let line_height = 20.0; // 行間
-
-            for (i, etym) in etymology.iter().enumerate() {
-                self.context.set_text_align("left");
-                let y = start_y + (i as f64) * line_height;
-                let _ = self.context.fill_text(etym, -140.0, y);
+        self.context.set_fill_style_str(&palette.ink(1.0));
+        self.context.set_font(&self.theme.font(20.0));
+        self.context.fill_text("設定", center_x, y + 44.0);
+
+        for (i, (label, value, control)) in rows.iter().enumerate() {
+            let row_y = y + SETTINGS_ROW_TOP + SETTINGS_ROW_HEIGHT * i as f64;
+            let middle = row_y + (SETTINGS_ROW_HEIGHT - 4.0) / 2.0;
+            if i == selected {
+                self.context.set_fill_style_str(&palette.accent);
+                self.context.begin_path();
+                self.context.round_rect(
+                    x + 20.0,
+                    row_y,
+                    box_width - 40.0,
+                    SETTINGS_ROW_HEIGHT - 4.0,
+                    6.0,
+                );
+                self.context.fill();
+            }
+
+            self.context.set_fill_style_str(&palette.ink(1.0));
+            self.context.set_font(&self.theme.font(14.0));
+            if *control == Control::Button {
+                self.context.set_text_align("center");
+                self.context.fill_text(label, center_x, middle + 5.0);
+                continue;
+            }
+            self.context.set_text_align("left");
+            self.context.fill_text(label, x + 30.0, middle + 5.0);
+
+            self.context.set_text_align("right");
+            match *control {
+                Control::Slider(fraction) => {
+                    let left = right - SETTINGS_SLIDER_WIDTH;
+                    self.context.set_line_width(4.0);
+                    self.context.set_stroke_style_str(&palette.ink(0.25));
+                    self.context.begin_path();
+                    self.context.move_to(left, middle);
+                    self.context.line_to(right, middle);
+                    self.context.stroke();
+                    let knob = left + SETTINGS_SLIDER_WIDTH * fraction.clamp(0.0, 1.0);
+                    self.context.set_stroke_style_str(&palette.ink(0.8));
+                    self.context.begin_path();
+                    self.context.move_to(left, middle);
+                    self.context.line_to(knob, middle);
+                    self.context.stroke();
+                    self.context.begin_path();
+                    self.context.arc(knob, middle, 7.0, 0.0, 2.0 * std::f64::consts::PI);
+                    self.context.fill();
+
+                    self.context.set_font(&self.theme.font(12.0));
+                    self.context.fill_text(value, left - 12.0, middle + 4.0);
+                }
+                Control::Toggle(on) => {
+                    let (width, height) = (36.0, 18.0);
+                    self.context.set_fill_style_str(&palette.ink(if on { 0.8 } else { 0.25 }));
+                    self.context.begin_path();
+                    self.context.round_rect(right - width, middle - height / 2.0, width, height, height / 2.0);
+                    self.context.fill();
+                    let knob = if on { right - height / 2.0 } else { right - width + height / 2.0 };
+                    self.context.set_fill_style_str(&palette.panel);
+                    self.context.begin_path();
+                    self.context.arc(knob, middle, height / 2.0 - 3.0, 0.0, 2.0 * std::f64::consts::PI);
+                    self.context.fill();
+                }
+                Control::Choice => {
+                    self.context.fill_text(value, right, middle + 5.0);
+                }
+                Control::Button => {}
             }
         }
 
+        // 操作説明
+        self.context.set_text_align("center");
+        self.context.set_font(&self.theme.font(12.0));
+        self.context.set_fill_style_str(&palette.ink(0.6));
+        self.context.fill_text_with_max_width(
+            "↑↓ 選ぶ   ←→ / スワイプ / タップ 変える   Esc 閉じる",
+            center_x,
+            y + box_height - 40.0,
+            box_width - 40.0,
+        );
+        self.context.fill_text_with_max_width(
+            "枚数と向きは次のセッションから",
+            center_x,
+            y + box_height - 22.0,
+            box_width - 40.0,
+        );
+
         self.context.restore();
     }
 
-    // 裏面用のケルト風パターンを描画（トリケトラ＋円形デザイン）
-    fn draw_constellation_pattern(
-        &self,
-        card_left: f64,
-        card_top: f64,
-        center_x: f64,
-        center_y: f64,
-        width: f32,
-        height: f32,
-    ) {
-        // 二重枠（ケルト風）
+    /// 設定画面を開くボタン（円の中に歯車）
+    pub fn draw_settings_button(&self, point: &Point, radius: f32, color: &str) {
+        let (cx, cy, r) = (point.x as f64, point.y as f64, radius as f64);
+        self.context.save();
+        self.context.set_fill_style_str("rgba(0, 0, 0, 0.25)");
+        self.context.set_stroke_style_str(color);
         self.context.set_line_width(2.0);
-        self.context
-            .set_stroke_style_str("rgba(255, 255, 255, 0.4)");
-
-        let border_offset = 10.0;
         self.context.begin_path();
-        let _ = self.context.round_rect_with_f64(
-            card_left + border_offset,
-            card_top + border_offset,
-            width as f64 - border_offset * 2.0,
-            height as f64 - border_offset * 2.0,
-            8.0,
-        );
+        self.context.arc(cx, cy, r, 0.0, 2.0 * std::f64::consts::PI);
+        self.context.fill();
         self.context.stroke();
 
-        // 中央の円形ケルトノット
+        // 歯車の歯と輪
         self.context.set_line_width(3.0);
-        self.context
-            .set_stroke_style_str("rgba(255, 255, 255, 0.6)");
-
-        // 外側の円
+        for i in 0..8 {
+            let angle = std::f64::consts::PI / 4.0 * i as f64;
+            self.context.begin_path();
+            self.context.move_to(cx + r * 0.4 * angle.cos(), cy + r * 0.4 * angle.sin());
+            self.context.line_to(cx + r * 0.65 * angle.cos(), cy + r * 0.65 * angle.sin());
+            self.context.stroke();
+        }
+        self.context.set_line_width(2.0);
         self.context.begin_path();
-        let _ = self
-            .context
-            .arc(center_x, center_y, 70.0, 0.0, 2.0 * std::f64::consts::PI);
+        self.context.arc(cx, cy, r * 0.4, 0.0, 2.0 * std::f64::consts::PI);
         self.context.stroke();
-
-        // 内側の円
         self.context.begin_path();
-        let _ = self
-            .context
-            .arc(center_x, center_y, 50.0, 0.0, 2.0 * std::f64::consts::PI);
+        self.context.arc(cx, cy, r * 0.15, 0.0, 2.0 * std::f64::consts::PI);
         self.context.stroke();
-
-        // トリケトラ（3つの葉のケルトシンボル）を中央に描画
-        self.draw_triquetra(center_x, center_y, 40.0);
-
-        // 四隅にケルト結び目を配置
-        let corner_positions = [
-            (card_left + 35.0, card_top + 35.0),
-            (card_left + width as f64 - 35.0, card_top + 35.0),
-            (card_left + 35.0, card_top + height as f64 - 35.0),
-            (
-                card_left + width as f64 - 35.0,
-                card_top + height as f64 - 35.0,
-            ),
-        ];
-
-        for &(cx, cy) in corner_positions.iter() {
-            self.draw_celtic_knot_small(cx, cy, 15.0);
-        }
+        self.context.restore();
     }
 
-    // トリケトラ（三位一体のケルトシンボル）を描画
-    fn draw_triquetra(&self, cx: f64, cy: f64, radius: f64) {
-        self.context.set_line_width(2.5);
-        self.context
-            .set_stroke_style_str("rgba(255, 255, 255, 0.8)");
-        self.context.set_fill_style_str("rgba(255, 255, 255, 0.2)");
-
-        // 3つの円弧でトリケトラを形成
-        for i in 0..3 {
-            let angle = (i as f64) * 2.0 * std::f64::consts::PI / 3.0 - std::f64::consts::PI / 2.0;
-            let arc_cx = cx + radius * 0.5 * angle.cos();
-            let arc_cy = cy + radius * 0.5 * angle.sin();
+    /// 画面下部に短い通知を表示（ゲームパッドの接続など）
+    pub fn draw_notice(&self, message: &str, alpha: f32) {
+        self.context.save();
+        self.context.set_global_alpha(alpha as f64);
+        self.context.set_font(&self.theme.font(14.0));
+        let width = self.context.measure_text(message)
+            + 32.0;
+        let height = 32.0;
+        let x = (SCREEN_WIDTH as f64 - width) / 2.0;
+        let y = GAMEPAD_NOTICE_Y as f64 - height / 2.0;
+
+        self.context.set_fill_style_str("rgba(0, 0, 0, 0.7)");
+        self.context.begin_path();
+        self.context.round_rect(x, y, width, height, height / 2.0);
+        self.context.fill();
 
-            self.context.begin_path();
-            let _ = self.context.arc(
-                arc_cx,
-                arc_cy,
-                radius * 0.8,
-                angle + std::f64::consts::PI * 0.6,
-                angle + std::f64::consts::PI * 1.4,
-            );
-            self.context.stroke();
-        }
+        self.context.set_fill_style_str("white");
+        self.context.set_text_align("center");
+        self.context.set_text_baseline("middle");
+        self
+            .context
+            .fill_text(message, SCREEN_WIDTH as f64 / 2.0, GAMEPAD_NOTICE_Y as f64);
+        self.context.restore();
+    }
 
-        // 中央の小さな三角形
+    /// 直前のスワイプを取り消すボタン（円の中に戻る矢印）
+    pub fn draw_undo_button(&self, point: &Point, radius: f32, color: &str) {
+        let (cx, cy, r) = (point.x as f64, point.y as f64, radius as f64);
+        self.context.save();
+        self.context.set_fill_style_str("rgba(0, 0, 0, 0.25)");
+        self.context.set_stroke_style_str(color);
+        self.context.set_line_width(2.0);
         self.context.begin_path();
-        for i in 0..3 {
-            let angle = (i as f64) * 2.0 * std::f64::consts::PI / 3.0 - std::f64::consts::PI / 2.0;
-            let x = cx + radius * 0.25 * angle.cos();
-            let y = cy + radius * 0.25 * angle.sin();
-
-            if i == 0 {
-                self.context.move_to(x, y);
-            } else {
-                self.context.line_to(x, y);
-            }
-        }
-        let _ = self.context.close_path();
+        self.context.arc(cx, cy, r, 0.0, 2.0 * std::f64::consts::PI);
         self.context.fill();
         self.context.stroke();
+
+        // 反時計回りの矢印
+        let arrow_r = r * 0.5;
+        let start = -std::f64::consts::PI * 0.9;
+        self.context.begin_path();
+        self.context.arc(cx, cy, arrow_r, start, std::f64::consts::PI * 0.6);
+        self.context.stroke();
+        let (hx, hy) = (cx + arrow_r * start.cos(), cy + arrow_r * start.sin());
+        self.context.begin_path();
+        self.context.move_to(hx - r * 0.25, hy - r * 0.05);
+        self.context.line_to(hx, hy);
+        self.context.line_to(hx + r * 0.05, hy - r * 0.3);
+        self.context.stroke();
+        self.context.restore();
     }
 
-    // 小さなケルト結び目を描画
-    fn draw_celtic_knot_small(&self, cx: f64, cy: f64, size: f64) {
-        self.context.set_line_width(1.5);
+    /// 左上に FPS・ティックなどのデバッグ情報を表示
+    pub fn draw_debug_overlay(&self, lines: &[String]) {
+        self.context.save();
+        self.context.set_fill_style_str("rgba(0, 0, 0, 0.6)");
         self.context
-            .set_stroke_style_str("rgba(255, 255, 255, 0.5)");
-
-        // 4つの円弧で結び目を形成
-        for i in 0..4 {
-            let angle = (i as f64) * std::f64::consts::PI / 2.0;
-            let start_x = cx + size * angle.cos();
-            let start_y = cy + size * angle.sin();
-
-            self.context.begin_path();
-            let _ = self.context.arc(
-                start_x,
-                start_y,
-                size * 0.6,
-                angle + std::f64::consts::PI,
-                angle + std::f64::consts::PI * 1.5,
-            );
-            self.context.stroke();
+            .fill_rect(4.0, 4.0, 170.0, 8.0 + 16.0 * lines.len() as f64);
+        self.context.set_fill_style_str("#7CFC00");
+        self.context.set_font("12px monospace");
+        self.context.set_text_align("left");
+        self.context.set_text_baseline("top");
+        for (i, line) in lines.iter().enumerate() {
+            self.context.fill_text(line, 10.0, 8.0 + 16.0 * i as f64);
         }
+        self.context.restore();
     }
 
     // 緑色のケルト風メッセージウィンドウを描画（カードと完全に同じデザイン）
@@ -609,26 +1097,27 @@ impl Renderer {
         let x = point.x as f64 - box_width / 2.0;
         let y = point.y as f64 - box_height / 2.0;
         let radius = 10.0; // FLASH_CARD_CORNER_RADIUS
+        let palette = &self.theme.front;
 
-        // 緑色の背景（カードと同じ色）
-        self.context.set_fill_style_str(&Color::Green.get());
+        // 表面と同じ色の背景
+        self.context.set_fill_style_str(&palette.panel);
         self.context.begin_path();
-        let _ = self.context.round_rect_with_f64(x, y, box_width, box_height, radius);
+        self.context.round_rect(x, y, box_width, box_height, radius);
         self.context.fill();
 
         // カードと完全に同じケルト風装飾を描画
-        self.context.set_fill_style_str("rgba(255, 255, 255, 0.3)");
-        self.context.set_stroke_style_str("rgba(255, 255, 255, 0.5)");
+        self.context.set_fill_style_str(&palette.ink(0.3));
+        self.context.set_stroke_style_str(&palette.ink(0.5));
         self.context.set_line_width(2.5);
 
         // 背景全体にケルト文様の枠線を描画（カードと同じ）
         let border_offset = 10.0;
         self.context.set_line_width(1.5);
-        self.context.set_stroke_style_str("rgba(255, 255, 255, 0.2)");
+        self.context.set_stroke_style_str(&palette.ink(0.2));
 
         // 外側の二重線
         self.context.begin_path();
-        let _ = self.context.round_rect_with_f64(
+        self.context.round_rect(
             x + border_offset,
             y + border_offset,
             box_width - border_offset * 2.0,
@@ -639,7 +1128,7 @@ impl Renderer {
 
         // 内側の二重線
         self.context.begin_path();
-        let _ = self.context.round_rect_with_f64(
+        self.context.round_rect(
             x + border_offset + 4.0,
             y + border_offset + 4.0,
             box_width - (border_offset + 4.0) * 2.0,
@@ -659,7 +1148,7 @@ impl Renderer {
         ];
 
         for &(cx, cy) in corners.iter() {
-            self.draw_celtic_knot_small(cx, cy, knot_size);
+            celtic_knot_small(&*self.context, palette, cx, cy, knot_size);
         }
 
         // 中央上部に円形の装飾パターンを追加（カードと同じスタイル）
@@ -668,26 +1157,26 @@ impl Renderer {
         let circle_radius = 50.0;
 
         // 外側の円
-        self.context.set_stroke_style_str("rgba(255, 255, 255, 0.3)");
+        self.context.set_stroke_style_str(&palette.ink(0.3));
         self.context.set_line_width(3.0);
         self.context.begin_path();
-        let _ = self.context.arc(center_x, center_y_top, circle_radius, 0.0, 2.0 * std::f64::consts::PI);
+        self.context.arc(center_x, center_y_top, circle_radius, 0.0, 2.0 * std::f64::consts::PI);
         self.context.stroke();
 
         // 内側の円
         self.context.set_line_width(2.0);
         self.context.begin_path();
-        let _ = self.context.arc(center_x, center_y_top, circle_radius * 0.7, 0.0, 2.0 * std::f64::consts::PI);
+        self.context.arc(center_x, center_y_top, circle_radius * 0.7, 0.0, 2.0 * std::f64::consts::PI);
         self.context.stroke();
 
         // 中心の小さい円
         self.context.set_line_width(1.5);
         self.context.begin_path();
-        let _ = self.context.arc(center_x, center_y_top, circle_radius * 0.3, 0.0, 2.0 * std::f64::consts::PI);
+        self.context.arc(center_x, center_y_top, circle_radius * 0.3, 0.0, 2.0 * std::f64::consts::PI);
         self.context.stroke();
 
         // メッセージエリアを囲む装飾的なケルトラインを追加
-        self.context.set_stroke_style_str("rgba(255, 255, 255, 0.25)");
+        self.context.set_stroke_style_str(&palette.ink(0.25));
         self.context.set_line_width(1.5);
 
         // 上部の装飾ライン
@@ -706,84 +1195,320 @@ impl Renderer {
 
         // メッセージテキストを描画（グロー効果付き）
         self.context.set_shadow_blur(10.0);
-        self.context.set_shadow_color("#72F285");
+        self.context.set_shadow_color(&palette.accent);
         self.context.set_shadow_offset_x(0.0);
         self.context.set_shadow_offset_y(0.0);
 
-        self.context.set_fill_style_str("white");
+        self.context.set_fill_style_str(&palette.ink(1.0));
         self.context.set_text_align("center");
-        self.context.set_font("24px MyFont");
-
-        // カードの中央にメッセージを配置
-        let text_y = y + box_height / 2.0;
-        let _ = self.context.fill_text(message, center_x, text_y);
+        self.context.set_font(&self.theme.font(24.0));
+
+        // カードの中央にメッセージを配置（改行ごとに1行ずつ、全体を中央揃え）
+        let line_height = 36.0;
+        let lines: Vec<&str> = message.lines().collect();
+        let text_y = y + box_height / 2.0 - line_height * (lines.len().max(1) - 1) as f64 / 2.0;
+        for (i, line) in lines.iter().enumerate() {
+            self.context.fill_text(line, center_x, text_y + line_height * i as f64);
+        }
 
         self.context.restore();
     }
 }
 
+/// ツリー図の1段に n 個のノードを並べたときの x 座標（カード中心を 0 とする）
+fn tree_row_positions(n: usize) -> Vec<f64> {
+    let step = ETYMOLOGY_TREE_WIDTH / n.max(1) as f64;
+    (0..n)
+        .map(|i| -ETYMOLOGY_TREE_WIDTH / 2.0 + step * (i as f64 + 0.5))
+        .collect()
+}
+
 #[async_trait(?Send)]
 pub trait Game {
     async fn initialize(&self) -> Result<Box<dyn Game>>;
+    /// dt: 1ティックの長さ（ms、GameLoop のティックレートで決まる固定値）
     fn update(
         &mut self,
+        dt: f64,
         keystate: &mut KeyState,
-        touchstate: &mut TouchState,
-        mousestate: &mut MouseState,
+        pointerstate: &mut PointerState,
     );
-    fn draw(&self, renderer: &Renderer);
+    /// interpolation: 前回の update から次の update までの位置（0.0〜1.0）
+    /// ティックの途中のフレームでは、前回と今回の状態の間を補間して描画する
+    fn draw(&self, renderer: &Renderer, interpolation: f64);
+    /// 画面読み上げ用の今の画面の内容と行える操作
+    fn mirror(&self) -> Mirror;
+    /// update の間にたまったブラウザへの依頼（読み上げ・設定の反映・デッキの保存）を取り出す
+    fn take_requests(&mut self) -> Vec<Request>;
+    /// 編集フォームでの操作を渡す（次の update で処理する）
+    fn edit(&mut self, event: EditorEvent);
+    /// 編集フォームに見せる内容（編集画面でなければ None）
+    fn editor(&self) -> Option<EditorView>;
+}
+
+/// ゲームからブラウザへの依頼（ゲームはブラウザの API を直接呼ばず、GameLoop が毎フレーム実行する）
+#[derive(Clone, Debug, PartialEq)]
+pub enum Request {
+    Speak(String),          // 文を読み上げる
+    ApplySettings(Settings), // 設定を保存し、テーマ・動き・スワイプの判定にすぐ反映する
+    SaveDeck(Deck),          // エディタで編集したデッキを保存する
+}
+
+/// 固定ティックの時間の積算
+/// フレームごとの経過時間を積算し、ティックの長さごとに update を何回呼ぶかを決める
+pub struct FixedStep {
+    step: f64,        // 1ティックの長さ（ms）
+    accumulated: f64, // まだ update に使っていない時間（ms）
+    dropped: f64,     // 追いつけずに捨てた時間の合計（ms、デバッグ表示用）
+}
+impl FixedStep {
+    pub fn new(tick_rate: f64) -> Self {
+        FixedStep {
+            step: 1000.0 / tick_rate.max(1.0),
+            accumulated: 0.0,
+            dropped: 0.0,
+        }
+    }
+    pub fn step(&self) -> f64 {
+        self.step
+    }
+    /// フレームの経過時間を加え、このフレームで呼ぶ update の回数を返す
+    /// タブが裏にあった後などの長い経過時間は MAX_FRAME_DELTA に切り詰め、
+    /// 1フレームの update は MAX_UPDATES_PER_FRAME 回までにする（残りは捨てる）
+    pub fn advance(&mut self, delta: f64) -> u32 {
+        self.dropped += (delta - MAX_FRAME_DELTA).max(0.0);
+        self.accumulated += delta.clamp(0.0, MAX_FRAME_DELTA);
+
+        let mut updates = 0;
+        while self.accumulated >= self.step && updates < MAX_UPDATES_PER_FRAME {
+            self.accumulated -= self.step;
+            updates += 1;
+        }
+        if self.accumulated >= self.step {
+            let behind = self.accumulated - self.accumulated % self.step;
+            self.dropped += behind;
+            self.accumulated -= behind;
+        }
+        updates
+    }
+    /// 前回の update から次の update までの位置（0.0〜1.0）
+    pub fn interpolation(&self) -> f64 {
+        (self.accumulated / self.step).clamp(0.0, 1.0)
+    }
+    /// 積算をやめる（一時停止から戻ったとき）
+    pub fn reset(&mut self) {
+        self.accumulated = 0.0;
+    }
+    pub fn dropped(&self) -> f64 {
+        self.dropped
+    }
+}
+
+/// FPS とティック数の計測（デバッグ表示用）
+struct FrameStats {
+    window_start: f64, // 計測を始めた時刻（ms）
+    frames: u32,       // 計測中に描画したフレーム数
+    ticks: u32,        // 計測中に呼んだ update の回数
+    fps: f64,          // 直近1秒のフレーム数
+    tps: f64,          // 直近1秒の update 回数
+}
+impl FrameStats {
+    fn new(now: f64) -> Self {
+        FrameStats {
+            window_start: now,
+            frames: 0,
+            ticks: 0,
+            fps: 0.0,
+            tps: 0.0,
+        }
+    }
+    fn record(&mut self, now: f64, updates: u32) {
+        self.frames += 1;
+        self.ticks += updates;
+        let elapsed = now - self.window_start;
+        if elapsed >= 1000.0 {
+            self.fps = self.frames as f64 * 1000.0 / elapsed;
+            self.tps = self.ticks as f64 * 1000.0 / elapsed;
+            *self = FrameStats {
+                window_start: now,
+                frames: 0,
+                ticks: 0,
+                ..*self
+            };
+        }
+    }
+}
+
+/// GameLoop の設定
+pub struct LoopConfig {
+    pub tick_rate: f64,      // 1秒あたりの update 回数
+    pub debug_overlay: bool, // 起動時に FPS/ティックのデバッグ表示を出すかどうか
+    pub theme: Theme,        // 見た目のテーマ（利用者の設定を反映済み）
+    pub motion: Motion,      // カードの動かし方
+    pub gesture: GestureConfig, // スワイプの判定（感度の設定を反映済み）
+    pub system: SystemPreferences, // 端末の設定（設定画面での変更を反映するときに使う）
+    pub recorder: Option<Rc<RefCell<Recorder>>>, // 入力を記録する（?record で起動したとき）
+}
+impl Default for LoopConfig {
+    fn default() -> Self {
+        LoopConfig {
+            tick_rate: TICK_RATE,
+            debug_overlay: false,
+            theme: Theme::default(),
+            motion: Motion::Full,
+            gesture: GestureConfig::default(),
+            system: SystemPreferences::default(),
+            recorder: None,
+        }
+    }
 }
 
 pub struct GameLoop {
     last_frame: f64,
-    accumulated_delta: f64,
+    timestep: FixedStep,
 }
 type SharedLoopClosure = Rc<RefCell<Option<LoopClosure>>>;
 
 impl GameLoop {
-    pub async fn start(game: impl Game + 'static) -> Result<()> {
+    pub async fn start(game: impl Game + 'static, config: LoopConfig) -> Result<()> {
         let mut keyevent_receiver = prepare_input()?;
-        let mut touch_receiver = prepare_touch_input()?;
-        let mut mouse_receiver = prepare_mouse_input()?;
+        let mut pointer_receiver = prepare_pointer_input()?;
+        let mut gamepad_receiver = prepare_gamepad()?;
+        let mut motion_receiver = prepare_motion()?;
+        let (dom_mirror, mut a11y_receiver) = DomMirror::new()?;
+        let (dom_editor, mut editor_receiver) = DomEditor::new()?;
         let mut game = game.initialize().await?;
         let mut game_loop = GameLoop {
-            last_frame: browser::now()?.into(),
-            accumulated_delta: 0.0,
+            last_frame: browser::now()?,
+            timestep: FixedStep::new(config.tick_rate),
         };
 
-        let renderer = Renderer {
-            context: browser::context()?,
-        };
+        let mut renderer = Renderer::new(
+            Rc::new(CanvasBackend::new(browser::canvas()?, browser::context()?)),
+            config.theme,
+        );
+        renderer.set_motion(config.motion);
+
+        // 画面サイズ・向き・devicePixelRatio の変化に合わせて canvas を作り直す
+        let mut viewport = Viewport::measure()?;
+        viewport.resize_canvas()?;
+        let resized = prepare_resize()?;
+
+        // タブが裏に回っている間は止め、戻ったときに溜まった時間で update が連続しないようにする
+        let resumed = prepare_visibility()?;
 
         let f: SharedLoopClosure = Rc::new(RefCell::new(None));
         let g = f.clone();
 
         let mut keystate = KeyState::new();
-        let mut touchstate = TouchState::new(
-            browser::canvas()?.offset_left(),
-            browser::canvas().unwrap().client_width(),
-            browser::canvas().unwrap().client_height(),
-        );
-        let mut mousestate = MouseState::new(browser::canvas()?.offset_left());
+        let mut pointerstate = PointerState::new();
+        pointerstate.set_gesture_config(config.gesture);
+        let system = config.system;
+        let mut gamepad = GamepadInput::new();
+        let mut shake = ShakeDetector::new();
+        let mut stats = FrameStats::new(game_loop.last_frame);
+        let mut show_debug = config.debug_overlay;
+        let mut debug_key_held = false;
+        let mut inputs: Vec<InputEvent> = Vec::new();
+        let recorder = config.recorder;
+        let mut mirror = Mirror::default();
+        let mut editor_view: Option<EditorView> = None;
 
         *g.borrow_mut() = Some(browser::create_raf_closure(move |perf: f64| {
-            process_input(&mut keystate, &mut keyevent_receiver);
-            process_touch_input(&mut touchstate, &mut touch_receiver);
-            process_mouse_input(&mut mousestate, &mut mouse_receiver);
+            if resumed.replace(false) {
+                game_loop.last_frame = perf;
+                game_loop.timestep.reset();
+                inputs.push(InputEvent::Clear);
+            }
+            if resized.replace(false)
+                && let Ok(measured) = Viewport::measure()
+            {
+                viewport = measured;
+                let _ = viewport.resize_canvas();
+            }
+            renderer.set_viewport(&viewport);
+
+            process_input(&mut inputs, &mut keyevent_receiver);
+            process_pointer_input(&mut inputs, &mut pointer_receiver, &viewport);
+            process_gamepad_connection(&mut gamepad, &mut gamepad_receiver, perf);
+            gamepad.poll(&mut inputs);
+            process_motion(&mut inputs, &mut shake, &mut motion_receiver);
+            process_a11y(&mut inputs, &mut a11y_receiver);
+            while let Ok(Some(event)) = editor_receiver.try_next() {
+                game.edit(event);
+            }
+            for input in inputs.drain(..) {
+                input.apply(&mut keystate, &mut pointerstate);
+                if let Some(recorder) = &recorder {
+                    recorder.borrow_mut().record(&input, perf);
+                }
+            }
+            pointerstate.tick(perf);
+            // update のないフレームもあるので、押された瞬間はここで自前に判定する
+            let debug_key = keystate.is_pressed(DEBUG_OVERLAY_KEY);
+            if debug_key && !debug_key_held {
+                show_debug = !show_debug;
+            }
+            debug_key_held = debug_key;
 
-            game_loop.accumulated_delta += perf - game_loop.last_frame;
-            while game_loop.accumulated_delta > FRAME_SIZE {
-                game.update(&mut keystate, &mut touchstate, &mut mousestate);
+            let updates = game_loop.timestep.advance(perf - game_loop.last_frame);
+            game_loop.last_frame = perf;
+            for _ in 0..updates {
+                game.update(game_loop.timestep.step(), &mut keystate, &mut pointerstate);
 
-                // 次のフレームのためにクリック/タップフラグをクリア
-                mousestate.clear_just_clicked();
-                touchstate.clear_just_tapped();
+                // 次のティックのためにジェスチャーと押された瞬間のキーをクリア
+                pointerstate.clear_gestures();
+                keystate.clear_just_pressed();
+            }
+            if let Some(recorder) = &recorder {
+                recorder.borrow_mut().advance(updates);
+            }
+            for request in game.take_requests() {
+                match request {
+                    Request::Speak(text) => {
+                        if let Err(err) = browser::speak(&text, SPEECH_LANG) {
+                            error!("Could not speak: {:#?}", err);
+                        }
+                    }
+                    Request::ApplySettings(settings) => {
+                        let _ = browser::save_setting(SETTINGS_STORAGE_KEY, &settings.serialize());
+                        renderer.set_theme(settings.theme(system));
+                        renderer.set_motion(settings.motion(system));
+                        pointerstate.set_gesture_config(settings.gesture_config());
+                    }
+                    Request::SaveDeck(deck) => {
+                        if let Err(err) = browser::save_setting(DECK_STORAGE_KEY, &deck.serialize()) {
+                            error!("Could not save the deck: {:#?}", err);
+                        }
+                    }
+                }
+            }
+            if updates > 0 {
+                let current = game.mirror();
+                if current != mirror {
+                    dom_mirror.update(&mirror, &current);
+                    mirror = current;
+                }
+                let current = game.editor();
+                if current != editor_view {
+                    dom_editor.update(&editor_view, &current);
+                    editor_view = current;
+                }
+            }
 
-                game_loop.accumulated_delta -= FRAME_SIZE;
+            game.draw(&renderer, game_loop.timestep.interpolation());
+            if let Some((text, alpha)) = gamepad.notice(perf) {
+                renderer.draw_notice(text, alpha);
+            }
+            stats.record(perf, updates);
+            if show_debug {
+                renderer.draw_debug_overlay(&[
+                    format!("FPS {:.1}", stats.fps),
+                    format!("TPS {:.1} / {:.0}", stats.tps, 1000.0 / game_loop.timestep.step()),
+                    format!("updates {}  lerp {:.2}", updates, game_loop.timestep.interpolation()),
+                    format!("dropped {:.0} ms", game_loop.timestep.dropped()),
+                ]);
             }
-            let _last_frame = browser::now().unwrap();
-            game.draw(&renderer);
-            game_loop.last_frame = _last_frame;
 
             let _ = browser::request_animation_frame(f.borrow().as_ref().unwrap());
         }));
@@ -797,343 +1522,360 @@ impl GameLoop {
     }
 }
 
-pub struct TouchState {
-    x: i32,        // current client_x
-    y: i32,        // current client_y
-    start_x: i32,  // touch start x position
-    start_y: i32,  // touch start y position
-    s: bool,       // true: pressed, false: unpressed
-    offset_x: i32, // Canvas offset
-    screen_width: i32,
-    screen_height: i32,
-    just_tapped: bool, // タップが検出されたフレームでのみtrue
-}
-impl TouchState {
-    fn new(offset_x: i32, screen_width: i32, screen_height: i32) -> Self {
-        return TouchState {
-            x: 0,
-            y: 0,
-            start_x: 0,
-            start_y: 0,
-            s: false,
-            offset_x: offset_x,
-            screen_width: screen_width,
-            screen_height: screen_height,
-            just_tapped: false,
-        };
-    }
-    pub fn is_swiping_left(&self) -> bool {
-        if self.s {
-            let delta_x = self.x - self.start_x;
-            // 左方向に30px以上スワイプしている場合
-            return delta_x < -SWIPING_JUDEGE_DISTANCE;
-        }
-        false
-    }
-    pub fn is_swiping_right(&self) -> bool {
-        if self.s {
-            let delta_x = self.x - self.start_x;
-            // 右方向に30px以上スワイプしている場合
-            return delta_x > SWIPING_JUDEGE_DISTANCE;
-        }
-        false
-    }
-    pub fn is_tapped(&self) -> bool {
-        // タップが検出されたフレームでのみtrueを返す（エッジトリガー）
-        self.just_tapped
-    }
-    fn set_pressed(&mut self, _x: i32, _y: i32) {
-        self.x = _x;
-        self.y = _y;
-        self.start_x = _x;
-        self.start_y = _y;
-        self.s = true;
-    }
-    fn set_moved(&mut self, _x: i32, _y: i32) {
-        self.x = _x;
-        self.y = _y;
-    }
-    fn set_released(&mut self) {
-        self.s = false;
-
-        // タップ判定（移動距離が小さい場合）
-        let delta_x = (self.x - self.start_x).abs();
-        let delta_y = (self.y - self.start_y).abs();
-        if delta_x < 10 && delta_y < 10 {
-            // タップが検出されたのでフラグを立てる
-            self.just_tapped = true;
-        }
-    }
-    pub fn clear_just_tapped(&mut self) {
-        self.just_tapped = false;
-    }
-
-    pub fn clear(&mut self) {
-        // delta_x (x - start_x) を0にするため、xとstart_xを同じ値に設定
-        self.x = self.start_x;
-        self.y = self.start_y;
-        self.s = false;
-        self.just_tapped = false;
-    }
-}
-enum TouchPress {
-    TouchStart(TouchEvent),
-    TouchMove(TouchEvent),
-    TouchEnd(TouchEvent),
+/// キーボードのイベントリスナーから入力の処理へ送るキーの押下・解放
+enum KeyPress {
+    KeyUp(web_sys::KeyboardEvent),
+    KeyDown(web_sys::KeyboardEvent),
 }
 
-fn process_touch_input(state: &mut TouchState, touch_receiver: &mut UnboundedReceiver<TouchPress>) {
+fn process_input(inputs: &mut Vec<InputEvent>, keyevent_receiver: &mut UnboundedReceiver<KeyPress>) {
     loop {
-        match touch_receiver.try_next() {
+        match keyevent_receiver.try_next() {
             Ok(None) => break,
             Err(_err) => break,
             Ok(Some(evt)) => match evt {
-                TouchPress::TouchStart(evt) => {
-                    let _list: TouchList = evt.touches();
-                    let _touch: Touch = _list.item(0).unwrap();
-                    state.set_pressed(_touch.client_x(), _touch.client_y());
-                }
-                TouchPress::TouchMove(evt) => {
-                    let _list: TouchList = evt.touches();
-                    let _touch: Touch = _list.item(0).unwrap();
-                    state.set_moved(_touch.client_x(), _touch.client_y());
-                }
-                TouchPress::TouchEnd(evt) => {
-                    // changedTouchesから座標を取得（canvas外でreleaseされた場合に対応）
-                    let _list: TouchList = evt.changed_touches();
-                    if let Some(_touch) = _list.item(0) {
-                        state.set_moved(_touch.client_x(), _touch.client_y());
-                    } else {
-                        // 座標が取得できない場合、delta_xを0にするためxをstart_xに設定
-                        state.x = state.start_x;
-                        state.y = state.start_y;
-                    }
-                    state.set_released();
-                }
+                KeyPress::KeyUp(evt) => inputs.push(InputEvent::Key(KeyInput::Up { code: evt.code() })),
+                KeyPress::KeyDown(evt) => inputs.push(InputEvent::Key(KeyInput::Down {
+                    code: evt.code(),
+                    repeat: evt.repeat(),
+                })),
             },
         };
     }
 }
 
-// For Touch Input
-fn prepare_touch_input() -> Result<UnboundedReceiver<TouchPress>> {
-    let (touch_start_sender, touch_receiver) = unbounded();
-    let touch_start_sender = Rc::new(RefCell::new(touch_start_sender));
-    let touch_move_sender = Rc::clone(&touch_start_sender);
-    let touch_end_sender = Rc::clone(&touch_start_sender);
-    let ontouchstart = browser::closure_wrap(Box::new(move |_touchcode: TouchEvent| {
-        _touchcode.prevent_default();
-        let _ = touch_start_sender
-            .borrow_mut()
-            .start_send(TouchPress::TouchStart(_touchcode));
-    }) as Box<dyn FnMut(TouchEvent)>);
-    let ontouchmove = browser::closure_wrap(Box::new(move |_touchcode: TouchEvent| {
-        _touchcode.prevent_default();
-        let _ = touch_move_sender
-            .borrow_mut()
-            .start_send(TouchPress::TouchMove(_touchcode));
-    }) as Box<dyn FnMut(TouchEvent)>);
-    let ontouchend = browser::closure_wrap(Box::new(move |_touchcode: TouchEvent| {
-        _touchcode.prevent_default();
-        let _ = touch_end_sender
-            .borrow_mut()
-            .start_send(TouchPress::TouchEnd(_touchcode));
-    }) as Box<dyn FnMut(TouchEvent)>);
-
-    browser::canvas()?.set_ontouchstart(Some(ontouchstart.as_ref().unchecked_ref()));
-    browser::canvas()?.set_ontouchmove(Some(ontouchmove.as_ref().unchecked_ref()));
-    browser::canvas()?.set_ontouchend(Some(ontouchend.as_ref().unchecked_ref()));
-    ontouchstart.forget();
-    ontouchmove.forget();
-    ontouchend.forget();
-
-    Ok(touch_receiver)
-}
-
-pub struct MouseState {
-    x: i32,             // current mouse x
-    y: i32,             // current mouse y
-    start_x: i32,       // mouse down x position
-    start_y: i32,       // mouse down y position
-    pressed: bool,      // true: mouse button pressed
-    offset_x: i32,      // Canvas offset
-    just_clicked: bool, // クリックが検出されたフレームでのみtrue
+/// ゲームパッドの入力
+/// 毎フレーム Gamepad API をポーリングし、ボタンの変化をキーを押した・離した入力にする
+struct GamepadInput {
+    held: Vec<&'static str>, // 前回のポーリングで押されていたボタン（全ゲームパッド分）
+    notice: Option<(String, f64)>, // 接続・切断の通知と表示を終える時刻
 }
-impl MouseState {
-    fn new(offset_x: i32) -> Self {
-        return MouseState {
-            x: 0,
-            y: 0,
-            start_x: 0,
-            start_y: 0,
-            pressed: false,
-            offset_x: offset_x,
-            just_clicked: false,
-        };
-    }
-    pub fn is_dragging_left(&self) -> bool {
-        if self.pressed {
-            let delta_x = self.x - self.start_x;
-            // 左方向に30px以上ドラッグしている場合
-            return delta_x < -30;
+impl GamepadInput {
+    fn new() -> Self {
+        GamepadInput {
+            held: Vec::new(),
+            notice: None,
         }
-        false
     }
-    pub fn is_dragging_right(&self) -> bool {
-        if self.pressed {
-            let delta_x = self.x - self.start_x;
-            // 右方向に30px以上ドラッグしている場合
-            return delta_x > 30;
-        }
-        false
-    }
-    pub fn is_clicked(&self) -> bool {
-        // クリックが検出されたフレームでのみtrueを返す（エッジトリガー）
-        self.just_clicked
-    }
-    fn set_pressed(&mut self, _x: i32, _y: i32) {
-        self.x = _x;
-        self.y = _y;
-        self.start_x = _x;
-        self.start_y = _y;
-        self.pressed = true;
-    }
-    fn set_moved(&mut self, _x: i32, _y: i32) {
-        self.x = _x;
-        self.y = _y;
-    }
-    fn set_released(&mut self) {
-        self.pressed = false;
-
-        // クリック判定（移動距離が小さい場合）
-        let delta_x = (self.x - self.start_x).abs();
-        let delta_y = (self.y - self.start_y).abs();
-        if delta_x < 10 && delta_y < 10 {
-            // クリックが検出されたのでフラグを立てる
-            self.just_clicked = true;
+    fn poll(&mut self, inputs: &mut Vec<InputEvent>) {
+        let mut held: Vec<&'static str> = Vec::new();
+        for gamepad in browser::gamepads().unwrap_or_default() {
+            let snapshot = GamepadSnapshot {
+                buttons: gamepad
+                    .buttons()
+                    .iter()
+                    .map(|button| {
+                        button
+                            .dyn_into::<web_sys::GamepadButton>()
+                            .map(|button| button.pressed())
+                            .unwrap_or(false)
+                    })
+                    .collect(),
+                axes: gamepad
+                    .axes()
+                    .iter()
+                    .map(|axis| axis.as_f64().unwrap_or(0.0))
+                    .collect(),
+            };
+            for code in held_codes(&snapshot) {
+                if !held.contains(&code) {
+                    held.push(code);
+                }
+            }
         }
-    }
-    pub fn clear_just_clicked(&mut self) {
-        self.just_clicked = false;
-    }
 
-    pub fn clear(&mut self) {
-        // delta_x (x - start_x) を0にするため、xとstart_xを同じ値に設定
-        self.x = self.start_x;
-        self.y = self.start_y;
-        self.pressed = false;
-        self.just_clicked = false;
+        for code in held.iter().filter(|code| !self.held.contains(code)) {
+            inputs.push(InputEvent::Key(KeyInput::Down {
+                code: code.to_string(),
+                repeat: false,
+            }));
+        }
+        for code in self.held.iter().filter(|code| !held.contains(code)) {
+            inputs.push(InputEvent::Key(KeyInput::Up {
+                code: code.to_string(),
+            }));
+        }
+        self.held = held;
     }
-}
-
-pub struct KeyState {
-    pressed_keys: HashMap<String, web_sys::KeyboardEvent>,
-}
-
-impl KeyState {
-    fn new() -> Self {
-        return KeyState {
-            pressed_keys: HashMap::new(),
+    fn notify(&mut self, connection: GamepadConnection, now: f64) {
+        let text = match connection {
+            GamepadConnection::Connected(id) => format!("コントローラーを接続しました: {}", id),
+            GamepadConnection::Disconnected(id) => format!("コントローラーが外れました: {}", id),
         };
+        self.notice = Some((text, now + GAMEPAD_NOTICE_DURATION));
     }
-    pub fn is_pressed(&self, code: &str) -> bool {
-        self.pressed_keys.contains_key(code)
-    }
-
-    fn set_pressed(&mut self, code: &str, event: web_sys::KeyboardEvent) {
-        self.pressed_keys.insert(code.into(), event);
-    }
-
-    fn set_released(&mut self, code: &str) {
-        self.pressed_keys.remove(code.into());
-    }
-
-    pub fn clear(&mut self) {
-        self.pressed_keys.clear();
+    /// 表示中の通知（残り時間が短くなると薄くなる）
+    fn notice(&self, now: f64) -> Option<(&str, f32)> {
+        let (text, until) = self.notice.as_ref()?;
+        let remaining = until - now;
+        if remaining <= 0.0 {
+            return None;
+        }
+        Some((text.as_str(), (remaining / 500.0).min(1.0) as f32))
     }
 }
 
-enum KeyPress {
-    KeyUp(web_sys::KeyboardEvent),
-    KeyDown(web_sys::KeyboardEvent),
+enum GamepadConnection {
+    Connected(String),
+    Disconnected(String),
 }
 
-fn process_input(state: &mut KeyState, keyevent_receiver: &mut UnboundedReceiver<KeyPress>) {
-    loop {
-        match keyevent_receiver.try_next() {
-            Ok(None) => break,
-            Err(_err) => break,
-            Ok(Some(evt)) => match evt {
-                KeyPress::KeyUp(evt) => state.set_released(&evt.code()),
-                KeyPress::KeyDown(evt) => state.set_pressed(&evt.code(), evt),
-            },
-        };
+fn process_gamepad_connection(
+    gamepad: &mut GamepadInput,
+    receiver: &mut UnboundedReceiver<GamepadConnection>,
+    now: f64,
+) {
+    while let Ok(Some(connection)) = receiver.try_next() {
+        gamepad.notify(connection, now);
     }
 }
 
-// For Mouse Input
-enum MousePress {
-    MouseDown(web_sys::MouseEvent),
-    MouseMove(web_sys::MouseEvent),
-    MouseUp(web_sys::MouseEvent),
+// For Pointer Input (touch / mouse / pen)
+enum PointerPress {
+    Down(PointerEvent),
+    Move(PointerEvent),
+    Up(PointerEvent),
+    Cancel(PointerEvent),
 }
 
-fn process_mouse_input(state: &mut MouseState, mouse_receiver: &mut UnboundedReceiver<MousePress>) {
+fn process_pointer_input(
+    inputs: &mut Vec<InputEvent>,
+    pointer_receiver: &mut UnboundedReceiver<PointerPress>,
+    viewport: &Viewport,
+) {
     loop {
-        match mouse_receiver.try_next() {
+        match pointer_receiver.try_next() {
             Ok(None) => break,
             Err(_err) => break,
             Ok(Some(evt)) => match evt {
-                MousePress::MouseDown(evt) => {
-                    state.set_pressed(evt.client_x(), evt.client_y());
+                PointerPress::Down(evt) => {
+                    let (x, y) = viewport.to_logical(evt.client_x(), evt.client_y());
+                    inputs.push(InputEvent::Pointer(PointerAction::Down {
+                        id: evt.pointer_id(),
+                        x,
+                        y,
+                        time: evt.time_stamp(),
+                    }));
                 }
-                MousePress::MouseMove(evt) => {
-                    if state.pressed {
-                        state.set_moved(evt.client_x(), evt.client_y());
-                    }
+                PointerPress::Move(evt) => {
+                    let (x, y) = viewport.to_logical(evt.client_x(), evt.client_y());
+                    inputs.push(InputEvent::Pointer(PointerAction::Move {
+                        id: evt.pointer_id(),
+                        x,
+                        y,
+                        time: evt.time_stamp(),
+                    }));
                 }
-                MousePress::MouseUp(evt) => {
-                    // canvas外でreleaseされた場合にも座標を更新
-                    state.set_moved(evt.client_x(), evt.client_y());
-                    state.set_released();
+                PointerPress::Up(evt) => {
+                    // pointer capture により canvas 外で離された場合も座標が届く
+                    let (x, y) = viewport.to_logical(evt.client_x(), evt.client_y());
+                    inputs.push(InputEvent::Pointer(PointerAction::Up {
+                        id: evt.pointer_id(),
+                        x,
+                        y,
+                        time: evt.time_stamp(),
+                    }));
                 }
+                PointerPress::Cancel(evt) => inputs.push(InputEvent::Pointer(PointerAction::Cancel {
+                    id: evt.pointer_id(),
+                })),
             },
         };
     }
 }
 
-fn prepare_mouse_input() -> Result<UnboundedReceiver<MousePress>> {
-    let (mouse_down_sender, mouse_receiver) = unbounded();
-    let mouse_down_sender = Rc::new(RefCell::new(mouse_down_sender));
-    let mouse_move_sender = Rc::clone(&mouse_down_sender);
-    let mouse_up_sender = Rc::clone(&mouse_down_sender);
+fn prepare_pointer_input() -> Result<UnboundedReceiver<PointerPress>> {
+    let (pointer_down_sender, pointer_receiver) = unbounded();
+    let pointer_down_sender = Rc::new(RefCell::new(pointer_down_sender));
+    let pointer_move_sender = Rc::clone(&pointer_down_sender);
+    let pointer_up_sender = Rc::clone(&pointer_down_sender);
+    let pointer_cancel_sender = Rc::clone(&pointer_down_sender);
 
-    let onmousedown = browser::closure_wrap(Box::new(move |evt: web_sys::MouseEvent| {
-        let _ = mouse_down_sender
+    let onpointerdown = browser::closure_wrap(Box::new(move |evt: PointerEvent| {
+        // マウスは左ボタンのみ
+        if evt.pointer_type() == "mouse" && evt.button() != 0 {
+            return;
+        }
+        evt.prevent_default();
+        // 指やマウスが canvas の外に出ても move/up を受け取る
+        if let Ok(canvas) = browser::canvas() {
+            let _ = canvas.set_pointer_capture(evt.pointer_id());
+        }
+        let _ = pointer_down_sender
             .borrow_mut()
-            .start_send(MousePress::MouseDown(evt));
-    }) as Box<dyn FnMut(web_sys::MouseEvent)>);
+            .start_send(PointerPress::Down(evt));
+    }) as Box<dyn FnMut(PointerEvent)>);
 
-    let onmousemove = browser::closure_wrap(Box::new(move |evt: web_sys::MouseEvent| {
-        let _ = mouse_move_sender
+    let onpointermove = browser::closure_wrap(Box::new(move |evt: PointerEvent| {
+        let _ = pointer_move_sender
             .borrow_mut()
-            .start_send(MousePress::MouseMove(evt));
-    }) as Box<dyn FnMut(web_sys::MouseEvent)>);
+            .start_send(PointerPress::Move(evt));
+    }) as Box<dyn FnMut(PointerEvent)>);
 
-    let onmouseup = browser::closure_wrap(Box::new(move |evt: web_sys::MouseEvent| {
-        let _ = mouse_up_sender
+    let onpointerup = browser::closure_wrap(Box::new(move |evt: PointerEvent| {
+        let _ = pointer_up_sender
             .borrow_mut()
-            .start_send(MousePress::MouseUp(evt));
-    }) as Box<dyn FnMut(web_sys::MouseEvent)>);
+            .start_send(PointerPress::Up(evt));
+    }) as Box<dyn FnMut(PointerEvent)>);
+
+    let onpointercancel = browser::closure_wrap(Box::new(move |evt: PointerEvent| {
+        let _ = pointer_cancel_sender
+            .borrow_mut()
+            .start_send(PointerPress::Cancel(evt));
+    }) as Box<dyn FnMut(PointerEvent)>);
+
+    let canvas = browser::canvas()?;
+    canvas.set_onpointerdown(Some(onpointerdown.as_ref().unchecked_ref()));
+    canvas.set_onpointermove(Some(onpointermove.as_ref().unchecked_ref()));
+    canvas.set_onpointerup(Some(onpointerup.as_ref().unchecked_ref()));
+    canvas.set_onpointercancel(Some(onpointercancel.as_ref().unchecked_ref()));
+    onpointerdown.forget();
+    onpointermove.forget();
+    onpointerup.forget();
+    onpointercancel.forget();
+
+    Ok(pointer_receiver)
+}
+
+// For Resize / Orientation Change / Scroll
+// 変化があったことだけを記録し、実際の再計算は次のフレームで行う
+// （スクロールすると getBoundingClientRect の位置が変わるので同様に扱う）
+fn prepare_resize() -> Result<Rc<Cell<bool>>> {
+    let resized = Rc::new(Cell::new(false));
+    let resize_flag = Rc::clone(&resized);
+    let orientation_flag = Rc::clone(&resized);
+    let scroll_flag = Rc::clone(&resized);
+
+    let onresize = browser::closure_wrap(Box::new(move || {
+        resize_flag.set(true);
+    }) as Box<dyn FnMut()>);
+    let onorientationchange = browser::closure_wrap(Box::new(move || {
+        orientation_flag.set(true);
+    }) as Box<dyn FnMut()>);
+    let onscroll = browser::closure_wrap(Box::new(move || {
+        scroll_flag.set(true);
+    }) as Box<dyn FnMut()>);
+
+    let window = browser::window()?;
+    window.set_onresize(Some(onresize.as_ref().unchecked_ref()));
+    window.set_onorientationchange(Some(onorientationchange.as_ref().unchecked_ref()));
+    window.set_onscroll(Some(onscroll.as_ref().unchecked_ref()));
+    onresize.forget();
+    onorientationchange.forget();
+    onscroll.forget();
+
+    Ok(resized)
+}
+
+/// document.hidden が false に戻ったら true になるフラグ
+fn prepare_visibility() -> Result<Rc<Cell<bool>>> {
+    let resumed = Rc::new(Cell::new(false));
+    let resumed_flag = Rc::clone(&resumed);
+
+    let onvisibilitychange = browser::closure_wrap(Box::new(move || {
+        if let Ok(document) = browser::document()
+            && !document.hidden()
+        {
+            resumed_flag.set(true);
+        }
+    }) as Box<dyn FnMut()>);
+
+    browser::document()?.set_onvisibilitychange(Some(onvisibilitychange.as_ref().unchecked_ref()));
+    onvisibilitychange.forget();
+
+    Ok(resumed)
+}
+
+// For Device Motion（端末を振る操作）
+fn prepare_motion() -> Result<UnboundedReceiver<(f64, f64)>> {
+    let (motion_sender, receiver) = unbounded();
+    let ondevicemotion = browser::closure_wrap(Box::new(move |evt: web_sys::DeviceMotionEvent| {
+        if let Some(x) = evt.acceleration_including_gravity().and_then(|a| a.x())
+            && let Ok(now) = browser::now()
+        {
+            let _ = motion_sender.unbounded_send((x, now));
+        }
+    }) as Box<dyn FnMut(web_sys::DeviceMotionEvent)>);
+
+    browser::window()?
+        .add_event_listener_with_callback("devicemotion", ondevicemotion.as_ref().unchecked_ref())
+        .map_err(|err| anyhow!("Could not listen devicemotion {:#?}", err))?;
+    ondevicemotion.forget();
+
+    Ok(receiver)
+}
+
+/// 加速度センサーの値からシェイクを判定し、SHAKE_CODE を押してすぐ離した入力にする
+fn process_motion(
+    inputs: &mut Vec<InputEvent>,
+    shake: &mut ShakeDetector,
+    receiver: &mut UnboundedReceiver<(f64, f64)>,
+) {
+    while let Ok(Some((acceleration_x, time))) = receiver.try_next() {
+        if shake.handle(acceleration_x, time) {
+            inputs.push(InputEvent::Key(KeyInput::Down {
+                code: SHAKE_CODE.to_string(),
+                repeat: false,
+            }));
+            inputs.push(InputEvent::Key(KeyInput::Up {
+                code: SHAKE_CODE.to_string(),
+            }));
+        }
+    }
+}
 
-    browser::canvas()?.set_onmousedown(Some(onmousedown.as_ref().unchecked_ref()));
-    browser::canvas()?.set_onmousemove(Some(onmousemove.as_ref().unchecked_ref()));
-    browser::canvas()?.set_onmouseup(Some(onmouseup.as_ref().unchecked_ref()));
-    onmousedown.forget();
-    onmousemove.forget();
-    onmouseup.forget();
+/// 画面読み上げ用の操作ボタンが押されたら、その操作のコードを押してすぐ離した入力にする
+fn process_a11y(inputs: &mut Vec<InputEvent>, receiver: &mut UnboundedReceiver<Action>) {
+    while let Ok(Some(action)) = receiver.try_next() {
+        inputs.push(InputEvent::Key(KeyInput::Down {
+            code: action.button_code(),
+            repeat: false,
+        }));
+        inputs.push(InputEvent::Key(KeyInput::Up {
+            code: action.button_code(),
+        }));
+    }
+}
 
-    Ok(mouse_receiver)
+// For Gamepad hot-plug
+fn prepare_gamepad() -> Result<UnboundedReceiver<GamepadConnection>> {
+    let (connected_sender, receiver) = unbounded();
+    let connected_sender = Rc::new(RefCell::new(connected_sender));
+    let disconnected_sender = Rc::clone(&connected_sender);
+    let ongamepadconnected = browser::closure_wrap(Box::new(move |evt: web_sys::GamepadEvent| {
+        if let Some(gamepad) = evt.gamepad() {
+            let _ = connected_sender
+                .borrow_mut()
+                .start_send(GamepadConnection::Connected(gamepad.id()));
+        }
+    }) as Box<dyn FnMut(web_sys::GamepadEvent)>);
+    let ongamepaddisconnected =
+        browser::closure_wrap(Box::new(move |evt: web_sys::GamepadEvent| {
+            if let Some(gamepad) = evt.gamepad() {
+                let _ = disconnected_sender
+                    .borrow_mut()
+                    .start_send(GamepadConnection::Disconnected(gamepad.id()));
+            }
+        }) as Box<dyn FnMut(web_sys::GamepadEvent)>);
+
+    // ongamepadconnected は web-sys では unstable API なので addEventListener で登録する
+    let window = browser::window()?;
+    window
+        .add_event_listener_with_callback(
+            "gamepadconnected",
+            ongamepadconnected.as_ref().unchecked_ref(),
+        )
+        .map_err(|err| anyhow!("Could not listen gamepadconnected {:#?}", err))?;
+    window
+        .add_event_listener_with_callback(
+            "gamepaddisconnected",
+            ongamepaddisconnected.as_ref().unchecked_ref(),
+        )
+        .map_err(|err| anyhow!("Could not listen gamepaddisconnected {:#?}", err))?;
+    ongamepadconnected.forget();
+    ongamepaddisconnected.forget();
+
+    Ok(receiver)
 }
 
 // For Keypress Input
@@ -1160,3 +1902,236 @@ fn prepare_input() -> Result<UnboundedReceiver<KeyPress>> {
 
     Ok(keyevent_receiver)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 枠線 2px、論理座標と同じ 450×600 の描画領域を (100, 50) に置いた canvas
+    fn layout() -> CanvasLayout {
+        CanvasLayout {
+            rect_left: 100.0,
+            rect_top: 50.0,
+            rect_width: 454.0,
+            rect_height: 604.0,
+            offset_width: 454.0,
+            offset_height: 604.0,
+            border_left: 2.0,
+            border_top: 2.0,
+            client_width: 450.0,
+            client_height: 600.0,
+        }
+    }
+
+    #[test]
+    fn client_to_content_subtracts_rect_and_border() {
+        assert_eq!(client_to_content(&layout(), 102.0, 52.0), (0.0, 0.0));
+        assert_eq!(client_to_content(&layout(), 327.0, 352.0), (225.0, 300.0));
+    }
+
+    #[test]
+    fn client_to_content_follows_scroll() {
+        // スクロールで canvas が上に 200px 移動すると rect.top が負になる
+        let scrolled = CanvasLayout {
+            rect_top: -150.0,
+            ..layout()
+        };
+        assert_eq!(client_to_content(&scrolled, 102.0, 10.0), (0.0, 158.0));
+    }
+
+    #[test]
+    fn client_to_content_undoes_css_transform() {
+        // transform: scale(0.5) で表示が半分になっている
+        let shrunk = CanvasLayout {
+            rect_width: 227.0,
+            rect_height: 302.0,
+            ..layout()
+        };
+        assert_eq!(client_to_content(&shrunk, 100.0 + 113.5, 50.0 + 151.0), (225.0, 300.0));
+    }
+
+    #[test]
+    fn content_to_logical_is_identity_at_logical_size() {
+        assert_eq!(content_to_logical(450.0, 600.0, 120.0, 340.0), (120.0, 340.0));
+    }
+
+    #[test]
+    fn content_to_logical_scales_uniformly() {
+        // 2倍の大きさで表示している場合
+        assert_eq!(logical_scale(900.0, 1200.0), 2.0);
+        assert_eq!(content_to_logical(900.0, 1200.0, 450.0, 600.0), (225.0, 300.0));
+    }
+
+    #[test]
+    fn content_to_logical_centres_letterbox() {
+        // 横長の描画領域では左右に余白ができ、高さに合わせて拡大される
+        assert_eq!(logical_scale(1000.0, 600.0), 1.0);
+        assert_eq!(logical_origin(1000.0, 600.0), (275.0, 0.0));
+        assert_eq!(content_to_logical(1000.0, 600.0, 275.0, 0.0), (0.0, 0.0));
+        assert_eq!(content_to_logical(1000.0, 600.0, 725.0, 600.0), (450.0, 600.0));
+
+        // 縦長の描画領域では上下に余白ができる
+        assert_eq!(logical_origin(450.0, 800.0), (0.0, 100.0));
+        assert_eq!(content_to_logical(450.0, 800.0, 225.0, 400.0), (225.0, 300.0));
+    }
+
+    #[test]
+    fn logical_scale_falls_back_when_hidden() {
+        assert_eq!(logical_scale(0.0, 0.0), 1.0);
+        assert_eq!(logical_origin(0.0, 0.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn viewport_maps_client_to_logical() {
+        let viewport = Viewport {
+            layout: CanvasLayout {
+                rect_width: 904.0,
+                rect_height: 1204.0,
+                offset_width: 904.0,
+                offset_height: 1204.0,
+                client_width: 900.0,
+                client_height: 1200.0,
+                ..layout()
+            },
+            dpr: 2.0,
+        };
+        assert_eq!(viewport.scale(), 2.0);
+        assert_eq!(viewport.to_logical(102, 52), (0, 0));
+        assert_eq!(viewport.to_logical(102 + 450, 52 + 600), (225, 300));
+    }
+
+    #[test]
+    fn fixed_step_runs_one_update_per_tick() {
+        let mut timestep = FixedStep::new(60.0);
+        let updates: u32 = (0..60).map(|_| timestep.advance(1000.0 / 60.0)).sum();
+        assert!((59..=60).contains(&updates));
+
+        // 144Hz の画面では update のないフレームがあり、その間は補間する
+        let mut timestep = FixedStep::new(60.0);
+        assert_eq!(timestep.advance(1000.0 / 144.0), 0);
+        assert!((timestep.interpolation() - 60.0 / 144.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fixed_step_clamps_long_frames() {
+        // タブが裏にあった 10 秒分をまとめて update しない
+        let mut timestep = FixedStep::new(60.0);
+        let updates = timestep.advance(10_000.0);
+        assert!(updates <= MAX_UPDATES_PER_FRAME);
+        assert!(timestep.interpolation() < 1.0);
+        assert!(timestep.dropped() >= 10_000.0 - MAX_FRAME_DELTA);
+        assert_eq!(timestep.advance(1000.0 / 60.0), 1);
+    }
+
+    #[test]
+    fn fixed_step_tick_rate_is_configurable() {
+        let mut timestep = FixedStep::new(30.0);
+        assert!((timestep.step() - 1000.0 / 30.0).abs() < 1e-9);
+        assert_eq!(timestep.advance(1000.0 / 60.0), 0);
+        assert_eq!(timestep.advance(1000.0 / 60.0), 1);
+    }
+
+    #[test]
+    fn perspective_is_flat_when_facing_the_viewer() {
+        assert_eq!(perspective_project(100.0, 0.0, 900.0), (100.0, 1.0));
+        let (x, _) = perspective_project(100.0, std::f64::consts::FRAC_PI_2, 900.0);
+        assert!(x.abs() < 1e-9);
+    }
+
+    #[test]
+    fn perspective_shrinks_the_far_edge() {
+        let angle = 0.8;
+        let (near_x, near_scale) = perspective_project(-175.0, angle, 900.0);
+        let (far_x, far_scale) = perspective_project(175.0, angle, 900.0);
+        assert!(near_scale > 1.0 && far_scale < 1.0);
+        assert!(near_x.abs() > far_x.abs());
+    }
+
+    #[test]
+    fn back_face_is_not_mirrored() {
+        // π/2 を過ぎたら裏面を π 戻した角度で描くので、縮尺が 0 に潰れたり左右が反転したりしない
+        let angle = visible_flip_angle(std::f64::consts::PI * 0.75);
+        assert!((angle + std::f64::consts::PI * 0.25).abs() < 1e-9);
+        let (left, _) = perspective_project(-175.0, angle, 900.0);
+        let (right, _) = perspective_project(175.0, angle, 900.0);
+        assert!(left < right);
+    }
+
+    fn recording_renderer(theme: Theme) -> (Rc<crate::backend::RecordingBackend>, Renderer) {
+        let backend = Rc::new(crate::backend::RecordingBackend::new(450, 600));
+        let renderer = Renderer::new(backend.clone(), theme);
+        (backend, renderer)
+    }
+
+    #[test]
+    fn card_faces_use_theme_colours_and_draw_the_etymology_on_the_back() {
+        let (backend, renderer) = recording_renderer(Theme::celtic());
+        let etymology = Etymology::parse(&["dūrābilis/ラテン語: 長持ちする", "endure（耐える）"]);
+        let draw = |face| {
+            renderer.fill_round_rect_rotate_with_flip(
+                &Point::new(225.0, 300.0),
+                0.0,
+                FLASH_CARD_WIDTH,
+                FLASH_CARD_HEIGHT,
+                FLASH_CARD_CORNER_RADIUS,
+                1.0,
+                0.0,
+                &CardFace {
+                    face,
+                    text: "I need [durable] shoes",
+                    etymology: &etymology,
+                    decorations: None,
+                    show_ruby: true,
+                },
+            )
+        };
+
+        draw(Face::Front);
+        let front = backend.calls();
+        assert!(front.contains(&"set_fill_style(\"#008000ff\")".to_string()));
+        assert!(!front.iter().any(|call| call.contains("dūrābilis")));
+
+        draw(Face::Back);
+        let back = backend.calls()[front.len()..].to_vec();
+        assert!(back.contains(&"set_fill_style(\"#4169e1\")".to_string()));
+        assert!(back.iter().any(|call| call.starts_with("fill_text(\"dūrābilis")));
+        assert_eq!(
+            back.iter().filter(|c| *c == "save()").count(),
+            back.iter().filter(|c| *c == "restore()").count()
+        );
+    }
+
+    #[test]
+    fn flipping_card_is_drawn_in_perspective_slices() {
+        let (backend, renderer) = recording_renderer(Theme::minimal());
+        renderer.fill_round_rect_rotate_with_flip(
+            &Point::new(225.0, 300.0),
+            0.0,
+            FLASH_CARD_WIDTH,
+            FLASH_CARD_HEIGHT,
+            FLASH_CARD_CORNER_RADIUS,
+            1.0,
+            0.6,
+            &CardFace {
+                face: Face::Front,
+                text: "card",
+                etymology: &Etymology::default(),
+                decorations: None,
+                show_ruby: false,
+            },
+        );
+        assert_eq!(backend.calls_named("draw_image").len(), FLIP_SLICES);
+        // 面は裏のバッファに描かれるので、表の描画先には文字が出ない
+        assert!(backend.calls_named("fill_text").is_empty());
+    }
+
+    #[test]
+    fn message_window_draws_every_line() {
+        let (backend, renderer) = recording_renderer(Theme::dark());
+        renderer.draw_message_window(&Point::new(225.0, 300.0), "学習状況\n覚えた 1/5");
+        let texts = backend.calls_named("fill_text");
+        assert_eq!(texts.len(), 2);
+        assert!(texts[0].starts_with("fill_text(\"学習状況\"") && texts[1].contains("覚えた 1/5"));
+        assert!(backend.calls().contains(&"set_font(\"24px MyFont\")".to_string()));
+    }
+}
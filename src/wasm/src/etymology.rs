@@ -0,0 +1,254 @@
+//! 語源（etymology）のデータモデルとパーサ
+//!
+//! 以前のデッキは `"dūrābilis/ラテン語: 長持ちする"` のような自由形式の文字列で語源を書いていた。
+//! `Etymology::parse` はこの形式を型付きのモデルに変換するので、既存のデッキはそのまま使える。
+
+/// 形態素の種類
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MorphemeKind {
+    Prefix, // 接頭辞（例: prō-）
+    Base,   // 語基（例: fatērī）
+    Suffix, // 接尾辞（例: -bilis）
+}
+
+/// 語源を構成する形態素
+#[derive(Clone, Debug, PartialEq)]
+pub struct Morpheme {
+    pub kind: MorphemeKind,
+    pub form: String,  // 綴り
+    pub gloss: String, // 意味
+}
+
+/// 同じ語源から派生した単語
+#[derive(Clone, Debug, PartialEq)]
+pub struct DerivedWord {
+    pub word: String,              // 派生語（例: endure）
+    pub gloss: String,             // 意味（例: 耐える）
+    pub formation: Option<String>, // 成り立ち（例: en- + dūrus）
+}
+
+/// 1枚のカードの語源
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Etymology {
+    pub root: String,             // 語根（例: dūrābilis）
+    pub language: Option<String>, // 起源の言語（例: ラテン語）
+    pub gloss: String,            // 語根の意味
+    pub morphemes: Vec<Morpheme>, // 語根を構成する形態素
+    pub derived: Vec<DerivedWord>, // 派生語
+    pub notes: Vec<String>,       // 上記に当てはまらない補足
+}
+
+impl Etymology {
+    /// 旧形式の語源文字列を解釈する
+    /// - 最初の `語根/言語: 意味` の行が語根になる（`/` がなくても末尾が「〜語」なら言語とみなす）
+    /// - `prō-: 意味` は接頭辞、`-bilis: 意味` は接尾辞、ラテン文字（発音記号付き）の見出しは語基
+    /// - ASCII の見出しは派生語（`endure（耐える）: en- + dūrus` の括弧内は意味、値は成り立ち）
+    /// - `:` を含まない行は補足として残す
+    pub fn parse(lines: &[&str]) -> Self {
+        let mut etymology = Etymology::default();
+        let mut has_root = false;
+
+        for line in lines.iter().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+            let Some((key, value)) = split_key_value(line) else {
+                etymology.notes.push(line.to_string());
+                continue;
+            };
+
+            if !has_root {
+                has_root = true;
+                let (root, language) = split_language(key);
+                etymology.root = root;
+                etymology.language = language;
+                if value.contains('+') {
+                    // `e-/ex-外へ + legere 選ぶ` のような成り立ちは形態素に分解する
+                    etymology.morphemes.extend(value.split('+').filter_map(parse_component));
+                } else {
+                    etymology.gloss = value.to_string();
+                }
+                continue;
+            }
+
+            let (word, paren) = split_paren(key);
+            if let Some(kind) = affix_kind(word) {
+                etymology.morphemes.push(Morpheme {
+                    kind,
+                    form: word.to_string(),
+                    gloss: value.to_string(),
+                });
+            } else if word.is_ascii() {
+                etymology.derived.push(match paren {
+                    Some(gloss) => DerivedWord {
+                        word: word.to_string(),
+                        gloss: gloss.to_string(),
+                        // `endure（耐える）:` のように値がなければ成り立ちはない
+                        formation: (!value.is_empty()).then(|| value.to_string()),
+                    },
+                    None => DerivedWord {
+                        word: word.to_string(),
+                        gloss: value.to_string(),
+                        formation: None,
+                    },
+                });
+            } else {
+                etymology.morphemes.push(Morpheme {
+                    kind: MorphemeKind::Base,
+                    form: word.to_string(),
+                    gloss: value.to_string(),
+                });
+            }
+        }
+        etymology
+    }
+
+    /// 接頭辞の一覧
+    pub fn prefixes(&self) -> impl Iterator<Item = &Morpheme> {
+        self.morphemes
+            .iter()
+            .filter(|m| m.kind == MorphemeKind::Prefix)
+    }
+
+    /// 接尾辞の一覧
+    pub fn suffixes(&self) -> impl Iterator<Item = &Morpheme> {
+        self.morphemes
+            .iter()
+            .filter(|m| m.kind == MorphemeKind::Suffix)
+    }
+}
+
+/// 日本語（かな・漢字）の文字かどうか
+fn is_japanese_char(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'   // ひらがな・カタカナ（長音符を含む）
+        | '\u{4E00}'..='\u{9FFF}' // CJK統合漢字
+        | '々')
+}
+
+/// `見出し: 値` に分割（全角コロンにも対応）
+fn split_key_value(line: &str) -> Option<(&str, &str)> {
+    let index = line.find([':', '：'])?;
+    let separator_len = line[index..].chars().next()?.len_utf8();
+    Some((line[..index].trim(), line[index + separator_len..].trim()))
+}
+
+/// `dūrābilis/ラテン語` や `complēreラテン語` を語根と言語に分ける
+fn split_language(key: &str) -> (String, Option<String>) {
+    if let Some((root, language)) = key.split_once('/') {
+        return (root.trim().to_string(), Some(language.trim().to_string()));
+    }
+    match key.find(is_japanese_char) {
+        Some(index) if index > 0 && key.ends_with('語') => {
+            (key[..index].trim().to_string(), Some(key[index..].to_string()))
+        }
+        _ => (key.to_string(), None),
+    }
+}
+
+/// `endure（耐える）` を単語と括弧内に分ける
+fn split_paren(key: &str) -> (&str, Option<&str>) {
+    for (open, close) in [('（', '）'), ('(', ')')] {
+        if let (Some(start), true) = (key.find(open), key.ends_with(close)) {
+            let inner = &key[start + open.len_utf8()..key.len() - close.len_utf8()];
+            return (key[..start].trim(), Some(inner.trim()));
+        }
+    }
+    (key, None)
+}
+
+/// 接頭辞・接尾辞の判定（`prō-` / `-bilis`）
+fn affix_kind(form: &str) -> Option<MorphemeKind> {
+    if form.len() > 1 && form.ends_with('-') {
+        Some(MorphemeKind::Prefix)
+    } else if form.len() > 1 && form.starts_with('-') {
+        Some(MorphemeKind::Suffix)
+    } else {
+        None
+    }
+}
+
+/// `e-/ex-外へ` や `legere 選ぶ` のような成り立ちの一要素を形態素にする
+fn parse_component(component: &str) -> Option<Morpheme> {
+    let component = component.trim();
+    let split = component
+        .find(|c: char| c.is_whitespace() || is_japanese_char(c))
+        .unwrap_or(component.len());
+    let form = component[..split].trim();
+    if form.is_empty() {
+        return None;
+    }
+    Some(Morpheme {
+        kind: affix_kind(form).unwrap_or(MorphemeKind::Base),
+        form: form.to_string(),
+        gloss: component[split..].trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::ITEMS;
+
+    fn morpheme(kind: MorphemeKind, form: &str, gloss: &str) -> Morpheme {
+        Morpheme {
+            kind,
+            form: form.to_string(),
+            gloss: gloss.to_string(),
+        }
+    }
+
+    #[test]
+    fn builtin_deck_etymologies_parse() {
+        let parsed: Vec<Etymology> = ITEMS.iter().map(|item| Etymology::parse(item.2)).collect();
+
+        assert_eq!(parsed[0].root, "dūrābilis");
+        assert_eq!(parsed[0].language.as_deref(), Some("ラテン語"));
+        assert_eq!(parsed[0].gloss, "長持ちする,耐久性のある");
+        assert_eq!(
+            parsed[0].derived,
+            [DerivedWord {
+                word: "endure".to_string(),
+                gloss: "耐える".to_string(),
+                formation: Some("en- + dūrus".to_string()),
+            }]
+        );
+
+        assert_eq!(parsed[1].root, "prōfessiō");
+        assert_eq!(
+            parsed[1].morphemes,
+            [
+                morpheme(MorphemeKind::Prefix, "prō-", "前に,公に"),
+                morpheme(MorphemeKind::Base, "fatērī", "認める,告白する,宣言する"),
+            ]
+        );
+        assert_eq!(parsed[1].derived[0].word, "profess");
+        assert_eq!(parsed[1].derived[0].formation, None);
+
+        // 成り立ちを書いた語根は形態素に分ける
+        assert_eq!(parsed[2].root, "eligere");
+        assert_eq!(parsed[2].gloss, "");
+        assert_eq!(
+            parsed[2].morphemes,
+            [
+                morpheme(MorphemeKind::Prefix, "e-/ex-", "外へ"),
+                morpheme(MorphemeKind::Base, "legere", "選ぶ"),
+            ]
+        );
+        assert_eq!(parsed[2].notes, ["⇒ 選ばれるに値する、選ばれる資格がある"]);
+        assert_eq!(parsed[2].derived[0].gloss, "選挙");
+
+        // `/` がなくても「〜語」で終われば言語
+        assert_eq!(parsed[3].root, "complēre");
+        assert_eq!(parsed[3].language.as_deref(), Some("ラテン語"));
+        assert_eq!(parsed[3].notes.len(), 2);
+
+        assert_eq!(parsed[4].root, "revidēre");
+        assert_eq!(parsed[4].notes, ["もう一度見る → 見直す → 改訂する"]);
+        assert_eq!(parsed[4].derived[0].word, "revision");
+    }
+
+    #[test]
+    fn empty_formation_is_none() {
+        let etymology = Etymology::parse(&["dūrābilis/ラテン語: 長持ちする", "endure（耐える）:"]);
+        assert_eq!(etymology.derived[0].gloss, "耐える");
+        assert_eq!(etymology.derived[0].formation, None);
+    }
+}
@@ -1,11 +1,26 @@
 mod card;
+mod family;
+use crate::a11y::{Mirror, describe_etymology, plain_text};
+use crate::animation::{Easing, Tween};
+use crate::browser;
+use crate::decoration::Decorations;
+use crate::deck::{Deck, Note};
+use crate::editor::{EditorEvent, EditorView};
+use crate::etymology::Etymology;
 use crate::common::*;
 use crate::engine::{
-    Align, Font, Game, KeyState, Message, MouseState, Point, Renderer, TouchState,
+    Align, Font, Game, Message, Point, Renderer, Request,
 };
+use crate::input::{KeyState, PointerState};
 use anyhow::Result;
 use async_trait::async_trait;
+use crate::gesture::SwipeDirection;
+use crate::keymap::{Action, KeyBindings};
+use crate::settings::{Control, Item, Motion, Settings};
+use crate::theme::Face;
 use card::card::*;
+use family::WordFamilyIndex;
+use std::collections::VecDeque;
 use web_sys::HtmlImageElement;
 
 /// ゲーム全体の状態を管理するメイン構造体
@@ -17,11 +32,21 @@ impl GameStage {
     pub fn new() -> Self {
         GameStage { machine: None }
     }
+    /// material のカードでプレイを始める（リプレイやテストではブラウザの設定を読まずにここから始める）
+    fn start(material: Material) -> Self {
+        GameStage {
+            machine: Some(GameStageStateMachine::new(material)),
+        }
+    }
 }
 /// ゲームの状態を表すステートマシン
 /// Playing: プレイ中、DisplayMessage: メッセージ表示、GameOver: ゲームオーバー、GameClear: ゲームクリア
+/// WordFamily: 同じ語根を持つカードの一覧、Settings: 設定画面、Editor: デッキの編集
 enum GameStageStateMachine {
     Playing(GameStageState<Playing>),
+    WordFamily(GameStageState<WordFamily>),
+    Settings(GameStageState<SettingsMenu>),
+    Editor(GameStageState<DeckEditor>),
     DisplayMessage(GameStageState<DisplayMessage>),
     GameOver(GameStageState<GameOver>),
     GameClear(GameStageState<GameClear>),
@@ -35,40 +60,128 @@ impl GameStageStateMachine {
     /// 各状態に応じたupdateを呼び出し、状態遷移を行う
     fn update(
         self,
+        _dt: f64,
         _keystate: &mut KeyState,
-        _touchstate: &mut TouchState,
-        _mousestate: &mut MouseState,
+        _pointerstate: &mut PointerState,
     ) -> Self {
         match self {
             GameStageStateMachine::Playing(state) => {
-                state.update(_keystate, _touchstate, _mousestate).into()
+                state.update(_dt, _keystate, _pointerstate).into()
+            }
+            GameStageStateMachine::WordFamily(state) => {
+                state.update(_dt, _keystate, _pointerstate).into()
+            }
+            GameStageStateMachine::Settings(state) => {
+                state.update(_dt, _keystate, _pointerstate).into()
+            }
+            GameStageStateMachine::Editor(state) => {
+                state.update(_dt, _keystate, _pointerstate).into()
             }
             GameStageStateMachine::DisplayMessage(state) => {
-                state.update(_keystate, _touchstate, _mousestate).into()
+                state.update(_dt, _keystate, _pointerstate).into()
             }
             GameStageStateMachine::GameOver(state) => {
-                state.update(_keystate, _touchstate, _mousestate).into()
+                state.update(_dt, _keystate, _pointerstate).into()
             }
             GameStageStateMachine::GameClear(state) => {
-                state.update(_keystate, _touchstate, _mousestate).into()
+                state.update(_dt, _keystate, _pointerstate).into()
             }
         }
     }
+    /// 画面読み上げ用の内容と、今の状態で行える操作
+    fn mirror(&self) -> Mirror {
+        match self {
+            GameStageStateMachine::Playing(state) => state.material.mirror(),
+            GameStageStateMachine::WordFamily(state) => Mirror {
+                progress: None,
+                content: state._state.describe(&state.material),
+                actions: vec![Action::Up, Action::Down, Action::Confirm, Action::Cancel],
+            },
+            GameStageStateMachine::Settings(state) => Mirror {
+                progress: None,
+                content: state._state.describe(&state.material.settings),
+                actions: vec![
+                    Action::Up,
+                    Action::Down,
+                    Action::SwipeLeft,
+                    Action::SwipeRight,
+                    Action::Confirm,
+                    Action::Cancel,
+                ],
+            },
+            GameStageStateMachine::Editor(state) => Mirror {
+                progress: None,
+                content: state._state.describe(),
+                actions: vec![Action::Flip, Action::Cancel],
+            },
+            GameStageStateMachine::DisplayMessage(state) => Mirror {
+                progress: None,
+                content: state.material.stats_message().replace('\n', "。"),
+                actions: vec![Action::Cancel],
+            },
+            GameStageStateMachine::GameOver(_state) => Mirror {
+                progress: None,
+                content: GAMEOVER_MESSAGE.to_string(),
+                actions: vec![Action::Confirm],
+            },
+            GameStageStateMachine::GameClear(state) => Mirror {
+                progress: None,
+                content: GAMECLEAR_MESSAGE.to_string(),
+                actions: if state.material.can_undo() {
+                    vec![Action::Confirm, Action::Undo]
+                } else {
+                    vec![Action::Confirm]
+                },
+            },
+        }
+    }
     /// 現在の状態に応じて描画を行う
-    fn draw(&self, renderer: &Renderer) {
+    fn draw(&self, renderer: &Renderer, interpolation: f64) {
         match self {
-            GameStageStateMachine::Playing(state) => state.material.draw(renderer, true),
-            GameStageStateMachine::DisplayMessage(state) => state.material.draw(renderer, true),
-            GameStageStateMachine::GameOver(state) => state.material.draw(renderer, true),
-            GameStageStateMachine::GameClear(state) => state.material.draw(renderer, false),
+            GameStageStateMachine::Playing(state) => state.material.draw(renderer, true, interpolation),
+            GameStageStateMachine::WordFamily(_state) => {}
+            GameStageStateMachine::Settings(_state) => {}
+            GameStageStateMachine::Editor(state) => {
+                state._state.preview.draw(renderer, state.material.show_ruby, interpolation)
+            }
+            GameStageStateMachine::DisplayMessage(_state) => {}
+            GameStageStateMachine::GameOver(state) => state.material.draw(renderer, true, interpolation),
+            GameStageStateMachine::GameClear(state) => state.material.draw(renderer, false, interpolation),
         };
     }
+    /// どの状態でも共通の素材（GameLoop への依頼を取り出すため）
+    fn material_mut(&mut self) -> &mut Material {
+        match self {
+            GameStageStateMachine::Playing(state) => &mut state.material,
+            GameStageStateMachine::WordFamily(state) => &mut state.material,
+            GameStageStateMachine::Settings(state) => &mut state.material,
+            GameStageStateMachine::Editor(state) => &mut state.material,
+            GameStageStateMachine::DisplayMessage(state) => &mut state.material,
+            GameStageStateMachine::GameOver(state) => &mut state.material,
+            GameStageStateMachine::GameClear(state) => &mut state.material,
+        }
+    }
 }
 impl From<GameStageState<Playing>> for GameStageStateMachine {
     fn from(state: GameStageState<Playing>) -> Self {
         GameStageStateMachine::Playing(state)
     }
 }
+impl From<GameStageState<WordFamily>> for GameStageStateMachine {
+    fn from(state: GameStageState<WordFamily>) -> Self {
+        GameStageStateMachine::WordFamily(state)
+    }
+}
+impl From<GameStageState<SettingsMenu>> for GameStageStateMachine {
+    fn from(state: GameStageState<SettingsMenu>) -> Self {
+        GameStageStateMachine::Settings(state)
+    }
+}
+impl From<GameStageState<DeckEditor>> for GameStageStateMachine {
+    fn from(state: GameStageState<DeckEditor>) -> Self {
+        GameStageStateMachine::Editor(state)
+    }
+}
 impl From<GameStageState<DisplayMessage>> for GameStageStateMachine {
     fn from(state: GameStageState<DisplayMessage>) -> Self {
         GameStageStateMachine::DisplayMessage(state)
@@ -90,7 +203,7 @@ struct GameStageState<T> {
     material: Material,
 }
 
-// Display Message
+// Display Message（学習状況の表示）
 struct DisplayMessage;
 impl GameStageState<DisplayMessage> {
     fn start_running(self) -> GameStageState<Playing> {
@@ -101,11 +214,16 @@ impl GameStageState<DisplayMessage> {
     }
     fn update(
         self,
+        _dt: f64,
         _keystate: &mut KeyState,
-        _touchstate: &mut TouchState,
-        _mousestate: &mut MouseState,
+        _pointerstate: &mut PointerState,
     ) -> DisplayMessageEndState {
-        if _keystate.is_pressed("Space") {
+        let bindings = &self.material.bindings;
+        if [Action::OpenStats, Action::Cancel, Action::Confirm, Action::Flip]
+            .into_iter()
+            .any(|action| bindings.is_just_pressed(_keystate, action))
+            || _pointerstate.is_tapped()
+        {
             return DisplayMessageEndState::Complete(self.start_running());
         }
         DisplayMessageEndState::Continue(self)
@@ -137,59 +255,110 @@ impl GameStageState<Playing> {
     /// カードの回転、削除、状態遷移を処理
     fn update(
         mut self,
+        _dt: f64,
         _keystate: &mut KeyState,
-        _touchstate: &mut TouchState,
-        _mousestate: &mut MouseState,
+        _pointerstate: &mut PointerState,
     ) -> PlayingEndState {
         self.material.frame += 1;
 
         // カードの自動回転を更新 閾値を超えた場合）
         if let Some(card) = self.material.cards.first_mut() {
-            card.update();
+            card.update(_dt);
         }
 
         // 削除中のカードも自動回転を継続
         if let Some(removing_card) = &mut self.material.removing_card {
-            removing_card.update();
+            removing_card.update(_dt);
+        }
+
+        let bindings = &self.material.bindings;
+        let flip = bindings.is_just_pressed(_keystate, Action::Flip);
+        let grade = graded_swipe(bindings, _keystate);
+        let hold_left = bindings.is_pressed(_keystate, Action::SwipeLeft);
+        let hold_right = bindings.is_pressed(_keystate, Action::SwipeRight);
+        let open_family = bindings.is_just_pressed(_keystate, Action::WordFamily);
+        let open_stats = bindings.is_just_pressed(_keystate, Action::OpenStats);
+        let open_settings = bindings.is_just_pressed(_keystate, Action::OpenSettings);
+        let undo = bindings.is_just_pressed(_keystate, Action::Undo);
+        let replay_audio = bindings.is_just_pressed(_keystate, Action::ReplayAudio);
+        let flying = self.material.cards.first().is_some_and(|card| card.is_auto_rotating());
+
+        // ルビ（ふりがな）の表示を切り替え（上級者向けに読みを隠せる）
+        if bindings.is_just_pressed(_keystate, Action::ToggleFurigana) {
+            self.material.show_ruby = !self.material.show_ruby;
+        }
+
+        // 直前のスワイプを取り消す（キー・シェイク・画面左上のボタン）
+        // スワイプの途中で取り消すと表示が乱れるので、カードが止まっているときだけ
+        if self.material.can_undo() && !flying && (undo || take_undo_button_tap(_pointerstate)) {
+            self.material.undo();
+        }
+
+        // 英文をもう一度読み上げる
+        if replay_audio
+            && let Some(card) = self.material.cards.first()
+        {
+            self.material
+                .requests
+                .push(Request::Speak(plain_text(card.get_back_text())));
+        }
+
+        // キーまたは画面右上のボタンで設定画面を開く（カードが飛んでいく途中は開かない）
+        if !flying && (open_settings || take_settings_button_tap(_pointerstate)) {
+            return PlayingEndState::Settings(GameStageState {
+                _state: SettingsMenu { selected: 0 },
+                material: self.material,
+            });
+        }
+
+        // 裏面ではキーまたは長押しでワードファミリー画面を開く
+        if let Some(card) = self.material.cards.first()
+            && card.get_face_state() == 1
+            && !card.is_flipping()
+            && !card.is_auto_rotating()
+            && (open_family || _pointerstate.take_long_press())
+        {
+            return PlayingEndState::WordFamily(GameStageState {
+                _state: WordFamily::new(&self.material),
+                material: self.material,
+            });
         }
 
         // ユーザー入力によるカード操作（自動回転中でない場合のみ）
-        if let Some(card) = self.material.cards.first() {
-            if !card.is_auto_rotating() {
-                // タップ/クリックでカードの表裏を切り替え
-                if _touchstate.is_tapped() || _mousestate.is_clicked() {
-                    if let Some(card) = self.material.cards.first_mut() {
-                        card.toggle_face();
-                    }
+        if let Some(card) = self.material.cards.first_mut()
+            && !card.is_auto_rotating()
+        {
+            // タップ/クリックまたはキーでカードの表裏を切り替え
+            if _pointerstate.is_tapped() || flip {
+                // 読み上げの設定がオンなら、裏面（英文）になるときに読み上げる
+                if self.material.settings.audio && card.face() == Face::Front {
+                    self.material
+                        .requests
+                        .push(Request::Speak(plain_text(card.get_back_text())));
                 }
+                card.toggle_face();
+            }
 
+            if let Some(direction) = _pointerstate.take_swipe().or(grade) {
+                // スワイプ・フリックで離したとき、または評価キーで確定して自動回転を開始
+                card.commit_swipe(direction);
+            } else if let Some(drag) = _pointerstate.drag() {
+                // ドラッグ中はカードが指・マウスに追従する（離すまで確定しない）
+                card.drag_to(drag.dx as f32, drag.dy as f32);
+            } else if hold_left {
                 // 左方向への回転
-                if _keystate.is_pressed("ArrowLeft")
-                    || _touchstate.is_swiping_left()
-                    || _mousestate.is_dragging_left()
-                {
-                    if let Some(card) = self.material.cards.first_mut() {
-                        card.rotate_left();
-                    }
-                }
+                card.rotate_left(_dt);
+            } else if hold_right {
                 // 右方向への回転
-                if _keystate.is_pressed("ArrowRight")
-                    || _touchstate.is_swiping_right()
-                    || _mousestate.is_dragging_right()
-                {
-                    if let Some(card) = self.material.cards.first_mut() {
-                        card.rotate_right();
-                    }
-                }
+                card.rotate_right(_dt);
+            } else {
+                // 閾値に届かずに離した（または中央に戻して取り消した）場合はばねで元に戻す
+                card.release_drag();
             }
         }
 
-        // 自動回転が開始されたら（閾値0.78超え）次のカードを準備
-        if let Some(card) = self.material.cards.first() {
-            if card.is_auto_rotating() && !self.material.next_card_ready {
-                self.material.next_card_ready = true;
-            }
-        }
+        // 自動回転が開始されたら、後ろに重なっているカードを1枚分手前に送る
+        self.material.update_stack(_dt);
 
         // カードの回転が閾値(FLASH_CARD_ERASE_POINT_ROTATE)を超えたら削除または配列末尾へ移動
         if let Some(card) = self.material.cards.first() {
@@ -198,13 +367,14 @@ impl GameStageState<Playing> {
 
                 // 入力状態をクリア
                 _keystate.clear();
-                _touchstate.clear();
-                _mousestate.clear();
+                _pointerstate.clear();
+
+                // 取り消せるように、スワイプする前の状態を保存
+                self.material.push_undo();
 
                 if rotate_direction == -1 {
                     // 左回転: カードを配列から削除
                     self.material.removing_card = Some(self.material.cards.remove(0));
-                    self.material.next_card_ready = false;
 
                     // プログレスカウンターを更新
                     self.material.current_card_index += 1;
@@ -223,12 +393,18 @@ impl GameStageState<Playing> {
                         });
                     }
                 } else if rotate_direction == 1 {
-                    // 右回転: カードを配列の最後に移動（プログレスカウンターは進めない）
+                    // 右回転: カードを後回しにする（プログレスカウンターは進めない）
+                    // 戻す位置は設定による（最後、または {SCHEDULER_SOON_GAP} 枚後）
+                    self.material.again_count += 1;
                     let mut removed_card = self.material.cards.remove(0);
                     self.material.removing_card = Some(removed_card.clone());
-                    removed_card.reset_card(); // カードの状態を完全にリセット（表面に戻す）
-                    self.material.cards.push(removed_card); // 配列の最後に追加
-                    self.material.next_card_ready = false;
+                    removed_card.reset_card(); // カードの状態を完全にリセット（最初に見せる面に戻す）
+                    let position = self
+                        .material
+                        .settings
+                        .scheduler
+                        .requeue_position(self.material.cards.len());
+                    self.material.cards.insert(position, removed_card);
 
                     // 次のカードの自動回転を停止
                     if let Some(next_card) = self.material.cards.first_mut() {
@@ -240,12 +416,13 @@ impl GameStageState<Playing> {
 
         // 削除中のカードがある場合、完全に画面外に出たらクリア
         if let Some(removing_card) = &self.material.removing_card {
-            if removing_card.get_rotate().abs() > 1.5 {
+            if removing_card.get_rotate().abs() >= FLASH_CARD_SWIPE_OUT_ROTATE {
                 self.material.removing_card = None;
             }
         }
 
-        if _keystate.is_pressed("Space") {
+        // 学習状況を表示
+        if open_stats {
             return PlayingEndState::Message(GameStageState {
                 _state: DisplayMessage,
                 material: self.material,
@@ -258,27 +435,489 @@ impl From<PlayingEndState> for GameStageStateMachine {
     fn from(state: PlayingEndState) -> Self {
         match state {
             PlayingEndState::Continue(running) => running.into(),
+            PlayingEndState::WordFamily(family) => family.into(),
+            PlayingEndState::Settings(settings) => settings.into(),
             PlayingEndState::Message(message) => message.into(),
             PlayingEndState::GameOver(gameover) => gameover.into(),
             PlayingEndState::GameClear(gameclear) => gameclear.into(),
         }
     }
 }
+/// 取り消しボタンがタップされていれば true（タップはカードの裏返しに使わない）
+fn take_undo_button_tap(pointerstate: &mut PointerState) -> bool {
+    pointerstate.take_tap_in(
+        (UNDO_BUTTON_X - UNDO_BUTTON_RADIUS) as f64,
+        (UNDO_BUTTON_Y - UNDO_BUTTON_RADIUS) as f64,
+        (UNDO_BUTTON_RADIUS * 2.0) as f64,
+        (UNDO_BUTTON_RADIUS * 2.0) as f64,
+    )
+}
+/// 設定ボタンがタップされていれば true（タップはカードの裏返しに使わない）
+fn take_settings_button_tap(pointerstate: &mut PointerState) -> bool {
+    pointerstate.take_tap_in(
+        (SETTINGS_BUTTON_X - SETTINGS_BUTTON_RADIUS) as f64,
+        (SETTINGS_BUTTON_Y - SETTINGS_BUTTON_RADIUS) as f64,
+        (SETTINGS_BUTTON_RADIUS * 2.0) as f64,
+        (SETTINGS_BUTTON_RADIUS * 2.0) as f64,
+    )
+}
+
+/// 評価キー（1〜4）をスワイプの方向に置き換える
+/// 評価を記録する仕組みはまだないので、1・2 は「まだ不安」（右）、3・4 は「覚えた」（左）と同じ扱い
+fn graded_swipe(bindings: &KeyBindings, keystate: &KeyState) -> Option<SwipeDirection> {
+    if [Action::Again, Action::Hard]
+        .into_iter()
+        .any(|action| bindings.is_just_pressed(keystate, action))
+    {
+        Some(SwipeDirection::Right)
+    } else if [Action::Good, Action::Easy]
+        .into_iter()
+        .any(|action| bindings.is_just_pressed(keystate, action))
+    {
+        Some(SwipeDirection::Left)
+    } else {
+        None
+    }
+}
 enum PlayingEndState {
     Continue(GameStageState<Playing>),
+    WordFamily(GameStageState<WordFamily>),
+    Settings(GameStageState<SettingsMenu>),
     Message(GameStageState<DisplayMessage>),
     GameOver(GameStageState<GameOver>),
     GameClear(GameStageState<GameClear>),
 }
+/// ワードファミリー画面（現在のカードと語根を共有するカードの一覧）
+/// セッションで学び終えたカードも一覧に出すため、索引はデッキ全体から作る
+struct WordFamily {
+    title: String,                // 語根の表示（例: dūrābilis（ラテン語））
+    members: Vec<usize>,          // 同じ語根を持つカードの位置（material.deck.notes の添字）
+    etymologies: Vec<Etymology>,  // デッキ全体の語源（material.deck.notes と同じ順番）
+    selected: usize,              // 選択中の行
+    derived: Vec<String>,         // 一覧のカードに含まれる派生語
+}
+impl WordFamily {
+    /// 先頭のカードの語根から一覧を作成
+    fn new(material: &Material) -> Self {
+        let etymologies: Vec<Etymology> = material
+            .deck
+            .notes
+            .iter()
+            .map(|note| Etymology::parse(&note.etymology.iter().map(String::as_str).collect::<Vec<_>>()))
+            .collect();
+        let index = WordFamilyIndex::build(etymologies.iter());
+        let (title, members) = match material.cards.first() {
+            Some(card) => {
+                let etymology = card.get_etymology();
+                let title = match &etymology.language {
+                    Some(language) => format!("{}（{}）", etymology.root, language),
+                    None => etymology.root.clone(),
+                };
+                (title, index.family_of(etymology))
+            }
+            None => (String::new(), Vec::new()),
+        };
+
+        let mut derived: Vec<String> = Vec::new();
+        for &i in members.iter() {
+            for d in etymologies[i].derived.iter() {
+                let word = format!("{}（{}）", d.word, d.gloss);
+                if !derived.contains(&word) {
+                    derived.push(word);
+                }
+            }
+        }
+
+        WordFamily {
+            title,
+            members,
+            etymologies,
+            selected: 0,
+            derived,
+        }
+    }
+    /// 読み上げ用の文（語根と選択中の行）
+    fn describe(&self, material: &Material) -> String {
+        let rows = self.rows(material);
+        match rows.get(self.selected) {
+            Some((text, _root)) => format!(
+                "ワードファミリー: {}。{}/{}: {}",
+                self.title,
+                self.selected + 1,
+                rows.len(),
+                plain_text(text)
+            ),
+            None => format!("ワードファミリー: {}", self.title),
+        }
+    }
+    /// 描画用の行（カードの英文と語根）
+    fn rows(&self, material: &Material) -> Vec<(String, String)> {
+        self.members
+            .iter()
+            .map(|&i| (material.deck.notes[i].back.clone(), self.etymologies[i].root.clone()))
+            .collect()
+    }
+}
+impl GameStageState<WordFamily> {
+    fn update(
+        mut self,
+        _dt: f64,
+        _keystate: &mut KeyState,
+        _pointerstate: &mut PointerState,
+    ) -> WordFamilyEndState {
+        let count = self._state.members.len();
+        let bindings = &self.material.bindings;
+        let close = bindings.is_just_pressed(_keystate, Action::Cancel)
+            || bindings.is_just_pressed(_keystate, Action::WordFamily);
+        let jump = bindings.is_just_pressed(_keystate, Action::Confirm)
+            || bindings.is_just_pressed(_keystate, Action::Flip);
+        let up = bindings.is_just_pressed(_keystate, Action::Up);
+        let down = bindings.is_just_pressed(_keystate, Action::Down);
+
+        // 一覧を閉じてカードに戻る
+        if close || _pointerstate.take_long_press() {
+            return WordFamilyEndState::Complete(self.back_to_card(None));
+        }
+
+        // 選択中のカードへジャンプ（セッションに残っていなければ今のカードに戻るだけ）
+        if jump || _pointerstate.is_tapped() {
+            let target = self._state.members.get(self._state.selected).and_then(|&i| {
+                let front = &self.material.deck.notes[i].front;
+                self.material.cards.iter().position(|card| card.get_front_text() == front)
+            });
+            return WordFamilyEndState::Complete(self.back_to_card(target));
+        }
+
+        // 選択の移動（キーまたは左右スワイプ）
+        if count > 0 {
+            let swipe = _pointerstate.take_swipe();
+            if up || swipe == Some(SwipeDirection::Left) {
+                self._state.selected = (self._state.selected + count - 1) % count;
+            }
+            if down || swipe == Some(SwipeDirection::Right) {
+                self._state.selected = (self._state.selected + 1) % count;
+            }
+        }
+        WordFamilyEndState::Continue(self)
+    }
+    /// Playing に戻る（target が指定されていればそのカードを先頭に移動）
+    fn back_to_card(mut self, target: Option<usize>) -> GameStageState<Playing> {
+        if let Some(index) = target {
+            self.material.bring_to_front(index);
+        }
+        GameStageState {
+            _state: Playing,
+            material: self.material,
+        }
+    }
+}
+enum WordFamilyEndState {
+    Continue(GameStageState<WordFamily>),
+    Complete(GameStageState<Playing>),
+}
+impl From<WordFamilyEndState> for GameStageStateMachine {
+    fn from(state: WordFamilyEndState) -> Self {
+        match state {
+            WordFamilyEndState::Continue(family) => family.into(),
+            WordFamilyEndState::Complete(playing) => playing.into(),
+        }
+    }
+}
+
+/// 設定画面（変えた値はすぐに保存し、テーマ・動き・スワイプの判定に反映する）
+/// セッションの枚数と出題の向きは次のセッションから使う
+struct SettingsMenu {
+    selected: usize, // 選択中の行（Item::ALL の添字）
+}
+impl SettingsMenu {
+    /// 読み上げ用の文（選択中の行の名前と値）
+    fn describe(&self, settings: &Settings) -> String {
+        let item = Item::ALL[self.selected];
+        let value = item.value(settings);
+        let row = if value.is_empty() {
+            item.label().to_string()
+        } else {
+            format!("{}、{}", item.label(), value)
+        };
+        format!("設定: {}（{}/{}）", row, self.selected + 1, Item::ALL.len())
+    }
+    /// 描画用の行（項目名、値、見た目）
+    fn rows(settings: &Settings) -> Vec<(&'static str, String, Control)> {
+        Item::ALL
+            .iter()
+            .map(|item| (item.label(), item.value(settings), item.control(settings)))
+            .collect()
+    }
+}
+impl GameStageState<SettingsMenu> {
+    fn update(
+        mut self,
+        _dt: f64,
+        _keystate: &mut KeyState,
+        _pointerstate: &mut PointerState,
+    ) -> SettingsEndState {
+        let count = Item::ALL.len();
+        let bindings = &self.material.bindings;
+        let close = bindings.is_just_pressed(_keystate, Action::Cancel)
+            || bindings.is_just_pressed(_keystate, Action::OpenSettings);
+        let confirm = bindings.is_just_pressed(_keystate, Action::Confirm)
+            || bindings.is_just_pressed(_keystate, Action::Flip);
+        let up = bindings.is_just_pressed(_keystate, Action::Up);
+        let down = bindings.is_just_pressed(_keystate, Action::Down);
+        let decrease = bindings.is_just_pressed(_keystate, Action::SwipeLeft);
+        let increase = bindings.is_just_pressed(_keystate, Action::SwipeRight);
+
+        if close || _pointerstate.take_long_press() {
+            return SettingsEndState::Complete(self.back_to_card());
+        }
+
+        let mut settings = self.material.settings.clone();
+
+        // タップした行を選び、スライダーならその位置に、それ以外は次の値にする
+        if let Some((x, y)) = _pointerstate.take_tap()
+            && let Some(index) = settings_row_at(x, y)
+        {
+            self._state.selected = index;
+            let item = Item::ALL[index];
+            if item == Item::Close {
+                return SettingsEndState::Complete(self.back_to_card());
+            }
+            if item == Item::EditDeck {
+                return SettingsEndState::Editor(self.open_editor());
+            }
+            match (item.control(&settings), settings_slider_fraction(x)) {
+                (Control::Slider(_), Some(fraction)) => item.set_fraction(&mut settings, fraction),
+                _ => item.step(&mut settings, 1),
+            }
+        }
+
+        // 選択の移動（キー）と値の変更（左右キー・左右スワイプ・決定）
+        if up {
+            self._state.selected = (self._state.selected + count - 1) % count;
+        }
+        if down {
+            self._state.selected = (self._state.selected + 1) % count;
+        }
+        let item = Item::ALL[self._state.selected];
+        if confirm && item == Item::Close {
+            return SettingsEndState::Complete(self.back_to_card());
+        }
+        if confirm && item == Item::EditDeck {
+            return SettingsEndState::Editor(self.open_editor());
+        }
+        let swipe = _pointerstate.take_swipe();
+        if decrease || swipe == Some(SwipeDirection::Left) {
+            item.step(&mut settings, -1);
+        }
+        if increase || confirm || swipe == Some(SwipeDirection::Right) {
+            item.step(&mut settings, 1);
+        }
+
+        if settings != self.material.settings {
+            self.material.apply_settings(settings);
+        }
+        SettingsEndState::Continue(self)
+    }
+    fn back_to_card(self) -> GameStageState<Playing> {
+        GameStageState {
+            _state: Playing,
+            material: self.material,
+        }
+    }
+    fn open_editor(self) -> GameStageState<DeckEditor> {
+        GameStageState {
+            _state: DeckEditor::new(self.material.deck.clone()),
+            material: self.material,
+        }
+    }
+}
+/// タップした位置の設定画面の行（Item::ALL の添字。draw_settings_window と同じ配置）
+fn settings_row_at(x: f64, y: f64) -> Option<usize> {
+    let left = ((SCREEN_WIDTH - FLASH_CARD_WIDTH) / 2.0) as f64;
+    let top = (SETTINGS_MESSAGE_Y - FLASH_CARD_HEIGHT / 2.0) as f64 + SETTINGS_ROW_TOP;
+    if !(left..=left + FLASH_CARD_WIDTH as f64).contains(&x) || y < top {
+        return None;
+    }
+    let index = ((y - top) / SETTINGS_ROW_HEIGHT) as usize;
+    (index < Item::ALL.len()).then_some(index)
+}
+/// スライダーの上（少しはみ出してもよい）をタップしていれば、その位置（0.0〜1.0）
+fn settings_slider_fraction(x: f64) -> Option<f64> {
+    let right = ((SCREEN_WIDTH + FLASH_CARD_WIDTH) / 2.0) as f64 - 30.0;
+    let left = right - SETTINGS_SLIDER_WIDTH;
+    (left - 10.0..=right + 10.0)
+        .contains(&x)
+        .then(|| ((x - left) / SETTINGS_SLIDER_WIDTH).clamp(0.0, 1.0))
+}
+enum SettingsEndState {
+    Continue(GameStageState<SettingsMenu>),
+    Editor(GameStageState<DeckEditor>),
+    Complete(GameStageState<Playing>),
+}
+impl From<SettingsEndState> for GameStageStateMachine {
+    fn from(state: SettingsEndState) -> Self {
+        match state {
+            SettingsEndState::Continue(settings) => settings.into(),
+            SettingsEndState::Editor(editor) => editor.into(),
+            SettingsEndState::Complete(playing) => playing.into(),
+        }
+    }
+}
+
+/// デッキの編集画面（入力はページに重ねたフォームで行い、canvas には選択中のカードをプレイ中と同じ形で描く）
+/// 編集はデッキの写しに対して行い、保存したときだけ Material のデッキと localStorage に反映する
+struct DeckEditor {
+    deck: Deck,                // 編集中のデッキ
+    selected: usize,           // 選択中のノートの添字
+    preview: Box<Card>,        // 選択中のノートのプレビュー（Card は大きいので箱に入れる）
+    message: Option<String>,   // 入力の問題、または保存したことの通知
+    saved: bool,               // 一度でも保存したか（閉じるときに新しいデッキでセッションをやり直す）
+    events: Vec<EditorEvent>,  // フォームから届いた、まだ処理していない操作
+}
+impl DeckEditor {
+    fn new(deck: Deck) -> Self {
+        let preview = Box::new(card_from_note(&deck.notes[0]));
+        DeckEditor {
+            deck,
+            selected: 0,
+            preview,
+            message: None,
+            saved: false,
+            events: Vec::new(),
+        }
+    }
+    /// 読み上げ用の文
+    fn describe(&self) -> String {
+        let note = &self.deck.notes[self.selected];
+        let mut content = format!(
+            "デッキの編集: カード {}/{}。表面 {}、裏面 {}",
+            self.selected + 1,
+            self.deck.notes.len(),
+            note.front,
+            note.back
+        );
+        if let Some(message) = &self.message {
+            content = format!("{}。{}", content, message);
+        }
+        content
+    }
+    /// フォームに見せる内容
+    fn view(&self) -> EditorView {
+        EditorView {
+            note: self.deck.notes[self.selected].clone(),
+            position: format!("カード {}/{}", self.selected + 1, self.deck.notes.len()),
+            message: self.message.clone(),
+        }
+    }
+    /// 選択を変えたとき・内容を変えたときにプレビューを作り直す（向きは今の向きを保つ）
+    fn select(&mut self, index: usize) {
+        let face = self.preview.face();
+        self.selected = index;
+        *self.preview = card_from_note(&self.deck.notes[index]);
+        if face != self.preview.face() {
+            self.preview.set_start_face(face);
+            self.preview.reset_card();
+        }
+        self.message = self.deck.notes[index].validate().err().map(|err| err.to_string());
+    }
+}
+impl GameStageState<DeckEditor> {
+    fn update(
+        mut self,
+        _dt: f64,
+        _keystate: &mut KeyState,
+        _pointerstate: &mut PointerState,
+    ) -> EditorEndState {
+        let bindings = &self.material.bindings;
+        let close = bindings.is_just_pressed(_keystate, Action::Cancel);
+        let flip = bindings.is_just_pressed(_keystate, Action::Flip);
+
+        let events = std::mem::take(&mut self._state.events);
+        let count = |editor: &DeckEditor| editor.deck.notes.len();
+        for event in events {
+            let editor = &mut self._state;
+            match event {
+                EditorEvent::Change(note) => {
+                    editor.deck.notes[editor.selected] = note;
+                    editor.select(editor.selected);
+                }
+                EditorEvent::Previous => editor.select((editor.selected + count(editor) - 1) % count(editor)),
+                EditorEvent::Next => editor.select((editor.selected + 1) % count(editor)),
+                EditorEvent::Add => {
+                    editor.deck.notes.insert(editor.selected + 1, Note::default());
+                    editor.select(editor.selected + 1);
+                }
+                EditorEvent::Delete if count(editor) == 1 => {
+                    editor.message = Some("最後のカードは削除できません".to_string());
+                }
+                EditorEvent::Delete => {
+                    editor.deck.notes.remove(editor.selected);
+                    editor.select(editor.selected.min(count(editor) - 1));
+                }
+                EditorEvent::Save => match editor.deck.validate() {
+                    Err((index, err)) => {
+                        editor.select(index);
+                        editor.message = Some(format!("カード {}: {}", index + 1, err));
+                    }
+                    Ok(()) => {
+                        self.material.deck = editor.deck.clone();
+                        self.material.requests.push(Request::SaveDeck(editor.deck.clone()));
+                        editor.saved = true;
+                        editor.message = Some("保存しました".to_string());
+                    }
+                },
+                EditorEvent::Close => return EditorEndState::Complete(self.back_to_card()),
+            }
+        }
+        if close || _pointerstate.take_long_press() {
+            return EditorEndState::Complete(self.back_to_card());
+        }
+
+        // プレビューはキーかタップで裏返す
+        if flip || _pointerstate.take_tap().is_some() {
+            self._state.preview.toggle_face();
+        }
+        self._state.preview.update(_dt);
+        EditorEndState::Continue(self)
+    }
+    /// プレイに戻る（保存していれば新しいデッキで最初からやり直す）
+    fn back_to_card(self) -> GameStageState<Playing> {
+        let material = if self._state.saved {
+            self.material.reset()
+        } else {
+            self.material
+        };
+        GameStageState {
+            _state: Playing,
+            material,
+        }
+    }
+}
+enum EditorEndState {
+    Continue(GameStageState<DeckEditor>),
+    Complete(GameStageState<Playing>),
+}
+impl From<EditorEndState> for GameStageStateMachine {
+    fn from(state: EditorEndState) -> Self {
+        match state {
+            EditorEndState::Continue(editor) => editor.into(),
+            EditorEndState::Complete(playing) => playing.into(),
+        }
+    }
+}
+
 struct GameOver;
 impl GameStageState<GameOver> {
     fn update(
         self,
+        _dt: f64,
         _keystate: &mut KeyState,
-        _touchstate: &mut TouchState,
-        _mousestate: &mut MouseState,
+        _pointerstate: &mut PointerState,
     ) -> GameOverEndState {
-        if _keystate.is_pressed("Space") {
+        let bindings = &self.material.bindings;
+        if bindings.is_just_pressed(_keystate, Action::Flip)
+            || bindings.is_just_pressed(_keystate, Action::Confirm)
+        {
             GameOverEndState::Complete(self.new_game())
         } else {
             GameOverEndState::Continue(self)
@@ -308,19 +947,37 @@ struct GameClear;
 impl GameStageState<GameClear> {
     fn update(
         self,
+        _dt: f64,
         _keystate: &mut KeyState,
-        _touchstate: &mut TouchState,
-        _mousestate: &mut MouseState,
+        _pointerstate: &mut PointerState,
     ) -> GameClearEndState {
-        // スペースキー、タッチ、またはクリックでゲームを再開
-        if _keystate.is_pressed("Space")
-            || _touchstate.is_tapped()
-            || _mousestate.is_clicked() {
+        // 最後のスワイプを取り消して最後のカードに戻る
+        let bindings = &self.material.bindings;
+        if self.material.can_undo()
+            && (bindings.is_just_pressed(_keystate, Action::Undo)
+                || take_undo_button_tap(_pointerstate))
+        {
+            return GameClearEndState::Complete(self.undo());
+        }
+
+        // 裏返す・決定のキー、タッチ、またはクリックでゲームを再開
+        let bindings = &self.material.bindings;
+        if bindings.is_just_pressed(_keystate, Action::Flip)
+            || bindings.is_just_pressed(_keystate, Action::Confirm)
+            || _pointerstate.is_tapped()
+        {
             GameClearEndState::Complete(self.new_game())
         } else {
             GameClearEndState::Continue(self)
         }
     }
+    fn undo(mut self) -> GameStageState<Playing> {
+        self.material.undo();
+        GameStageState {
+            _state: Playing,
+            material: self.material,
+        }
+    }
     fn new_game(self) -> GameStageState<Playing> {
         GameStageState {
             _state: Playing,
@@ -341,36 +998,43 @@ impl From<GameClearEndState> for GameStageStateMachine {
     }
 }
 
+/// スワイプを取り消すために保存する、スワイプ直前の状態
+/// （評価を記録する仕組みはまだないので、学習状況の「もう一度」の回数だけを戻す）
+struct UndoEntry {
+    cards: Vec<Card>,        // カードの並び（先頭はスワイプしたカード、表裏もそのまま）
+    current_card_index: i32, // プログレスカウンター
+    again_count: i32,        // 「まだ不安」で後回しにした回数
+}
+
 /// ゲームの素材（カード、フレームカウンタなど）を管理する構造体
 pub struct Material {
     frame: i32,                  // フレームカウンタ
     cards: Vec<Card>,            // カードの配列（最大{FLASH_CARD_NUMBERS}枚）
     removing_card: Option<Card>, // 削除中のカード
-    next_card_ready: bool,       // 次のカードの準備完了フラグ
+    stack_advance: Option<Tween>, // 後ろのカードが1枚分手前に出てくるアニメーション（0.0〜1.0）
     current_card_index: i32,     // 現在のカード番号（1から始まる）
     total_cards: i32,            // 総カード枚数
+    show_ruby: bool,             // ルビ（ふりがな）を表示するかどうか
+    again_count: i32,            // 「まだ不安」で後回しにした回数
+    bindings: KeyBindings,       // キー割り当て
+    undo_stack: VecDeque<UndoEntry>, // 取り消せるスワイプ（最大{UNDO_STACK_SIZE}件）
+    settings: Settings,          // 利用者の設定（設定画面で変更する）
+    requests: Vec<Request>,      // GameLoop に渡すブラウザへの依頼
+    deck: Deck,                  // カードの元になるデッキ（エディタで保存すると次のセッションから替わる）
 }
 impl Material {
-    /// 新しいMaterialインスタンスを作成
-    /// {FLASH_CARD_NUMBERS}枚のカード（"Card 1"〜"Card {FLASH_CARD_NUMBERS}"）を初期化
+    /// 組み込みのデッキ（ITEMS）と既定の設定で新しいMaterialインスタンスを作成
     fn new() -> Self {
+        Material::with(Deck::builtin(), Settings::default())
+    }
+    /// デッキから設定の枚数（既定は{FLASH_CARD_NUMBERS}枚）のカードを、設定の向きで初期化
+    fn with(deck: Deck, settings: Settings) -> Self {
         let mut cards = Vec::new();
 
-        // {FLASH_CARD_NUMBERS} 個のカードを作成
-        for i in 0..FLASH_CARD_NUMBERS {
-            // ITEMSの範囲内でループさせる
-            let front_text = ITEMS[(i % ITEM_SIZE as i32) as usize].0;
-            let back_text = ITEMS[(i % ITEM_SIZE as i32) as usize].1;
-            let etymologies = ITEMS[(i % ITEM_SIZE as i32) as usize].2;
-            let card = Card::new(
-                Point::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0),
-                FLASH_CARD_WIDTH,
-                FLASH_CARD_HEIGHT,
-                Color::Green,
-                front_text,
-                back_text,
-                etymologies,
-            );
+        // session_size 個のカードを作成（デッキの範囲内でループさせる）
+        for i in 0..settings.session_size as usize {
+            let mut card = card_from_note(&deck.notes[i % deck.notes.len()]);
+            card.set_start_face(settings.direction);
             cards.push(card);
         }
 
@@ -378,49 +1042,211 @@ impl Material {
             frame: 0,
             cards: cards,
             removing_card: None,
-            next_card_ready: false,
+            stack_advance: None,
             current_card_index: 1,
-            total_cards: FLASH_CARD_NUMBERS,
+            total_cards: settings.session_size,
+            show_ruby: true,
+            again_count: 0,
+            bindings: KeyBindings::default(),
+            undo_stack: VecDeque::new(),
+            settings,
+            requests: Vec::new(),
+            deck,
         }
     }
     /// Materialをリセット（新しいインスタンスを作成）
+    /// ルビ表示・キー割り当て・利用者の設定・デッキはリセット後も引き継ぐ（枚数・向き・デッキの変更はここで反映される）
     fn reset(&self) -> Material {
-        Material::new()
+        Material {
+            show_ruby: self.show_ruby,
+            bindings: self.bindings.clone(),
+            ..Material::with(self.deck.clone(), self.settings.clone())
+        }
+    }
+    /// 設定を変えて、保存とテーマなどへの反映を GameLoop に依頼する
+    fn apply_settings(&mut self, settings: Settings) {
+        self.settings = settings.clone();
+        self.requests.push(Request::ApplySettings(settings));
+    }
+    /// スワイプする直前の状態を取り消し用に保存
+    fn push_undo(&mut self) {
+        let mut cards = self.cards.clone();
+        if let Some(card) = cards.first_mut() {
+            card.stop_auto_rotating(); // 傾きだけ戻し、表裏はそのまま
+        }
+        self.undo_stack.push_back(UndoEntry {
+            cards,
+            current_card_index: self.current_card_index,
+            again_count: self.again_count,
+        });
+        if self.undo_stack.len() > UNDO_STACK_SIZE {
+            self.undo_stack.pop_front();
+        }
+    }
+    fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+    /// 直前のスワイプを取り消す
+    fn undo(&mut self) {
+        if let Some(entry) = self.undo_stack.pop_back() {
+            self.cards = entry.cards;
+            self.current_card_index = entry.current_card_index;
+            self.again_count = entry.again_count;
+            self.removing_card = None;
+            self.stack_advance = None;
+        }
+    }
+    /// 取り消しボタン（取り消せるスワイプがあるときだけ）
+    fn draw_undo_button(&self, renderer: &Renderer) {
+        if self.can_undo() {
+            renderer.draw_undo_button(
+                &Point {
+                    x: UNDO_BUTTON_X,
+                    y: UNDO_BUTTON_Y,
+                },
+                UNDO_BUTTON_RADIUS,
+                &renderer.theme().front.accent,
+            );
+        }
+    }
+    /// 設定画面を開くボタン
+    fn draw_settings_button(&self, renderer: &Renderer) {
+        renderer.draw_settings_button(
+            &Point {
+                x: SETTINGS_BUTTON_X,
+                y: SETTINGS_BUTTON_Y,
+            },
+            SETTINGS_BUTTON_RADIUS,
+            &renderer.theme().front.accent,
+        );
+    }
+    /// 学習状況のメッセージ（覚えた枚数・残り枚数・後回しにした回数）
+    fn stats_message(&self) -> String {
+        format!(
+            "学習状況\n覚えた {}/{}\n残り {} 枚\nもう一度 {} 回",
+            self.current_card_index - 1,
+            self.total_cards,
+            self.cards.len(),
+            self.again_count
+        )
+    }
+    /// 読み上げ用の先頭のカードの内容と、プレイ中に行える操作
+    fn mirror(&self) -> Mirror {
+        let progress = Some(format!("カード {}/{}", self.current_card_index, self.total_cards));
+        let Some(card) = self.cards.first() else {
+            return Mirror {
+                progress,
+                ..Mirror::default()
+            };
+        };
+        let mut actions = vec![Action::Flip, Action::Good, Action::Again];
+        if self.can_undo() {
+            actions.push(Action::Undo);
+        }
+        let content = match card.face() {
+            Face::Front => format!("表面: {}", plain_text(card.get_front_text())),
+            Face::Back => {
+                actions.extend([Action::WordFamily, Action::ReplayAudio]);
+                let etymology = describe_etymology(card.get_etymology());
+                if etymology.is_empty() {
+                    format!("裏面: {}", plain_text(card.get_back_text()))
+                } else {
+                    format!("裏面: {}。{}", plain_text(card.get_back_text()), etymology)
+                }
+            }
+        };
+        actions.extend([Action::OpenStats, Action::ToggleFurigana, Action::OpenSettings]);
+        Mirror {
+            progress,
+            content,
+            actions,
+        }
+    }
+    /// 指定した位置のカードを先頭に移動（ワードファミリーからのジャンプ用）
+    /// 移動前の先頭カードと移動したカードは表面に戻す
+    fn bring_to_front(&mut self, index: usize) {
+        if index >= self.cards.len() {
+            return;
+        }
+        if let Some(current) = self.cards.first_mut() {
+            current.reset_card();
+        }
+        if index > 0 {
+            let mut card = self.cards.remove(index);
+            card.reset_card();
+            self.cards.insert(0, card);
+        }
+        self.removing_card = None;
+        self.stack_advance = None;
+    }
+    /// 先頭のカードが飛んでいき始めたら、後ろのカードを手前に送るアニメーションを進める
+    /// 飛んでいくカードが画面から消えたら止める
+    fn update_stack(&mut self, dt: f64) {
+        let flying = self.cards.first().is_some_and(|card| card.is_auto_rotating());
+        if flying && self.stack_advance.is_none() {
+            self.stack_advance = Some(Tween::new(
+                0.0,
+                1.0,
+                STACK_ADVANCE_DURATION,
+                Easing::EaseInOutCubic,
+            ));
+        }
+        if let Some(advance) = &mut self.stack_advance {
+            advance.advance(dt);
+        }
+        if !flying && self.removing_card.is_none() {
+            self.stack_advance = None;
+        }
+    }
+    /// 手前に描くカード（ドラッグなどで動かせる先頭のカード）と、その後ろに重なるカードと重なりの深さ（手前から順に最大{STACK_VISIBLE}枚）
+    /// 削除中のカードが飛んでいる間は、先頭のカードも手前に出てくるまでは重なりの一部として描く
+    fn stack_layout(&self, motion: Motion) -> (Option<&Card>, Vec<(&Card, f32)>) {
+        // 動きを減らす設定では、後ろのカードはすぐに1枚分手前に出る（飛んでいくカードが薄くなって見えてくる）
+        let advance = match (&self.stack_advance, motion) {
+            (Some(_), Motion::Reduced) => 1.0,
+            (advance, _) => advance.as_ref().map_or(0.0, |t| t.value()),
+        };
+        let (front, behind, shift) = match &self.removing_card {
+            Some(_) if advance < 1.0 => (None, self.cards.as_slice(), advance),
+            Some(_) => (self.cards.first(), self.cards.get(1..).unwrap_or(&[]), 0.0),
+            None => (self.cards.first(), self.cards.get(1..).unwrap_or(&[]), advance),
+        };
+        let behind = behind
+            .iter()
+            .take(STACK_VISIBLE)
+            .enumerate()
+            .map(|(k, card)| (card, (k + 1) as f32 - shift))
+            .collect();
+        (front, behind)
     }
     /// カードを描画
-    /// 削除中のカードがある場合はそれを描画し、準備完了なら次のカードも表示
+    /// 先頭のカードの後ろに次の{STACK_VISIBLE}枚を少しずつ上にずらして小さく重ね、残りの枚数がわかるようにする
+    /// 先頭のカードが飛んでいく間は、後ろのカードが1枚分ずつ手前に出てくる
     /// show_progress: プログレスカウンターを表示するかどうか
-    fn draw(&self, _renderer: &Renderer, show_progress: bool) {
-        // 次のカードを先に描画（背面）
-        if self.next_card_ready {
-            if let Some(card) = self.cards.get(0) {
-                card.draw(_renderer);
-            }
+    /// interpolation: 前回の update から次の update までの位置（カードの動きの補間用）
+    fn draw(&self, _renderer: &Renderer, show_progress: bool, interpolation: f64) {
+        let (front, behind) = self.stack_layout(_renderer.motion());
+
+        // 奥のカードから描画（背面）
+        for &(card, depth) in behind.iter().rev() {
+            card.draw_in_stack(_renderer, self.show_ruby, depth);
+        }
+
+        if let Some(card) = front {
+            card.draw(_renderer, self.show_ruby, interpolation);
         }
 
         // 削除中のカードを描画（前面）
         if let Some(removing_card) = &self.removing_card {
-            removing_card.draw(_renderer);
-        } else if !self.next_card_ready {
-            // 削除中でも次カード準備中でもない場合、最初のカードを描画
-            if let Some(card) = self.cards.first() {
-                card.draw(_renderer);
-            }
+            removing_card.draw(_renderer, self.show_ruby, interpolation);
         }
 
         // プログレスカウンターを描画（カードの上部・ケルト風）
         // ゲームクリア時は非表示
         if show_progress {
             // カードの表裏に応じて色を変更
-            let counter_color = if let Some(card) = self.cards.first() {
-                if card.get_face_state() == 0 {
-                    Color::MintGreen.get() // 表面: ミントグリーン
-                } else {
-                    Color::RoyalBlue.get() // 裏面: ロイヤルブルー
-                }
-            } else {
-                Color::MintGreen.get() // デフォルト
-            };
+            let face = self.cards.first().map_or(Face::Front, |card| card.face());
+            let counter_color = &_renderer.theme().palette(face).accent;
 
             let progress_text = format!("{}/{}", self.current_card_index, self.total_cards);
             _renderer.celtic_progress_counter(
@@ -429,18 +1255,83 @@ impl Material {
                     y: PROGRESS_COUNTER_Y,
                 },
                 &progress_text,
-                &counter_color,
+                counter_color,
             );
+            self.draw_undo_button(_renderer);
+            self.draw_settings_button(_renderer);
         }
     }
 }
 
+/// ノートからカードを作る（プレイ中のカードも編集画面のプレビューも同じ作り方で、同じ Card::draw で描く）
+fn card_from_note(note: &Note) -> Card {
+    let etymology: Vec<&str> = note.etymology.iter().map(String::as_str).collect();
+    let tags: Vec<&str> = note.tags.iter().map(String::as_str).collect();
+    Card::new(
+        Point::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0),
+        FLASH_CARD_WIDTH,
+        FLASH_CARD_HEIGHT,
+        &note.front,
+        &note.back,
+        &etymology,
+        Decorations::resolve(&tags, TAG_DECORATIONS, DECK_DECORATIONS),
+    )
+}
+
 impl Default for Material {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// localStorage に保存されたデッキ（保存されていないか、読めないかカードにできなければ組み込みのデッキ）
+fn load_deck() -> Deck {
+    match browser::load_setting(DECK_STORAGE_KEY) {
+        Ok(Some(text)) => Deck::load(&text).unwrap_or_else(|err| {
+            error!("Could not load the saved deck: {:#?}", err);
+            Deck::builtin()
+        }),
+        _ => Deck::builtin(),
+    }
+}
+
+/// localStorage に保存された利用者の設定（保存されていなければ既定の設定）
+/// 以前のテーマだけの保存先が残っていれば、設定のテーマに移して保存し、古い方は消す
+pub fn load_settings() -> Settings {
+    let saved = browser::load_setting(SETTINGS_STORAGE_KEY).ok().flatten();
+    let Ok(Some(legacy_theme)) = browser::load_setting(LEGACY_THEME_STORAGE_KEY) else {
+        return saved.map_or_else(Settings::default, |text| Settings::parse(&text));
+    };
+    let settings = settings_with_legacy_theme(saved.as_deref(), &legacy_theme);
+    if let Err(err) = browser::save_setting(SETTINGS_STORAGE_KEY, &settings.serialize())
+        .and_then(|_| browser::remove_setting(LEGACY_THEME_STORAGE_KEY))
+    {
+        error!("Could not migrate the saved theme: {:#?}", err);
+    }
+    settings
+}
+
+/// 保存された設定に以前のテーマを移す（設定にテーマが保存されていれば、そちらの方が新しいので使う）
+fn settings_with_legacy_theme(saved: Option<&str>, legacy_theme: &str) -> Settings {
+    let mut settings = saved.map_or_else(Settings::default, Settings::parse);
+    let has_theme = saved.is_some_and(|text| {
+        text.split(';')
+            .any(|entry| entry.split_once('=').is_some_and(|(name, _)| name.trim() == "theme"))
+    });
+    if !has_theme {
+        let _ = settings.set("theme", legacy_theme);
+    }
+    settings
+}
+
+/// localStorage に保存されたキー割り当て（保存されていなければ既定の割り当て）
+fn load_key_bindings() -> KeyBindings {
+    match browser::load_setting(KEY_BINDINGS_STORAGE_KEY) {
+        Ok(Some(text)) => KeyBindings::parse(&text),
+        _ => KeyBindings::default(),
+    }
+}
+
 #[async_trait(?Send)]
 impl Game for GameStage {
     /// ゲームの初期化
@@ -449,10 +1340,11 @@ impl Game for GameStage {
         log!("START");
         match &self.machine {
             _none => {
-                let machine = GameStageStateMachine::new(Material::new());
-                Ok(Box::new(GameStage {
-                    machine: Some(machine),
-                }))
+                let material = Material {
+                    bindings: load_key_bindings(),
+                    ..Material::with(load_deck(), load_settings())
+                };
+                Ok(Box::new(GameStage::start(material)))
             }
         }
     }
@@ -460,28 +1352,76 @@ impl Game for GameStage {
     /// ステートマシンのupdateを呼び出し、入力に応じた状態遷移を行う
     fn update(
         &mut self,
+        _dt: f64,
         _keystate: &mut KeyState,
-        _touchstate: &mut TouchState,
-        _mousestate: &mut MouseState,
+        _pointerstate: &mut PointerState,
     ) {
         if let Some(machine) = self.machine.take() {
             self.machine
-                .replace(machine.update(_keystate, _touchstate, _mousestate));
+                .replace(machine.update(_dt, _keystate, _pointerstate));
         }
         assert!(self.machine.is_some());
     }
+    fn mirror(&self) -> Mirror {
+        match &self.machine {
+            Some(machine) => machine.mirror(),
+            None => Mirror::default(),
+        }
+    }
+    fn edit(&mut self, event: EditorEvent) {
+        if let Some(GameStageStateMachine::Editor(state)) = &mut self.machine {
+            state._state.events.push(event);
+        }
+    }
+    fn editor(&self) -> Option<EditorView> {
+        match &self.machine {
+            Some(GameStageStateMachine::Editor(state)) => Some(state._state.view()),
+            _ => None,
+        }
+    }
+    fn take_requests(&mut self) -> Vec<Request> {
+        match &mut self.machine {
+            Some(machine) => std::mem::take(&mut machine.material_mut().requests),
+            None => Vec::new(),
+        }
+    }
     /// ゲーム全体の描画処理
     /// 画面をクリアし、現在の状態に応じたメッセージとカードを描画
-    fn draw(&self, renderer: &Renderer) {
+    fn draw(&self, renderer: &Renderer, interpolation: f64) {
         renderer.clear();
         match &self.machine {
-            Some(GameStageStateMachine::Playing(_state)) => {
-                // 最初のカードのみ描画
-                if let Some(card) = _state.material.cards.first() {
-                    card.draw(renderer);
-                }
+            Some(GameStageStateMachine::Playing(_state)) => {}
+            Some(GameStageStateMachine::WordFamily(_state)) => {
+                renderer.draw_word_family_window(
+                    &Point {
+                        x: SCREEN_WIDTH / 2.0,
+                        y: WORD_FAMILY_MESSAGE_Y,
+                    },
+                    &_state._state.title,
+                    &_state._state.rows(&_state.material),
+                    _state._state.selected,
+                    &_state._state.derived,
+                );
+            }
+            Some(GameStageStateMachine::Settings(_state)) => {
+                renderer.draw_settings_window(
+                    &Point {
+                        x: SCREEN_WIDTH / 2.0,
+                        y: SETTINGS_MESSAGE_Y,
+                    },
+                    &SettingsMenu::rows(&_state.material.settings),
+                    _state._state.selected,
+                );
+            }
+            Some(GameStageStateMachine::DisplayMessage(_state)) => {
+                renderer.draw_message_window(
+                    &Point {
+                        x: SCREEN_WIDTH / 2.0,
+                        y: STATS_MESSAGE_Y,
+                    },
+                    &_state.material.stats_message(),
+                );
             }
-            Some(GameStageStateMachine::DisplayMessage(_state)) => {}
             Some(GameStageStateMachine::GameOver(_state)) => {
                 let _ = renderer.text(
                     &Point {
@@ -491,7 +1431,7 @@ impl Game for GameStage {
                     GAMEOVER_MESSAGE,
                     Align::Center,
                     Font::Middle,
-                    Color::Green,
+                    &renderer.theme().front.card,
                 );
             }
             Some(GameStageStateMachine::GameClear(_state)) => {
@@ -502,11 +1442,571 @@ impl Game for GameStage {
                     },
                     GAMECLEAR_MESSAGE,
                 );
+                _state.material.draw_undo_button(renderer);
             }
             _ => {}
         }
         if let Some(machine) = &self.machine {
-            machine.draw(renderer);
+            machine.draw(renderer, interpolation);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::FixedStep;
+    use crate::input::{InputEvent, KeyInput, PointerAction};
+    use crate::replay::Replay;
+
+    fn material(game: &GameStage) -> &Material {
+        match game.machine.as_ref().expect("started") {
+            GameStageStateMachine::Playing(state) => &state.material,
+            GameStageStateMachine::WordFamily(state) => &state.material,
+            GameStageStateMachine::Settings(state) => &state.material,
+            GameStageStateMachine::Editor(state) => &state.material,
+            GameStageStateMachine::DisplayMessage(state) => &state.material,
+            GameStageStateMachine::GameOver(state) => &state.material,
+            GameStageStateMachine::GameClear(state) => &state.material,
+        }
+    }
+
+    /// ブラウザなしでステートマシンを動かすテスト用のループ
+    /// GameLoop の1ティックと同じ順番で進め、時計はティックの数から決める（実際の時刻は使わない）
+    struct Harness {
+        game: GameStage,
+        keystate: KeyState,
+        pointerstate: PointerState,
+        step: f64,  // 1ティックの長さ（ms）
+        ticks: u64, // これまでの update の回数
+    }
+    impl Harness {
+        fn new() -> Self {
+            Harness::with(Material::new())
+        }
+        fn with(material: Material) -> Self {
+            Harness {
+                game: GameStage::start(material),
+                keystate: KeyState::new(),
+                pointerstate: PointerState::new(),
+                step: FixedStep::new(TICK_RATE).step(),
+                ticks: 0,
+            }
+        }
+        fn now(&self) -> f64 {
+            self.ticks as f64 * self.step
+        }
+        fn material(&self) -> &Material {
+            material(&self.game)
+        }
+        fn state(&self) -> &'static str {
+            match self.game.machine.as_ref().expect("started") {
+                GameStageStateMachine::Playing(_) => "Playing",
+                GameStageStateMachine::WordFamily(_) => "WordFamily",
+                GameStageStateMachine::Settings(_) => "Settings",
+                GameStageStateMachine::Editor(_) => "Editor",
+                GameStageStateMachine::DisplayMessage(_) => "DisplayMessage",
+                GameStageStateMachine::GameOver(_) => "GameOver",
+                GameStageStateMachine::GameClear(_) => "GameClear",
+            }
+        }
+        fn input(&mut self, input: InputEvent) {
+            input.apply(&mut self.keystate, &mut self.pointerstate);
+        }
+        fn tick(&mut self) {
+            self.pointerstate.tick(self.now());
+            self.game
+                .update(self.step, &mut self.keystate, &mut self.pointerstate);
+            self.pointerstate.clear_gestures();
+            self.keystate.clear_just_pressed();
+            self.ticks += 1;
+        }
+        fn run(&mut self, ms: f64) {
+            for _ in 0..(ms / self.step).ceil() as u64 {
+                self.tick();
+            }
+        }
+        /// 条件を満たすまで最大 ms だけ進める（満たしたら true）
+        fn run_until(&mut self, ms: f64, done: impl Fn(&Material) -> bool) -> bool {
+            for _ in 0..(ms / self.step).ceil() as u64 {
+                if done(self.material()) {
+                    return true;
+                }
+                self.tick();
+            }
+            done(self.material())
+        }
+        /// キーを押して次のティックで離す
+        fn press(&mut self, code: &str) {
+            self.input(InputEvent::Key(KeyInput::Down {
+                code: code.to_string(),
+                repeat: false,
+            }));
+            self.tick();
+            self.input(InputEvent::Key(KeyInput::Up {
+                code: code.to_string(),
+            }));
+            self.tick();
+        }
+        /// その場で押して離す（タップ）
+        fn tap(&mut self, x: i32, y: i32) {
+            let time = self.now();
+            self.input(InputEvent::Pointer(PointerAction::Down { id: 1, x, y, time }));
+            self.tick();
+            let time = self.now();
+            self.input(InputEvent::Pointer(PointerAction::Up { id: 1, x, y, time }));
+            self.tick();
+        }
+        /// カードの中央から横へ素早く動かして離す（フリック）
+        fn swipe(&mut self, direction: SwipeDirection) {
+            let sign = match direction {
+                SwipeDirection::Left => -1,
+                SwipeDirection::Right => 1,
+            };
+            let (x, y) = ((SCREEN_WIDTH / 2.0) as i32, (SCREEN_HEIGHT / 2.0) as i32);
+            let time = self.now();
+            self.input(InputEvent::Pointer(PointerAction::Down { id: 1, x, y, time }));
+            for i in 1..=4 {
+                self.tick();
+                let time = self.now();
+                let x = x + sign * 40 * i;
+                self.input(InputEvent::Pointer(PointerAction::Move { id: 1, x, y, time }));
+            }
+            self.tick();
+            let time = self.now();
+            let x = x + sign * 160;
+            self.input(InputEvent::Pointer(PointerAction::Up { id: 1, x, y, time }));
+            self.tick();
+        }
+        /// スワイプして、カードが飛んでいって消えるまで進める
+        fn swipe_and_settle(&mut self, direction: SwipeDirection) {
+            self.swipe(direction);
+            self.run(FLASH_CARD_SWIPE_OUT_DURATION + 200.0);
+        }
+    }
+
+    fn fronts(material: &Material) -> Vec<&str> {
+        material.cards.iter().map(|card| card.get_front_text()).collect()
+    }
+
+    #[test]
+    fn recorded_session_replays_to_the_same_material() {
+        let replay = Replay::parse(include_str!("../tests/replays/swipe_left_then_again.replay"))
+            .unwrap();
+        let mut game = GameStage::start(Material::new());
+        replay.run(&mut game);
+
+        assert!(matches!(game.machine, Some(GameStageStateMachine::Playing(_))));
+        let material = material(&game);
+        assert_eq!(material.current_card_index, 2);
+        assert_eq!(material.again_count, 1);
+        assert!(!material.show_ruby);
+        assert!(material.removing_card.is_none());
+        assert_eq!(material.undo_stack.len(), 2);
+        assert_eq!(fronts(material), [ITEMS[2].0, ITEMS[3].0, ITEMS[4].0, ITEMS[1].0]);
+        assert_eq!(material.cards[0].face(), Face::Front);
+    }
+
+    #[test]
+    fn left_swipe_removes_the_card_and_advances_the_counter() {
+        let mut harness = Harness::new();
+        harness.swipe(SwipeDirection::Left);
+        assert!(harness.run_until(1000.0, |m| m.removing_card.is_some()));
+
+        let material = harness.material();
+        assert_eq!(harness.state(), "Playing");
+        assert_eq!(material.current_card_index, 2);
+        assert_eq!(fronts(material), [ITEMS[1].0, ITEMS[2].0, ITEMS[3].0, ITEMS[4].0]);
+        assert_eq!(
+            material.removing_card.as_ref().map(|card| card.get_front_text()),
+            Some(ITEMS[0].0)
+        );
+        assert!(!material.cards[0].is_auto_rotating());
+    }
+
+    #[test]
+    fn word_family_lists_cards_already_learned_in_the_session() {
+        let deck = Deck::parse(
+            "front 丈夫{じょうぶ}な\nback durable\netymology dūrābilis/ラテン語: 長持ちする\netymology endure（耐える）: en- + dūrus\n\n\
+             front 硬{かた}い\nback hard\netymology dūrus/ラテン語: 硬い\n\n\
+             front 職業{しょくぎょう}\nback profession\netymology prōfessiō/ラテン語: 公の宣言\netymology dūrus: 硬い\n",
+        )
+        .unwrap();
+        let settings = Settings {
+            session_size: 3,
+            ..Settings::default()
+        };
+        let mut harness = Harness::with(Material::with(deck, settings));
+        harness.swipe_and_settle(SwipeDirection::Left);
+        assert_eq!(fronts(harness.material()), ["硬{かた}い", "職業{しょくぎょう}"]);
+
+        // 裏返してから開くと、学び終えた最初のカードも一覧に出る
+        harness.press("Space");
+        harness.run(1000.0);
+        harness.press("KeyF");
+        assert_eq!(harness.state(), "WordFamily");
+        assert!(harness.game.mirror().content.ends_with("1/3: durable"), "{}", harness.game.mirror().content);
+
+        // セッションに残っているカードを選ぶとそのカードへ移る
+        harness.press("ArrowDown");
+        harness.press("ArrowDown");
+        harness.press("Enter");
+        assert_eq!(harness.state(), "Playing");
+        assert_eq!(fronts(harness.material())[0], "職業{しょくぎょう}");
+    }
+
+    #[test]
+    fn stack_moves_up_one_card_while_the_top_card_flies_away() {
+        // 後ろのカードの中心の高さ（先頭のカードの中心から上にずれた量）と大きさの倍率
+        fn offsets(material: &Material) -> Vec<(f32, f32)> {
+            let (_, behind) = material.stack_layout(Motion::Full);
+            behind
+                .iter()
+                .map(|(card, depth)| {
+                    let (center, scale) = card.stack_pose(*depth);
+                    (SCREEN_HEIGHT / 2.0 - center.y, scale)
+                })
+                .collect()
+        }
+        let settled = [
+            (STACK_OFFSET_Y, 1.0 - STACK_SCALE_STEP),
+            (STACK_OFFSET_Y * 2.0, 1.0 - STACK_SCALE_STEP * 2.0),
+            (STACK_OFFSET_Y * 3.0, 1.0 - STACK_SCALE_STEP * 3.0),
+        ];
+        let mut harness = Harness::new();
+        assert_eq!(offsets(harness.material()), settled);
+
+        // 飛んでいく途中では、どのカードも同じだけ（1枚分より少なく）手前に出ている
+        harness.swipe(SwipeDirection::Left);
+        harness.run(STACK_ADVANCE_DURATION / 2.0);
+        let shift = harness.material().stack_advance.as_ref().expect("advancing").value();
+        assert!(0.0 < shift && shift < 1.0, "{}", shift);
+        let moving = offsets(harness.material());
+        assert_eq!(moving.len(), STACK_VISIBLE);
+        for (k, &(offset, scale)) in moving.iter().enumerate() {
+            let depth = (k + 1) as f32 - shift;
+            assert!((offset - STACK_OFFSET_Y * depth).abs() < 1e-3, "{:?}", moving);
+            assert!((scale - (1.0 - STACK_SCALE_STEP * depth)).abs() < 1e-6, "{:?}", moving);
+        }
+
+        // 飛び終わると次のカードが先頭になり、残りは元の重なり方に戻る
+        harness.run(2000.0);
+        let material = harness.material();
+        assert!(material.removing_card.is_none() && material.stack_advance.is_none());
+        assert_eq!(fronts(material)[0], ITEMS[1].0);
+        assert_eq!(offsets(material), settled);
+    }
+
+    #[test]
+    fn stack_shows_the_same_face_as_the_top_card() {
+        let settings = Settings {
+            direction: Face::Back,
+            ..Settings::default()
+        };
+        let material = Material::with(Deck::builtin(), settings);
+        let backend = std::rc::Rc::new(crate::backend::RecordingBackend::new(450, 600));
+        let renderer = Renderer::new(backend.clone(), crate::theme::Theme::celtic());
+        material.draw(&renderer, false, 1.0);
+        let texts: Vec<String> = backend
+            .calls_named("fill_text")
+            .iter()
+            .filter_map(|call| call.split('"').nth(1).map(|text| text.to_string()))
+            .collect();
+
+        // 奥のカードから順に、どのカードも裏面の英文を描く
+        let backs: Vec<&str> = texts
+            .iter()
+            .map(String::as_str)
+            .filter(|text| ITEMS.iter().any(|item| item.1 == *text))
+            .collect();
+        assert_eq!(backs, [ITEMS[3].1, ITEMS[2].1, ITEMS[1].1, ITEMS[0].1]);
+        // 表面のルビの親文字（例: 教職）はどこにも描かない
+        let bases: Vec<String> = ITEMS[..4]
+            .iter()
+            .flat_map(|item| crate::ruby::parse_ruby(item.0))
+            .filter(|segment| segment.reading.is_some())
+            .map(|segment| segment.base)
+            .collect();
+        assert!(texts.iter().all(|text| !bases.contains(text)), "{:?}", texts);
+    }
+
+    #[test]
+    fn removing_card_lives_until_it_leaves_the_screen_or_is_undone() {
+        let mut harness = Harness::new();
+        harness.swipe(SwipeDirection::Left);
+        assert!(harness.run_until(1000.0, |m| m.removing_card.is_some()));
+        harness.tick();
+        assert!(harness.material().removing_card.is_some(), "まだ飛んでいる途中");
+        assert!(harness.run_until(FLASH_CARD_SWIPE_OUT_DURATION, |m| m.removing_card.is_none()));
+
+        // 飛んでいる途中で取り消すと、飛んでいるカードは消えて元の位置に戻る
+        harness.swipe(SwipeDirection::Left);
+        assert!(harness.run_until(1000.0, |m| m.removing_card.is_some()));
+        harness.press("KeyZ");
+        let material = harness.material();
+        assert!(material.removing_card.is_none());
+        assert_eq!(material.current_card_index, 2);
+        assert_eq!(fronts(material)[0], ITEMS[1].0);
+    }
+
+    #[test]
+    fn right_swipe_requeues_the_card_face_up() {
+        let mut harness = Harness::new();
+        harness.press("Space");
+        harness.run(FLASH_CARD_FLIP_DURATION);
+        assert_eq!(harness.material().cards[0].face(), Face::Back);
+
+        harness.swipe(SwipeDirection::Right);
+        assert!(harness.run_until(1000.0, |m| m.removing_card.is_some()));
+        let material = harness.material();
+        assert_eq!(material.current_card_index, 1);
+        assert_eq!(material.again_count, 1);
+        assert_eq!(fronts(material), [ITEMS[1].0, ITEMS[2].0, ITEMS[3].0, ITEMS[4].0, ITEMS[0].0]);
+        assert_eq!(material.cards[4].face(), Face::Front);
+        assert!(!material.cards[4].is_auto_rotating());
+    }
+
+    #[test]
+    fn swiping_the_last_card_right_keeps_it_in_play() {
+        let mut harness = Harness::new();
+        for _ in 0..4 {
+            harness.swipe_and_settle(SwipeDirection::Left);
+        }
+        assert_eq!(fronts(harness.material()), [ITEMS[4].0]);
+
+        harness.swipe_and_settle(SwipeDirection::Right);
+        let material = harness.material();
+        assert_eq!(harness.state(), "Playing");
+        assert_eq!(fronts(material), [ITEMS[4].0]);
+        assert_eq!((material.current_card_index, material.again_count), (5, 1));
+        assert!(material.removing_card.is_none());
+        assert!(!material.cards[0].is_auto_rotating());
+
+        harness.swipe_and_settle(SwipeDirection::Left);
+        assert_eq!(harness.state(), "GameClear");
+        let material = harness.material();
+        assert!(material.cards.is_empty() && material.removing_card.is_none());
+        assert_eq!(material.current_card_index, 6);
+    }
+
+    #[test]
+    fn game_clear_starts_a_new_game_keeping_the_settings() {
+        let mut harness = Harness::new();
+        harness.press("KeyR");
+        for _ in 0..FLASH_CARD_NUMBERS {
+            harness.swipe_and_settle(SwipeDirection::Left);
         }
+        assert_eq!(harness.state(), "GameClear");
+
+        // 取り消すと最後のカードに戻り、もう一度めくるとクリアに戻る
+        harness.press("KeyZ");
+        assert_eq!(harness.state(), "Playing");
+        assert_eq!(fronts(harness.material()), [ITEMS[4].0]);
+        harness.swipe_and_settle(SwipeDirection::Left);
+        assert_eq!(harness.state(), "GameClear");
+
+        harness.press("Space");
+        assert_eq!(harness.state(), "Playing");
+        let material = harness.material();
+        assert_eq!(material.cards.len(), FLASH_CARD_NUMBERS as usize);
+        assert_eq!((material.current_card_index, material.again_count), (1, 0));
+        assert!(!material.can_undo());
+        assert!(!material.show_ruby, "ルビの設定は新しいゲームでも引き継ぐ");
+    }
+
+    #[test]
+    fn mirror_follows_the_card_and_buttons_drive_the_game() {
+        let mut harness = Harness::new();
+        let mirror = harness.game.mirror();
+        assert_eq!(mirror.progress.as_deref(), Some("カード 1/5"));
+        assert_eq!(mirror.content, "表面: ハイキング用の丈夫な靴が必要です");
+        assert!(!mirror.actions.contains(&Action::Undo));
+        assert!(!mirror.actions.contains(&Action::WordFamily));
+
+        // 読み上げ用のボタンはキー割り当てに関係なく操作を伝える
+        harness.press(&Action::Flip.button_code());
+        harness.run(FLASH_CARD_FLIP_DURATION);
+        let mirror = harness.game.mirror();
+        assert!(
+            mirror
+                .content
+                .starts_with("裏面: I need durable shoes for hiking。語源: dūrābilis（ラテン語）"),
+            "{}",
+            mirror.content
+        );
+        assert!(mirror.actions.contains(&Action::WordFamily));
+
+        harness.press(&Action::Good.button_code());
+        harness.run(FLASH_CARD_SWIPE_OUT_DURATION + 200.0);
+        let mirror = harness.game.mirror();
+        assert_eq!(mirror.progress.as_deref(), Some("カード 2/5"));
+        assert!(mirror.actions.contains(&Action::Undo));
+
+        harness.press(&Action::OpenStats.button_code());
+        let mirror = harness.game.mirror();
+        assert_eq!(harness.state(), "DisplayMessage");
+        assert!(mirror.content.starts_with("学習状況。覚えた 1/5。"), "{}", mirror.content);
+        assert_eq!(mirror.actions, [Action::Cancel]);
+    }
+
+    #[test]
+    fn reduced_motion_cross_fades_instead_of_turning_the_card() {
+        use crate::backend::RecordingBackend;
+        use crate::theme::Theme;
+        use std::rc::Rc;
+
+        let mut harness = Harness::new();
+        harness.press("Space");
+        harness.run(FLASH_CARD_FLIP_DURATION / 2.0);
+        assert!(harness.material().cards[0].is_flipping());
+
+        let draw = |motion| {
+            let backend = Rc::new(RecordingBackend::new(450, 600));
+            let mut renderer = Renderer::new(backend.clone(), Theme::celtic());
+            renderer.set_motion(motion);
+            harness.material().draw(&renderer, true, 1.0);
+            backend
+        };
+        let full = draw(Motion::Full);
+        assert!(!full.calls_named("draw_image").is_empty(), "遠近法で短冊に分けて描く");
+
+        // 表面の上に裏面を半分の濃さで重ね、どちらも傾けない
+        let reduced = draw(Motion::Reduced);
+        assert!(reduced.calls_named("draw_image").is_empty());
+        assert!(reduced.calls_named("rotate").iter().all(|call| call == "rotate(0)"));
+        let alphas = reduced.calls_named("set_global_alpha");
+        assert!(alphas.iter().any(|call| call.starts_with("set_global_alpha(0.5")), "{:?}", alphas);
+    }
+
+    #[test]
+    fn settings_screen_changes_apply_live_and_size_waits_for_the_next_session() {
+        let mut harness = Harness::new();
+        harness.press("KeyO");
+        assert_eq!(harness.state(), "Settings");
+        assert_eq!(harness.game.mirror().content, "設定: セッションの枚数、5 枚（1/12）");
+
+        // 左右キーで値を変え、変えるたびに保存と反映を依頼する
+        harness.press("ArrowRight");
+        harness.press("ArrowDown");
+        harness.press("ArrowDown");
+        harness.press("ArrowDown");
+        harness.press("ArrowRight");
+        assert_eq!(harness.game.mirror().content, "設定: テーマ、minimal（4/12）");
+        let requests = harness.game.take_requests();
+        assert_eq!(requests.len(), 2);
+        let Some(Request::ApplySettings(applied)) = requests.last() else {
+            panic!("{:?}", requests);
+        };
+        assert_eq!((applied.session_size, applied.theme.as_str()), (6, "minimal"));
+
+        // スワイプの感度の行のスライダーの右端をタップ（ウィンドウの上端は 60、行は 64 + 30 × 5）
+        harness.tap(370, 60 + 64 + 30 * 5 + 14);
+        assert_eq!(harness.material().settings.swipe_sensitivity, SWIPE_SENSITIVITY_MAX);
+        assert!(harness.game.mirror().content.starts_with("設定: スワイプの感度"));
+
+        // 「閉じる」をタップするとカードに戻る。枚数は次のセッションから
+        harness.tap(225, 60 + 64 + 30 * 11 + 14);
+        assert_eq!(harness.state(), "Playing");
+        let material = harness.material();
+        assert_eq!(material.total_cards, FLASH_CARD_NUMBERS);
+        assert_eq!(material.reset().cards.len(), 6);
+    }
+
+    #[test]
+    fn editor_validates_saves_and_restarts_with_the_new_deck() {
+        let mut harness = Harness::new();
+        harness.press("KeyO");
+        // 「デッキを編集」をタップすると編集画面になり、フォームに最初のカードを見せる
+        harness.tap(225, 60 + 64 + 30 * 10 + 14);
+        assert_eq!(harness.state(), "Editor");
+        let view = harness.game.editor().expect("editor is open");
+        assert_eq!((view.note, view.position.as_str()), (Deck::builtin().notes[0].clone(), "カード 1/5"));
+
+        // 入力に問題があればメッセージを出し、保存しない
+        harness.game.edit(EditorEvent::Add);
+        harness.game.edit(EditorEvent::Change(Note {
+            front: "[丈夫{じょうぶ}".to_string(),
+            back: "durable".to_string(),
+            ..Note::default()
+        }));
+        harness.game.edit(EditorEvent::Save);
+        harness.tick();
+        let view = harness.game.editor().expect("editor is open");
+        assert_eq!(view.position, "カード 2/6");
+        assert_eq!(
+            view.message.as_deref(),
+            Some("カード 2: 表面の { } または [ ] の対応が取れていません")
+        );
+        assert!(harness.game.take_requests().is_empty());
+
+        // 直して最初のカードを消して保存すると、保存を依頼する
+        harness.game.edit(EditorEvent::Change(Note {
+            front: "[丈夫{じょうぶ}]な靴".to_string(),
+            back: "durable shoes".to_string(),
+            ..Note::default()
+        }));
+        harness.game.edit(EditorEvent::Previous);
+        harness.game.edit(EditorEvent::Delete);
+        harness.game.edit(EditorEvent::Save);
+        harness.tick();
+        let view = harness.game.editor().expect("editor is open");
+        assert_eq!((view.note.back.as_str(), view.message.as_deref()), ("durable shoes", Some("保存しました")));
+        let requests = harness.game.take_requests();
+        let [Request::SaveDeck(saved)] = requests.as_slice() else {
+            panic!("{:?}", requests);
+        };
+        assert_eq!(saved.notes.len(), 5);
+
+        // 閉じると新しいデッキで最初からやり直す
+        harness.game.edit(EditorEvent::Close);
+        harness.tick();
+        assert_eq!(harness.state(), "Playing");
+        assert!(harness.game.editor().is_none());
+        assert_eq!(fronts(harness.material())[0], "[丈夫{じょうぶ}]な靴");
+    }
+
+    #[test]
+    fn legacy_theme_moves_into_the_settings_unless_they_already_have_one() {
+        // テーマだけを保存していたころの利用者（設定はまだ保存していない、またはテーマのない設定）
+        assert_eq!(settings_with_legacy_theme(None, "dark").theme, "dark");
+        let settings = settings_with_legacy_theme(Some("audio=on"), "minimal");
+        assert_eq!((settings.theme.as_str(), settings.audio), ("minimal", true));
+        // 設定画面でテーマを選んだあとなら、そちらを使う
+        assert_eq!(settings_with_legacy_theme(Some("theme=celtic;audio=off"), "dark").theme, "celtic");
+        // 知らない名前は移さない
+        assert_eq!(settings_with_legacy_theme(None, "neon").theme, DECK_THEME);
+    }
+
+    #[test]
+    fn scheduler_direction_and_audio_follow_the_settings() {
+        let settings = Settings {
+            scheduler: crate::settings::Scheduler::Soon,
+            audio: true,
+            ..Settings::default()
+        };
+        let mut harness = Harness::with(Material::with(Deck::builtin(), settings.clone()));
+
+        // 裏面（英文）になるときに読み上げる
+        harness.press("Space");
+        assert_eq!(
+            harness.game.take_requests(),
+            [Request::Speak("I need durable shoes for hiking".to_string())]
+        );
+
+        // 「もう一度」のカードは {SCHEDULER_SOON_GAP} 枚後に戻る
+        harness.swipe_and_settle(SwipeDirection::Right);
+        assert_eq!(
+            fronts(harness.material()),
+            [ITEMS[1].0, ITEMS[2].0, ITEMS[3].0, ITEMS[0].0, ITEMS[4].0]
+        );
+
+        // 裏面から出題する向きなら、後回しにしたカードも裏面に戻す
+        let mut harness = Harness::with(Material::with(Deck::builtin(), Settings {
+            direction: Face::Back,
+            ..settings
+        }));
+        assert!(harness.material().cards.iter().all(|card| card.face() == Face::Back));
+        harness.swipe_and_settle(SwipeDirection::Right);
+        assert_eq!(harness.material().cards[3].face(), Face::Back);
     }
 }
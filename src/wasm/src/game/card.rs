@@ -1,97 +1,223 @@
 pub mod card {
+    use crate::animation::{Easing, Tween};
     use crate::common::*;
-    use crate::game::{Align, Color, Font, Point, Renderer};
+    use crate::decoration::Decorations;
+    use crate::engine::CardFace;
+    use crate::etymology::Etymology;
+    use crate::gesture::SwipeDirection;
+    use crate::settings::Motion;
+    use crate::game::{Align, Font, Point, Renderer};
+    use crate::theme::Face;
+
+    /// 0 に向かって戻る臨界減衰のばね
+    /// 1ステップを解析解で進めるので、ティックが長くても発散しない
+    #[derive(Clone, Copy, Default)]
+    struct Spring {
+        value: f32,
+        velocity: f32,
+    }
+    impl Spring {
+        /// dt 秒進める
+        fn step(&mut self, dt: f32) {
+            let omega = FLASH_CARD_SPRING_FREQUENCY;
+            let decay = (-omega * dt).exp();
+            let slope = self.velocity + omega * self.value;
+            self.value = (self.value + slope * dt) * decay;
+            self.velocity = (self.velocity - omega * slope * dt) * decay;
+            if self.value.abs() < 0.5 && self.velocity.abs() < 5.0 {
+                *self = Spring::default();
+            }
+        }
+    }
+
+    /// 描画に使う見た目の状態（update の前後を補間して描画する）
+    #[derive(Clone, Copy, Default)]
+    struct Pose {
+        rotate: f32,
+        drag_x: f32,
+        drag_y: f32,
+        flip_angle: f32,
+        alpha: f32,
+    }
+    impl Pose {
+        fn lerp(&self, to: &Pose, t: f32) -> Pose {
+            let mix = |a: f32, b: f32| a + (b - a) * t;
+            Pose {
+                rotate: mix(self.rotate, to.rotate),
+                drag_x: mix(self.drag_x, to.drag_x),
+                drag_y: mix(self.drag_y, to.drag_y),
+                // フリップが終わって 0 に戻った（または始まった）ティックは補間しない
+                flip_angle: if to.flip_angle >= self.flip_angle {
+                    mix(self.flip_angle, to.flip_angle)
+                } else {
+                    to.flip_angle
+                },
+                alpha: mix(self.alpha, to.alpha),
+            }
+        }
+    }
 
     #[derive(Clone, Default)]
     pub struct Card {
         cp: Point,              // center of the card
         width: f32,             // card width
         height: f32,            // card height
-        color: Color,           // color of the card (deprecated, use front_color/back_color)
         rotate: f32,            // angle
         front_text: String,     // text on the front of the card
         back_text: String,      // text on the back of the card
-        etymology: Vec<String>, // etymologies on the back of the card
+        etymology: Etymology,   // etymology on the back of the card
+        decorations: Option<Decorations>, // デッキ・タグで指定された装飾（None ならテーマの既定）
         face_state: i32,        // 0: front, 1: back
+        start_face: i32,        // 最初に見せる面（出題の向きの設定。後回しにしたときもこの面に戻す）
         alpha: f32,
         auto_rotating: i32, // rotate direction: 0: none, 1: right, -1:left
+        swipe_out: Option<(Tween, Tween)>, // 自動回転で飛んでいくアニメーション（傾き・透明度）
         flip_angle: f32,    // フリップアニメーション用の角度 (0.0 ~ π)
-        is_flipping: bool,  // フリップアニメーション中かどうか
-        front_color: Color, // 表面の色
-        back_color: Color,  // 裏面の色
+        flip: Option<Tween>, // フリップアニメーション（None: フリップしていない）
+        flip_from_face: i32, // フリップ開始時の面（半分回ったところで反対の面になる）
+        drag_x: Spring,     // 指・マウスに追従する横方向の移動量（論理px）
+        drag_y: Spring,     // 指・マウスに追従する縦方向の移動量（論理px）
+        dragging: bool,     // ドラッグ中かどうか（離すとばねで元の位置に戻る）
+        previous: Pose,     // 前回の update 開始時の見た目（補間用）
     }
     impl Card {
         pub fn new(
             cp: Point,
             width: f32,
             height: f32,
-            color: Color,
             front_text: &str,
             back_text: &str,
             etymology: &[&str],
+            decorations: Option<Decorations>,
         ) -> Self {
             Card {
                 cp: cp,                             // Center of the Card
                 width: width,                       // Card Width
                 height: height,                     // Card Height
-                color: color,                       // Card Color (deprecated)
                 rotate: 0.0,                        // Card Rotate
                 front_text: front_text.to_string(), // Card Front Text
                 back_text: back_text.to_string(),   // Card Back Text
                 face_state: 0,
+                start_face: 0,
                 alpha: 1.0,
                 auto_rotating: 0, // 0:non_rotate 1:rotate
+                swipe_out: None,
                 flip_angle: 0.0,
-                is_flipping: false,
-                front_color: Color::Green,    // 表面は緑色（日本語）
-                back_color: Color::RoyalBlue, // 裏面はロイヤルブルー（英語）
-                etymology: etymology.iter().map(|s| s.to_string()).collect(),
+                flip: None,
+                flip_from_face: 0,
+                etymology: Etymology::parse(etymology), // 旧形式の文字列から変換
+                decorations,
+                drag_x: Spring::default(),
+                drag_y: Spring::default(),
+                dragging: false,
+                previous: Pose {
+                    alpha: 1.0,
+                    ..Pose::default()
+                },
             }
         }
-        pub fn rotate_left(&mut self) {
-            self.rotate -= FLASH_CARD_ROTATE_SPEED;
+        /// 矢印キーでカードを左へ送る（押している間ドラッグと同じように動く）
+        /// dt: 前回の update からの経過時間（ms）
+        pub fn rotate_left(&mut self, dt: f64) {
+            self.push_by_key(-FLASH_CARD_KEY_DRAG_SPEED * (dt / 1000.0) as f32);
+        }
+        pub fn rotate_right(&mut self, dt: f64) {
+            self.push_by_key(FLASH_CARD_KEY_DRAG_SPEED * (dt / 1000.0) as f32);
+        }
+        fn push_by_key(&mut self, dx: f32) {
+            self.drag_to(self.drag_x.value + dx, self.drag_y.value);
 
-            // 閾値を超えたら自動回転開始
-            if self.rotate < -FLASH_CARD_REMOVING_POINT_ROTATE && self.auto_rotating == 0 {
-                self.auto_rotating = -1;
+            // スワイプの確定距離を超えたら自動回転開始
+            if self.drag_x.value.abs() >= SWIPE_COMMIT_DISTANCE as f32 {
+                self.start_swipe_out(self.drag_x.value.signum() as i32);
             }
         }
-        pub fn rotate_right(&mut self) {
-            self.rotate += FLASH_CARD_ROTATE_SPEED;
-
-            // 閾値を超えたら自動回転開始
-            if self.rotate > FLASH_CARD_REMOVING_POINT_ROTATE && self.auto_rotating == 0 {
-                self.auto_rotating = 1;
+        /// ドラッグ量（押した位置からの移動量）にカードを追従させる（確定はしない）
+        pub fn drag_to(&mut self, dx: f32, dy: f32) {
+            self.dragging = true;
+            self.drag_x = Spring { value: dx, velocity: 0.0 };
+            self.drag_y = Spring { value: dy, velocity: 0.0 };
+            self.rotate = self.drag_rotate();
+        }
+        /// スワイプを確定して自動回転を開始（ドラッグした位置から飛んでいく）
+        pub fn commit_swipe(&mut self, direction: SwipeDirection) {
+            self.dragging = false;
+            self.start_swipe_out(match direction {
+                SwipeDirection::Left => -1,
+                SwipeDirection::Right => 1,
+            });
+        }
+        /// 今の傾きから画面外へ回転しながら消えていく
+        fn start_swipe_out(&mut self, direction: i32) {
+            self.auto_rotating = direction;
+            self.swipe_out = Some((
+                Tween::new(
+                    self.rotate,
+                    FLASH_CARD_SWIPE_OUT_ROTATE * direction as f32,
+                    FLASH_CARD_SWIPE_OUT_DURATION,
+                    Easing::EaseInCubic,
+                ),
+                Tween::new(
+                    self.alpha,
+                    0.0,
+                    FLASH_CARD_SWIPE_OUT_DURATION,
+                    Easing::Linear,
+                ),
+            ));
+        }
+        /// 確定せずに離したときは、ばねで元の位置に戻す
+        pub fn release_drag(&mut self) {
+            self.dragging = false;
+        }
+        fn drag_rotate(&self) -> f32 {
+            self.drag_x.value * FLASH_CARD_DRAG_ROTATE
+        }
+        fn pose(&self) -> Pose {
+            Pose {
+                rotate: self.rotate,
+                drag_x: self.drag_x.value,
+                drag_y: self.drag_y.value,
+                flip_angle: self.flip_angle,
+                alpha: self.alpha,
             }
         }
-        pub fn update(&mut self) {
-            // フリップアニメーション処理
-            if self.is_flipping {
-                self.flip_angle += 0.4; // フリップ速度（0.4 より大きい数値は設定不可）
+        /// 補間せずに今の見た目から描画し直す（状態を巻き戻したとき）
+        fn snap(&mut self) {
+            self.previous = self.pose();
+        }
+        /// アニメーションを dt ミリ秒進める
+        pub fn update(&mut self, dt: f64) {
+            self.previous = self.pose();
 
-                // 半分（π/2）まで回転したら表裏を切り替え
-                if self.flip_angle >= std::f32::consts::PI / 2.0
-                    && self.flip_angle - 0.35 < std::f32::consts::PI / 2.0
-                {
-                    self.face_state = if self.face_state == 0 { 1 } else { 0 };
-                }
+            // フリップアニメーション処理（半分（π/2）まで回転したら表裏を切り替え）
+            if let Some(flip) = &mut self.flip {
+                self.flip_angle = flip.advance(dt);
+                let turned = self.flip_angle >= std::f32::consts::FRAC_PI_2;
+                self.face_state = if turned {
+                    1 - self.flip_from_face
+                } else {
+                    self.flip_from_face
+                };
 
                 // 180度（π）回転したらアニメーション終了
-                if self.flip_angle >= std::f32::consts::PI {
+                if flip.is_finished() {
                     self.flip_angle = 0.0;
-                    self.is_flipping = false;
+                    self.flip = None;
                 }
             }
 
-            // 自動回転中なら継続
-            if self.auto_rotating == 1 {
-                self.rotate += FLASH_CARD_ROTATE_SPEED;
-                // alphaを徐々に減少（フェードアウト効果）
-                self.alpha = (self.alpha - 0.1).max(0.0);
-            } else if self.auto_rotating == -1 {
-                self.rotate -= FLASH_CARD_ROTATE_SPEED;
-                // alphaを徐々に減少（フェードアウト効果）
-                self.alpha = (self.alpha - 0.1).max(0.0);
+            // 離したあとはばねで元の位置へ戻る
+            if !self.dragging && self.auto_rotating == 0 {
+                let dt = (dt / 1000.0) as f32;
+                self.drag_x.step(dt);
+                self.drag_y.step(dt);
+                self.rotate = self.drag_rotate();
+            }
+
+            // 自動回転中なら継続（回転しながらフェードアウト）
+            if let Some((rotate, alpha)) = &mut self.swipe_out {
+                self.rotate = rotate.advance(dt);
+                self.alpha = alpha.advance(dt);
             }
         }
         pub fn get_rotate(&self) -> f32 {
@@ -108,25 +234,49 @@ pub mod card {
         }
         pub fn stop_auto_rotating(&mut self) {
             self.auto_rotating = 0;
+            self.swipe_out = None;
+            self.drag_x = Spring::default();
+            self.drag_y = Spring::default();
+            self.dragging = false;
             self.rotate = 0.0;
             self.alpha = 1.0;
+            self.snap();
+        }
+        /// 最初に見せる面を決めて、その面にする
+        pub fn set_start_face(&mut self, face: Face) {
+            self.start_face = match face {
+                Face::Front => 0,
+                Face::Back => 1,
+            };
+            self.face_state = self.start_face;
         }
         pub fn reset_card(&mut self) {
             self.auto_rotating = 0;
+            self.swipe_out = None;
+            self.drag_x = Spring::default();
+            self.drag_y = Spring::default();
+            self.dragging = false;
             self.rotate = 0.0;
             self.alpha = 1.0;
-            self.face_state = 0;  // 表面に戻す
+            self.face_state = self.start_face; // 最初に見せる面に戻す
             self.flip_angle = 0.0;
-            self.is_flipping = false;
+            self.flip = None;
+            self.snap();
         }
         pub fn toggle_face(&mut self) {
-            // フリップアニメーション開始
-            self.is_flipping = true;
+            // フリップアニメーション開始（フリップ中なら今見えている面から裏返し直す）
+            self.flip_from_face = self.face_state;
             self.flip_angle = 0.0;
+            self.flip = Some(Tween::new(
+                0.0,
+                std::f32::consts::PI,
+                FLASH_CARD_FLIP_DURATION,
+                Easing::EaseInOutCubic,
+            ));
         }
 
         pub fn is_flipping(&self) -> bool {
-            self.is_flipping
+            self.flip.is_some()
         }
 
         pub fn get_front_text(&self) -> &str {
@@ -141,32 +291,119 @@ pub mod card {
             self.face_state
         }
 
-        pub fn draw(&self, renderer: &Renderer) {
-            // カードの矩形を描画
-            let (text, color) = if self.face_state == 0 {
-                (&self.front_text, self.front_color)
+        /// 今見えている面
+        pub fn face(&self) -> Face {
+            if self.face_state == 0 {
+                Face::Front
             } else {
-                (&self.back_text, self.back_color)
+                Face::Back
+            }
+        }
+
+        pub fn get_etymology(&self) -> &Etymology {
+            &self.etymology
+        }
+
+        /// 先頭のカードの後ろに重ねたときの中心と大きさの倍率（depth: 何枚目の後ろか）
+        /// 後ろのカードほど小さく、上にずらして縁が見えるようにする
+        pub fn stack_pose(&self, depth: f32) -> (Point, f32) {
+            let center = Point {
+                x: self.cp.x,
+                y: self.cp.y - STACK_OFFSET_Y * depth,
             };
+            (center, 1.0 - STACK_SCALE_STEP * depth)
+        }
+
+        /// 指定した面に描く内容（テキスト・語源・装飾）
+        fn content(&self, face: Face, show_ruby: bool) -> CardFace<'_> {
+            CardFace {
+                face,
+                text: match face {
+                    Face::Front => &self.front_text,
+                    Face::Back => &self.back_text,
+                },
+                etymology: &self.etymology,
+                decorations: self.decorations,
+                show_ruby,
+            }
+        }
 
-            // etymology を &str のベクタに変換
-            let etymology_refs: Vec<&str> = self.etymology.iter().map(|s| s.as_str()).collect();
+        /// 先頭のカードの後ろに重ねて描画（depth: 何枚目の後ろか、途中の値は手前に出てくる途中）
+        pub fn draw_in_stack(&self, renderer: &Renderer, show_ruby: bool, depth: f32) {
+            let (center, scale) = self.stack_pose(depth);
+            renderer.fill_round_rect_rotate_with_flip(
+                &center,
+                0.0,
+                self.width * scale,
+                self.height * scale,
+                FLASH_CARD_CORNER_RADIUS,
+                1.0,
+                0.0,
+                &self.content(self.face(), show_ruby), // 出題の向きが裏なら、後ろのカードも裏を見せる
+            );
+        }
+
+        /// カードを描画（show_ruby: 表面のルビを表示するかどうか）
+        /// interpolation: 前回の update から次の update までの位置（0.0〜1.0）
+        pub fn draw(&self, renderer: &Renderer, show_ruby: bool, interpolation: f64) {
+            let pose = self.previous.lerp(&self.pose(), interpolation as f32);
+
+            if renderer.motion() == Motion::Reduced {
+                self.draw_cross_fade(renderer, show_ruby, &pose);
+                return;
+            }
+
+            // ドラッグの傾きによる横ずれを差し引き、カードの中心が指の真下に来るように平行移動する
+            let drag_rotate = pose.drag_x * FLASH_CARD_DRAG_ROTATE;
+            let shift_x = pose.drag_x - FLASH_CARD_PIVOT_DISTANCE * drag_rotate.sin();
+            let shift_y = pose.drag_y * FLASH_CARD_DRAG_LIFT;
 
             renderer.fill_round_rect_rotate_with_flip(
                 &Point {
-                    x: self.cp.x, //　Card Center.x
-                    y: self.cp.y, //  Card Center.y
+                    x: self.cp.x + shift_x, //　Card Center.x
+                    y: self.cp.y + shift_y, //  Card Center.y
                 },
-                self.rotate,              // Cardの傾き
+                pose.rotate,              // Cardの傾き
                 self.width,               // Card Width
                 self.height,              // Card Height
                 FLASH_CARD_CORNER_RADIUS, // Card Conner Radius
-                color,                    // 表裏に応じた色
-                self.alpha,
-                text,
-                self.flip_angle, // フリップ角度
-                etymology_refs,
+                pose.alpha,
+                pose.flip_angle, // フリップ角度
+                &self.content(self.face(), show_ruby), // 表裏（配色はテーマで決まる）
             );
         }
+        /// 動きを減らす設定のときの描画
+        /// 傾けたり裏返したりせず、ドラッグした位置でそのまま薄くなって消え、表裏は重ねて切り替える
+        fn draw_cross_fade(&self, renderer: &Renderer, show_ruby: bool, pose: &Pose) {
+            let center = Point {
+                x: self.cp.x + pose.drag_x,
+                y: self.cp.y + pose.drag_y * FLASH_CARD_DRAG_LIFT,
+            };
+            let draw_face = |face: Face, alpha: f32| {
+                renderer.fill_round_rect_rotate_with_flip(
+                    &center,
+                    0.0,
+                    self.width,
+                    self.height,
+                    FLASH_CARD_CORNER_RADIUS,
+                    alpha,
+                    0.0,
+                    &self.content(face, show_ruby),
+                );
+            };
+            if self.flip.is_some() && pose.flip_angle > 0.0 {
+                // 裏返す前の面の上に、裏返したあとの面を少しずつ濃く重ねる
+                let (from, to) = if self.flip_from_face == 0 {
+                    (Face::Front, Face::Back)
+                } else {
+                    (Face::Back, Face::Front)
+                };
+                let progress = pose.flip_angle / std::f32::consts::PI;
+                draw_face(from, pose.alpha);
+                draw_face(to, pose.alpha * progress);
+            } else {
+                draw_face(self.face(), pose.alpha);
+            }
+        }
     }
 }
@@ -0,0 +1,121 @@
+//! 語根（root morpheme）でカードを結びつけるワードファミリーの索引
+
+use crate::etymology::{Etymology, MorphemeKind};
+use std::collections::HashMap;
+
+/// 形態素を索引のキーに正規化する（小文字化・長音記号の除去・ハイフン等の除去）
+/// 例: `dūrābilis` → `durabilis`、`prō-` → `pro`
+pub fn morpheme_key(form: &str) -> String {
+    form.chars()
+        .flat_map(|c| c.to_lowercase())
+        .map(|c| match c {
+            'ā' | 'ă' | 'â' => 'a',
+            'ē' | 'ĕ' | 'ê' => 'e',
+            'ī' | 'ĭ' | 'î' => 'i',
+            'ō' | 'ŏ' | 'ô' => 'o',
+            'ū' | 'ŭ' | 'û' => 'u',
+            'ȳ' => 'y',
+            _ => c,
+        })
+        .filter(|c| c.is_alphabetic())
+        .collect()
+}
+
+/// 1枚のカードの語源から索引のキーを集める
+/// 語根そのもの・語基・派生語の成り立ちに含まれる語基（`en- + dūrus` の `dūrus`）が対象
+pub fn root_keys(etymology: &Etymology) -> Vec<String> {
+    let mut forms: Vec<&str> = vec![etymology.root.as_str()];
+    forms.extend(
+        etymology
+            .morphemes
+            .iter()
+            .filter(|m| m.kind == MorphemeKind::Base)
+            .map(|m| m.form.as_str()),
+    );
+    for derived in etymology.derived.iter() {
+        if let Some(formation) = &derived.formation {
+            forms.extend(
+                formation
+                    .split('+')
+                    .map(|part| part.trim())
+                    .filter(|part| !part.ends_with('-') && !part.starts_with('-')),
+            );
+        }
+    }
+
+    let mut keys: Vec<String> = Vec::new();
+    for key in forms.into_iter().map(morpheme_key) {
+        if !key.is_empty() && !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys
+}
+
+/// デッキ全体の語根 → カード番号の索引
+pub struct WordFamilyIndex {
+    families: HashMap<String, Vec<usize>>, // 語根キー → カードの位置（デッキ内の順番）
+}
+impl WordFamilyIndex {
+    /// 各カードの語源から索引を作成
+    pub fn build<'a>(etymologies: impl Iterator<Item = &'a Etymology>) -> Self {
+        let mut families: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, etymology) in etymologies.enumerate() {
+            for key in root_keys(etymology) {
+                families.entry(key).or_default().push(index);
+            }
+        }
+        WordFamilyIndex { families }
+    }
+
+    /// 指定した語源と語根を共有するカードの位置（重複なし・デッキ順）
+    pub fn family_of(&self, etymology: &Etymology) -> Vec<usize> {
+        let mut members: Vec<usize> = root_keys(etymology)
+            .iter()
+            .filter_map(|key| self.families.get(key))
+            .flatten()
+            .copied()
+            .collect();
+        members.sort_unstable();
+        members.dedup();
+        members
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn morpheme_keys_drop_macrons_hyphens_and_case() {
+        assert_eq!(morpheme_key("dūrābilis"), "durabilis");
+        assert_eq!(morpheme_key("prō-"), "pro");
+        assert_eq!(morpheme_key("-Bilis"), "bilis");
+        assert_eq!(morpheme_key("e-/ex-"), "eex");
+    }
+
+    #[test]
+    fn root_keys_include_bases_and_formations_without_affixes() {
+        let etymology = Etymology::parse(&[
+            "dūrābilis/ラテン語: 長持ちする",
+            "fatērī: 認める",
+            "prō-: 前に",
+            "endure（耐える）: en- + dūrus",
+            "durable: 丈夫な",
+        ]);
+        assert_eq!(root_keys(&etymology), ["durabilis", "fateri", "durus"]);
+    }
+
+    #[test]
+    fn family_of_lists_every_card_sharing_a_root_once() {
+        let etymologies = [
+            Etymology::parse(&["dūrābilis/ラテン語: 長持ちする", "endure（耐える）: en- + dūrus"]),
+            Etymology::parse(&["prōfessiō/ラテン語: 公の宣言", "fatērī: 認める"]),
+            Etymology::parse(&["dūrus/ラテン語: 硬い", "durabilis（長持ちする）: dūrābilis"]),
+        ];
+        let index = WordFamilyIndex::build(etymologies.iter());
+        assert_eq!(index.family_of(&etymologies[0]), [0, 2]);
+        assert_eq!(index.family_of(&etymologies[1]), [1]);
+        assert_eq!(index.family_of(&Etymology::default()), Vec::<usize>::new());
+    }
+}
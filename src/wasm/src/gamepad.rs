@@ -0,0 +1,110 @@
+//! ゲームパッド（Gamepad API の standard mapping）のボタン・スティックをキーコードに変換する
+//!
+//! ゲームパッドの入力はキーボードと同じ `KeyState` に `GamepadA` のような名前で入るので、
+//! キー割り当て（keymap）でボタンの役割も変更できる。
+
+use crate::common::*;
+
+/// standard mapping のボタン番号順の名前
+const BUTTON_CODES: [&str; 16] = [
+    "GamepadA",      // 0: 下の face ボタン
+    "GamepadB",      // 1: 右の face ボタン
+    "GamepadX",      // 2: 左の face ボタン
+    "GamepadY",      // 3: 上の face ボタン
+    "GamepadL1",     // 4
+    "GamepadR1",     // 5
+    "GamepadL2",     // 6
+    "GamepadR2",     // 7
+    "GamepadSelect", // 8
+    "GamepadStart",  // 9
+    "GamepadL3",     // 10
+    "GamepadR3",     // 11
+    "GamepadUp",     // 12: 十字キー
+    "GamepadDown",   // 13
+    "GamepadLeft",   // 14
+    "GamepadRight",  // 15
+];
+
+/// ある瞬間のゲームパッドの状態
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GamepadSnapshot {
+    pub buttons: Vec<bool>, // standard mapping の順に押されているかどうか
+    pub axes: Vec<f64>,     // 0: 左スティック X、1: 左スティック Y（-1.0〜1.0）
+}
+
+/// スティックの遊び（デッドゾーン）を取り除く
+/// 中心からの距離が deadzone 未満なら (0, 0)、それ以外は deadzone〜1.0 を 0.0〜1.0 に伸ばす
+pub fn apply_deadzone(x: f64, y: f64, deadzone: f64) -> (f64, f64) {
+    let magnitude = (x * x + y * y).sqrt();
+    if magnitude < deadzone || magnitude == 0.0 {
+        return (0.0, 0.0);
+    }
+    let scaled = ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0);
+    (x / magnitude * scaled, y / magnitude * scaled)
+}
+
+/// 押されているボタンと傾けられているスティックの方向をキーコードにする
+pub fn held_codes(snapshot: &GamepadSnapshot) -> Vec<&'static str> {
+    let mut codes: Vec<&'static str> = snapshot
+        .buttons
+        .iter()
+        .zip(BUTTON_CODES)
+        .filter(|(pressed, _)| **pressed)
+        .map(|(_, code)| code)
+        .collect();
+
+    let axis = |i: usize| snapshot.axes.get(i).copied().unwrap_or(0.0);
+    let (x, y) = apply_deadzone(axis(0), axis(1), GAMEPAD_DEADZONE);
+    if x <= -GAMEPAD_STICK_THRESHOLD {
+        codes.push("GamepadStickLeft");
+    } else if x >= GAMEPAD_STICK_THRESHOLD {
+        codes.push("GamepadStickRight");
+    }
+    if y <= -GAMEPAD_STICK_THRESHOLD {
+        codes.push("GamepadStickUp");
+    } else if y >= GAMEPAD_STICK_THRESHOLD {
+        codes.push("GamepadStickDown");
+    }
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadzone_drops_small_tilt() {
+        assert_eq!(apply_deadzone(0.1, -0.1, 0.25), (0.0, 0.0));
+        let (x, y) = apply_deadzone(1.0, 0.0, 0.25);
+        assert!((x - 1.0).abs() < 1e-9 && y == 0.0);
+        let (x, _) = apply_deadzone(0.625, 0.0, 0.25);
+        assert!((x - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn buttons_map_to_codes() {
+        let mut buttons = vec![false; 17];
+        buttons[0] = true;
+        buttons[14] = true;
+        buttons[16] = true; // standard mapping 外のボタンは無視
+        let snapshot = GamepadSnapshot {
+            buttons,
+            axes: vec![0.0, 0.0],
+        };
+        assert_eq!(held_codes(&snapshot), ["GamepadA", "GamepadLeft"]);
+    }
+
+    #[test]
+    fn stick_tilt_maps_to_direction() {
+        let snapshot = GamepadSnapshot {
+            buttons: Vec::new(),
+            axes: vec![0.9, 0.2],
+        };
+        assert_eq!(held_codes(&snapshot), ["GamepadStickRight"]);
+        let drifting = GamepadSnapshot {
+            buttons: Vec::new(),
+            axes: vec![0.2, -0.15],
+        };
+        assert!(held_codes(&drifting).is_empty());
+    }
+}
@@ -0,0 +1,592 @@
+//! ポインタの動きからジェスチャー（タップ・ダブルタップ・長押し・スワイプ・フリック・キャンセル）を判定する
+//!
+//! ブラウザに依存しないので、時刻付きの合成イベント列でテストできる。
+//! 複数ポインタの扱いは呼び出し側（PointerState）で行い、ここには1本のポインタの入力だけを渡す。
+//! 端末を振る操作（シェイク）は加速度センサーの値から ShakeDetector で判定する。
+
+use crate::common::*;
+use std::collections::VecDeque;
+
+/// スワイプ・フリックの方向
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+}
+impl SwipeDirection {
+    fn from_delta(dx: f64) -> Self {
+        if dx < 0.0 {
+            SwipeDirection::Left
+        } else {
+            SwipeDirection::Right
+        }
+    }
+}
+
+/// 判定されたジェスチャー
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    Tap {
+        x: f64,
+        y: f64,
+    },
+    DoubleTap {
+        x: f64,
+        y: f64,
+    },
+    LongPress {
+        x: f64,
+        y: f64,
+    },
+    /// swipe_distance を超えて離した
+    Swipe {
+        direction: SwipeDirection,
+        velocity: f64,
+    },
+    /// 距離は短いが速く払って離した
+    Flick {
+        direction: SwipeDirection,
+        velocity: f64,
+    },
+    /// 一度 swipe_distance を超えたが中央付近に戻して離した
+    Cancel,
+}
+
+/// ジェスチャーの判定に渡す入力（座標は論理px、時刻はms）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PointerInput {
+    Down { x: f64, y: f64, time: f64 },
+    Move { x: f64, y: f64, time: f64 },
+    Up { x: f64, y: f64, time: f64 },
+    Cancel,
+}
+
+/// ドラッグ中の状態（押した位置からの移動量と現在の横方向の速度）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Drag {
+    pub dx: f64,
+    pub dy: f64,
+    pub velocity_x: f64, // px/ms
+}
+
+/// ジェスチャー判定の閾値
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GestureConfig {
+    pub tap_distance: f64,         // タップとみなす移動量の上限（px）
+    pub drag_start_distance: f64,  // ドラッグ開始とみなす移動量（px）
+    pub swipe_distance: f64,       // 離したときにスワイプとみなす横方向の移動量（px）
+    pub flick_velocity: f64,       // フリックとみなす横方向の速度（px/ms）
+    pub velocity_window: f64,      // 速度の計算に使う直近の履歴の長さ（ms）
+    pub long_press_duration: f64,  // 長押しとみなす時間（ms）
+    pub long_press_tolerance: f64, // 長押し中に許容する移動量（px）
+    pub double_tap_interval: f64,  // ダブルタップとみなす2回のタップの間隔の上限（ms）
+    pub double_tap_distance: f64,  // ダブルタップとみなす2回のタップの距離の上限（px）
+}
+impl Default for GestureConfig {
+    fn default() -> Self {
+        GestureConfig {
+            tap_distance: TAP_JUDGE_DISTANCE,
+            drag_start_distance: SWIPE_JUDGE_DISTANCE,
+            swipe_distance: SWIPE_COMMIT_DISTANCE,
+            flick_velocity: FLICK_VELOCITY,
+            velocity_window: FLICK_VELOCITY_WINDOW,
+            long_press_duration: LONG_PRESS_DURATION,
+            long_press_tolerance: LONG_PRESS_TOLERANCE,
+            double_tap_interval: DOUBLE_TAP_INTERVAL,
+            double_tap_distance: DOUBLE_TAP_DISTANCE,
+        }
+    }
+}
+
+/// 時刻付きの座標
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+    x: f64,
+    y: f64,
+    time: f64,
+}
+
+/// 押してから離すまでの1回分の追跡
+struct Track {
+    start: Sample,
+    history: VecDeque<Sample>, // velocity_window 内の履歴
+    max_distance_x: f64,       // 横方向の最大移動量
+    dragging: bool,            // drag_start_distance を超えた
+    long_pressed: bool,        // 長押しを通知済み
+    abandoned: bool,           // 呼び出し側が処理済みにした（離しても何も通知しない）
+}
+impl Track {
+    fn last(&self) -> Sample {
+        *self.history.back().unwrap_or(&self.start)
+    }
+    /// 直近 velocity_window の横方向の速度（px/ms）
+    fn velocity_x(&self) -> f64 {
+        match (self.history.front(), self.history.back()) {
+            (Some(first), Some(last)) if last.time > first.time => {
+                (last.x - first.x) / (last.time - first.time)
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// ジェスチャーの判定器
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    track: Option<Track>,
+    last_tap: Option<Sample>, // ダブルタップ判定用の直前のタップ
+    gestures: Vec<Gesture>,   // 判定済みでまだ取り出されていないジェスチャー
+}
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> Self {
+        GestureRecognizer {
+            config,
+            track: None,
+            last_tap: None,
+            gestures: Vec::new(),
+        }
+    }
+    /// 判定の設定を替える（スワイプの感度の変更。次に押したときから使う）
+    pub fn set_config(&mut self, config: GestureConfig) {
+        self.config = config;
+    }
+
+    /// ポインタの入力を1つ処理する
+    pub fn handle(&mut self, input: PointerInput) {
+        match input {
+            PointerInput::Down { x, y, time } => {
+                let start = Sample { x, y, time };
+                self.track = Some(Track {
+                    start,
+                    history: VecDeque::from([start]),
+                    max_distance_x: 0.0,
+                    dragging: false,
+                    long_pressed: false,
+                    abandoned: false,
+                });
+            }
+            PointerInput::Move { x, y, time } => self.record(Sample { x, y, time }),
+            PointerInput::Up { x, y, time } => {
+                self.record(Sample { x, y, time });
+                if let Some(track) = self.track.take() {
+                    self.finish(track);
+                }
+            }
+            PointerInput::Cancel => self.track = None,
+        }
+    }
+
+    /// 時間経過による判定（長押し）を行う。毎フレーム呼び出す
+    pub fn tick(&mut self, now: f64) {
+        let config = self.config;
+        if let Some(track) = self.track.as_mut()
+            && !track.long_pressed
+            && !track.dragging
+            && !track.abandoned
+            && now - track.start.time >= config.long_press_duration
+        {
+            let last = track.last();
+            if (last.x - track.start.x).abs() < config.long_press_tolerance
+                && (last.y - track.start.y).abs() < config.long_press_tolerance
+            {
+                track.long_pressed = true;
+                self.last_tap = None;
+                self.gestures.push(Gesture::LongPress {
+                    x: last.x,
+                    y: last.y,
+                });
+            }
+        }
+    }
+
+    /// ドラッグ中なら移動量と速度を返す
+    pub fn drag(&self) -> Option<Drag> {
+        let track = self.track.as_ref()?;
+        if !track.dragging || track.abandoned {
+            return None;
+        }
+        let last = track.last();
+        Some(Drag {
+            dx: last.x - track.start.x,
+            dy: last.y - track.start.y,
+            velocity_x: track.velocity_x(),
+        })
+    }
+
+    /// 現在の押下を処理済みにする（離すまで何も通知しない）
+    pub fn abandon(&mut self) {
+        if let Some(track) = self.track.as_mut() {
+            track.abandoned = true;
+        }
+    }
+
+    /// 判定済みのジェスチャーを取り出す
+    pub fn take_gestures(&mut self) -> Vec<Gesture> {
+        std::mem::take(&mut self.gestures)
+    }
+
+    fn record(&mut self, sample: Sample) {
+        let config = self.config;
+        let Some(track) = self.track.as_mut() else {
+            return;
+        };
+        let dx = sample.x - track.start.x;
+        let dy = sample.y - track.start.y;
+        track.max_distance_x = track.max_distance_x.max(dx.abs());
+        if !track.long_pressed && dx.abs().max(dy.abs()) >= config.drag_start_distance {
+            track.dragging = true;
+        }
+
+        track.history.push_back(sample);
+        while let Some(first) = track.history.front() {
+            if track.history.len() > 2 && sample.time - first.time > config.velocity_window {
+                track.history.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// 離したときの判定
+    fn finish(&mut self, track: Track) {
+        if track.long_pressed || track.abandoned {
+            return;
+        }
+        let config = self.config;
+        let last = track.last();
+        let dx = last.x - track.start.x;
+        let dy = last.y - track.start.y;
+        let velocity = track.velocity_x();
+        let fast = velocity.abs() >= config.flick_velocity;
+
+        if dx.abs() >= config.swipe_distance {
+            // 逆方向に速く払って離した場合は取り消しとみなす
+            if fast && velocity.signum() != dx.signum() {
+                self.gestures.push(Gesture::Cancel);
+            } else {
+                self.gestures.push(Gesture::Swipe {
+                    direction: SwipeDirection::from_delta(dx),
+                    velocity,
+                });
+            }
+        } else if fast && track.dragging && velocity.signum() == dx.signum() {
+            self.gestures.push(Gesture::Flick {
+                direction: SwipeDirection::from_delta(dx),
+                velocity,
+            });
+        } else if track.max_distance_x >= config.swipe_distance {
+            self.gestures.push(Gesture::Cancel);
+        } else if dx.abs() < config.tap_distance && dy.abs() < config.tap_distance {
+            self.tap(last);
+        }
+    }
+
+    /// タップ／ダブルタップの判定
+    fn tap(&mut self, at: Sample) {
+        let config = self.config;
+        match self.last_tap.take() {
+            Some(previous)
+                if at.time - previous.time <= config.double_tap_interval
+                    && (at.x - previous.x).abs() < config.double_tap_distance
+                    && (at.y - previous.y).abs() < config.double_tap_distance =>
+            {
+                self.gestures.push(Gesture::DoubleTap { x: at.x, y: at.y });
+            }
+            _ => {
+                self.last_tap = Some(at);
+                self.gestures.push(Gesture::Tap { x: at.x, y: at.y });
+            }
+        }
+    }
+}
+
+/// 端末を左右に振る操作の判定
+/// 横方向の加速度が閾値を超えて向きを変えた回数が、一定時間内に規定数に達したらシェイクとする
+pub struct ShakeDetector {
+    reversals: VecDeque<f64>, // 向きが変わった時刻（ms）
+    last_sign: f64,           // 直前に閾値を超えた向き（-1.0 / 1.0、まだなければ 0.0）
+    cooldown_until: f64,      // 連続して判定しないための待ち時間の終わり
+}
+impl ShakeDetector {
+    pub fn new() -> Self {
+        ShakeDetector {
+            reversals: VecDeque::new(),
+            last_sign: 0.0,
+            cooldown_until: f64::NEG_INFINITY,
+        }
+    }
+    /// 横方向の加速度（m/s²、重力を含む）を渡し、シェイクと判定したら true
+    pub fn handle(&mut self, acceleration_x: f64, time: f64) -> bool {
+        if time < self.cooldown_until || acceleration_x.abs() < SHAKE_ACCELERATION {
+            return false;
+        }
+        let sign = acceleration_x.signum();
+        if sign != self.last_sign {
+            if self.last_sign != 0.0 {
+                self.reversals.push_back(time);
+            }
+            self.last_sign = sign;
+        }
+        while let Some(&first) = self.reversals.front()
+            && time - first > SHAKE_WINDOW
+        {
+            self.reversals.pop_front();
+        }
+        if self.reversals.len() >= SHAKE_REVERSALS {
+            self.reversals.clear();
+            self.last_sign = 0.0;
+            self.cooldown_until = time + SHAKE_COOLDOWN;
+            return true;
+        }
+        false
+    }
+}
+impl Default for ShakeDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn down(x: f64, time: f64) -> PointerInput {
+        PointerInput::Down { x, y: 300.0, time }
+    }
+    fn move_to(x: f64, time: f64) -> PointerInput {
+        PointerInput::Move { x, y: 300.0, time }
+    }
+    fn up(x: f64, time: f64) -> PointerInput {
+        PointerInput::Up { x, y: 300.0, time }
+    }
+
+    /// 合成イベント列を流して判定結果を返す
+    fn run(inputs: &[PointerInput]) -> Vec<Gesture> {
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+        for input in inputs {
+            recognizer.handle(*input);
+        }
+        recognizer.take_gestures()
+    }
+
+    #[test]
+    fn tap_when_released_in_place() {
+        assert_eq!(
+            run(&[down(200.0, 0.0), move_to(203.0, 50.0), up(203.0, 80.0)]),
+            vec![Gesture::Tap { x: 203.0, y: 300.0 }]
+        );
+    }
+
+    #[test]
+    fn double_tap_within_interval() {
+        let gestures = run(&[
+            down(200.0, 0.0),
+            up(200.0, 60.0),
+            down(204.0, 200.0),
+            up(204.0, 260.0),
+        ]);
+        assert_eq!(
+            gestures,
+            vec![
+                Gesture::Tap { x: 200.0, y: 300.0 },
+                Gesture::DoubleTap { x: 204.0, y: 300.0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn slow_second_tap_is_a_new_tap() {
+        let gestures = run(&[
+            down(200.0, 0.0),
+            up(200.0, 60.0),
+            down(200.0, 1000.0),
+            up(200.0, 1060.0),
+        ]);
+        assert_eq!(
+            gestures,
+            vec![
+                Gesture::Tap { x: 200.0, y: 300.0 },
+                Gesture::Tap { x: 200.0, y: 300.0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn long_press_fires_once_and_suppresses_tap() {
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+        recognizer.handle(down(200.0, 0.0));
+        recognizer.tick(LONG_PRESS_DURATION - 1.0);
+        assert!(recognizer.take_gestures().is_empty());
+
+        recognizer.tick(LONG_PRESS_DURATION);
+        recognizer.tick(LONG_PRESS_DURATION + 100.0);
+        assert_eq!(
+            recognizer.take_gestures(),
+            vec![Gesture::LongPress { x: 200.0, y: 300.0 }]
+        );
+
+        recognizer.handle(up(200.0, LONG_PRESS_DURATION + 200.0));
+        assert!(recognizer.take_gestures().is_empty());
+    }
+
+    #[test]
+    fn no_long_press_while_dragging() {
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+        recognizer.handle(down(200.0, 0.0));
+        recognizer.handle(move_to(240.0, 100.0));
+        recognizer.tick(LONG_PRESS_DURATION + 100.0);
+        assert!(recognizer.take_gestures().is_empty());
+    }
+
+    #[test]
+    fn slow_long_drag_is_a_swipe() {
+        let gestures = run(&[
+            down(200.0, 0.0),
+            move_to(160.0, 200.0),
+            move_to(110.0, 400.0),
+            up(100.0, 600.0),
+        ]);
+        assert!(matches!(
+            gestures.as_slice(),
+            [Gesture::Swipe {
+                direction: SwipeDirection::Left,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn short_fast_drag_is_a_flick() {
+        let gestures = run(&[
+            down(200.0, 0.0),
+            move_to(215.0, 20.0),
+            move_to(240.0, 40.0),
+            up(250.0, 50.0),
+        ]);
+        match gestures.as_slice() {
+            [
+                Gesture::Flick {
+                    direction: SwipeDirection::Right,
+                    velocity,
+                },
+            ] => assert!(*velocity >= FLICK_VELOCITY),
+            other => panic!("unexpected gestures: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn short_slow_drag_is_nothing() {
+        let gestures = run(&[
+            down(200.0, 0.0),
+            move_to(220.0, 300.0),
+            move_to(240.0, 600.0),
+            up(240.0, 900.0),
+        ]);
+        assert!(gestures.is_empty());
+    }
+
+    #[test]
+    fn returning_to_centre_cancels() {
+        let gestures = run(&[
+            down(200.0, 0.0),
+            move_to(260.0, 200.0),
+            move_to(320.0, 400.0),
+            move_to(260.0, 600.0),
+            move_to(205.0, 800.0),
+            up(205.0, 1000.0),
+        ]);
+        assert_eq!(gestures, vec![Gesture::Cancel]);
+    }
+
+    #[test]
+    fn flicking_back_cancels_a_long_drag() {
+        let gestures = run(&[
+            down(200.0, 0.0),
+            move_to(320.0, 400.0),
+            move_to(340.0, 800.0),
+            move_to(310.0, 820.0),
+            up(290.0, 840.0),
+        ]);
+        assert_eq!(gestures, vec![Gesture::Cancel]);
+    }
+
+    #[test]
+    fn drag_reports_delta_after_slop() {
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+        recognizer.handle(down(200.0, 0.0));
+        recognizer.handle(move_to(210.0, 50.0));
+        assert_eq!(recognizer.drag(), None);
+
+        recognizer.handle(move_to(170.0, 100.0));
+        let drag = recognizer.drag().expect("dragging");
+        assert_eq!(drag.dx, -30.0);
+        assert!(drag.velocity_x < 0.0);
+
+        recognizer.handle(up(170.0, 150.0));
+        assert_eq!(recognizer.drag(), None);
+    }
+
+    #[test]
+    fn abandoned_press_reports_nothing() {
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+        recognizer.handle(down(200.0, 0.0));
+        recognizer.handle(move_to(100.0, 100.0));
+        recognizer.abandon();
+        assert_eq!(recognizer.drag(), None);
+        recognizer.handle(up(50.0, 150.0));
+        assert!(recognizer.take_gestures().is_empty());
+    }
+
+    #[test]
+    fn cancelled_pointer_reports_nothing() {
+        let gestures = run(&[down(200.0, 0.0), move_to(100.0, 50.0), PointerInput::Cancel]);
+        assert!(gestures.is_empty());
+    }
+
+    #[test]
+    fn thresholds_are_configurable() {
+        let mut recognizer = GestureRecognizer::new(GestureConfig {
+            swipe_distance: 30.0,
+            flick_velocity: 10.0,
+            ..GestureConfig::default()
+        });
+        recognizer.handle(down(200.0, 0.0));
+        recognizer.handle(move_to(230.0, 500.0));
+        recognizer.handle(up(235.0, 1000.0));
+        assert!(matches!(
+            recognizer.take_gestures().as_slice(),
+            [Gesture::Swipe {
+                direction: SwipeDirection::Right,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn shaking_back_and_forth_is_detected_once() {
+        let mut shake = ShakeDetector::new();
+        let samples = [20.0, -20.0, 20.0, -20.0, 20.0, -20.0];
+        let detected: Vec<bool> = samples
+            .iter()
+            .enumerate()
+            .map(|(i, &a)| shake.handle(a, i as f64 * 100.0))
+            .collect();
+        assert_eq!(detected, [false, false, false, true, false, false]);
+    }
+
+    #[test]
+    fn gentle_or_slow_motion_is_not_a_shake() {
+        let mut gentle = ShakeDetector::new();
+        assert!(![5.0, -5.0, 5.0, -5.0, 5.0]
+            .iter()
+            .enumerate()
+            .any(|(i, &a)| gentle.handle(a, i as f64 * 100.0)));
+
+        let mut slow = ShakeDetector::new();
+        assert!(![20.0, -20.0, 20.0, -20.0]
+            .iter()
+            .enumerate()
+            .any(|(i, &a)| slow.handle(a, i as f64 * 1000.0)));
+    }
+}
@@ -0,0 +1,294 @@
+//! ブラウザに依存しない入力の状態
+//!
+//! engine はブラウザのイベントを `KeyInput` / `PointerAction` にしてここへ渡す。
+//! テストやリプレイ（replay）では同じ値を直接渡せば、ブラウザなしで同じ入力を再現できる。
+
+use crate::gesture::{
+    Drag, Gesture, GestureConfig, GestureRecognizer, PointerInput, SwipeDirection,
+};
+use std::collections::HashSet;
+
+/// キーの押下・解放（code は `KeyboardEvent.code` か `GamepadA` などの仮想キー）
+#[derive(Clone, Debug, PartialEq)]
+pub enum KeyInput {
+    Down { code: String, repeat: bool },
+    Up { code: String },
+}
+
+/// ポインタ（タッチ・マウス・ペン）の入力（座標は論理px、time は ms）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PointerAction {
+    Down { id: i32, x: i32, y: i32, time: f64 },
+    Move { id: i32, x: i32, y: i32, time: f64 },
+    Up { id: i32, x: i32, y: i32, time: f64 },
+    Cancel { id: i32 },
+}
+
+/// フレームごとに届く入力（記録・リプレイではこの単位で保存する）
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputEvent {
+    Key(KeyInput),
+    Pointer(PointerAction),
+    Clear, // タブが裏から戻ったときなど、押されているキーと途中のジェスチャーを捨てる
+}
+impl InputEvent {
+    pub fn apply(&self, keystate: &mut KeyState, pointerstate: &mut PointerState) {
+        match self {
+            InputEvent::Key(input) => keystate.apply(input),
+            InputEvent::Pointer(action) => pointerstate.apply(action),
+            InputEvent::Clear => {
+                keystate.clear();
+                pointerstate.clear();
+            }
+        }
+    }
+}
+
+/// Pointer Events によるタッチ・マウス・ペン共通の入力状態
+/// 複数のポインタを pointerId で追跡し、最初に押されたポインタ（primary）の動きを GestureRecognizer に渡す
+/// 2本目の指が触れた場合はピンチなどとみなし、そのジェスチャーを取り消す
+pub struct PointerState {
+    pointers: Vec<i32>,             // 押されているポインタの pointerId（押された順）
+    primary: Option<i32>,           // ジェスチャーを判定するポインタの pointerId
+    recognizer: GestureRecognizer,  // primary の動きからジェスチャーを判定
+    gestures: Vec<Gesture>,         // 次の update で使うジェスチャー
+}
+impl PointerState {
+    pub fn new() -> Self {
+        PointerState {
+            pointers: Vec::new(),
+            primary: None,
+            recognizer: GestureRecognizer::new(GestureConfig::default()),
+            gestures: Vec::new(),
+        }
+    }
+    pub fn is_tapped(&self) -> bool {
+        // タップが検出されたフレームでのみtrueを返す（エッジトリガー）
+        // ダブルタップの2回目もタップとして扱う
+        self.gestures
+            .iter()
+            .any(|g| matches!(g, Gesture::Tap { .. } | Gesture::DoubleTap { .. }))
+    }
+    /// 指定した矩形（論理px）の中でタップされていれば true にしてそのタップを消費する
+    /// 画面上のボタン用（消費したタップはカードの裏返しなどに使われない）
+    pub fn take_tap_in(&mut self, left: f64, top: f64, width: f64, height: f64) -> bool {
+        self.take(|g| match g {
+            Gesture::Tap { x, y } | Gesture::DoubleTap { x, y } => {
+                (left..=left + width).contains(x) && (top..=top + height).contains(y)
+            }
+            _ => false,
+        })
+        .is_some()
+    }
+    /// タップされていればその位置（論理px）を返してタップを消費する
+    pub fn take_tap(&mut self) -> Option<(f64, f64)> {
+        match self.take(|g| matches!(g, Gesture::Tap { .. } | Gesture::DoubleTap { .. })) {
+            Some(Gesture::Tap { x, y }) | Some(Gesture::DoubleTap { x, y }) => Some((x, y)),
+            _ => None,
+        }
+    }
+    /// 長押しが検出されていれば true（1回の押下につき1度だけ）
+    pub fn take_long_press(&mut self) -> bool {
+        self.take(|g| matches!(g, Gesture::LongPress { .. }))
+            .is_some()
+    }
+    /// スワイプまたはフリックで離されていればその方向
+    pub fn take_swipe(&mut self) -> Option<SwipeDirection> {
+        match self.take(|g| matches!(g, Gesture::Swipe { .. } | Gesture::Flick { .. })) {
+            Some(Gesture::Swipe { direction, .. }) | Some(Gesture::Flick { direction, .. }) => {
+                Some(direction)
+            }
+            _ => None,
+        }
+    }
+    /// スワイプの判定などの設定を替える
+    pub fn set_gesture_config(&mut self, config: GestureConfig) {
+        self.recognizer.set_config(config);
+    }
+    /// ドラッグ中なら押した位置からの移動量と速度
+    pub fn drag(&self) -> Option<Drag> {
+        self.recognizer.drag()
+    }
+    fn take(&mut self, predicate: impl Fn(&Gesture) -> bool) -> Option<Gesture> {
+        let index = self.gestures.iter().position(predicate)?;
+        Some(self.gestures.remove(index))
+    }
+    /// ポインタの入力を1つ反映する
+    pub fn apply(&mut self, action: &PointerAction) {
+        match *action {
+            PointerAction::Down { id, x, y, time } => self.set_pressed(id, x, y, time),
+            PointerAction::Move { id, x, y, time } => self.set_moved(id, x, y, time),
+            PointerAction::Up { id, x, y, time } => self.set_released(id, x, y, time),
+            PointerAction::Cancel { id } => self.set_cancelled(id),
+        }
+    }
+    fn set_pressed(&mut self, id: i32, x: i32, y: i32, time: f64) {
+        self.pointers.retain(|p| *p != id);
+        self.pointers.push(id);
+        if self.pointers.len() == 1 {
+            // 新しいジェスチャーの開始
+            self.primary = Some(id);
+            self.recognizer.handle(PointerInput::Down {
+                x: x as f64,
+                y: y as f64,
+                time,
+            });
+        } else if self.primary.is_some() {
+            // 2本目のポインタ: primary のジェスチャーを取り消す
+            self.primary = None;
+            self.recognizer.handle(PointerInput::Cancel);
+        }
+    }
+    fn set_moved(&mut self, id: i32, x: i32, y: i32, time: f64) {
+        if Some(id) == self.primary {
+            self.recognizer.handle(PointerInput::Move {
+                x: x as f64,
+                y: y as f64,
+                time,
+            });
+        }
+    }
+    fn set_released(&mut self, id: i32, x: i32, y: i32, time: f64) {
+        if Some(id) == self.primary {
+            self.primary = None;
+            self.recognizer.handle(PointerInput::Up {
+                x: x as f64,
+                y: y as f64,
+                time,
+            });
+        }
+        self.pointers.retain(|p| *p != id);
+    }
+    fn set_cancelled(&mut self, id: i32) {
+        // ブラウザに横取りされた場合は何も判定しない
+        if Some(id) == self.primary {
+            self.primary = None;
+            self.recognizer.handle(PointerInput::Cancel);
+        }
+        self.pointers.retain(|p| *p != id);
+    }
+    /// 時間経過による判定（長押し）を行い、判定済みのジェスチャーを受け取る
+    pub fn tick(&mut self, now: f64) {
+        self.recognizer.tick(now);
+        self.gestures.extend(self.recognizer.take_gestures());
+    }
+    /// update で使い終わったジェスチャーを捨てる
+    pub fn clear_gestures(&mut self) {
+        self.gestures.clear();
+    }
+
+    /// 現在のジェスチャーを処理済みにする（指を離して押し直すまで反応しない）
+    pub fn clear(&mut self) {
+        self.recognizer.abandon();
+        self.gestures.clear();
+    }
+}
+
+pub struct KeyState {
+    pressed_keys: HashSet<String>, // キーボードのコードとゲームパッドのボタン名
+    just_pressed: HashSet<String>, // 前回の update 以降に新しく押されたキー
+}
+
+impl KeyState {
+    pub fn new() -> Self {
+        KeyState {
+            pressed_keys: HashSet::new(),
+            just_pressed: HashSet::new(),
+        }
+    }
+    pub fn is_pressed(&self, code: &str) -> bool {
+        self.pressed_keys.contains(code)
+    }
+
+    /// 前回の update 以降に押されたキーなら true（エッジトリガー）
+    /// 押しっぱなしのキーリピートでは反応せず、update の前に離されていても取りこぼさない
+    pub fn is_just_pressed(&self, code: &str) -> bool {
+        self.just_pressed.contains(code)
+    }
+
+    /// キーの入力を1つ反映する
+    pub fn apply(&mut self, input: &KeyInput) {
+        match input {
+            KeyInput::Down { code, repeat } => self.set_pressed(code, *repeat),
+            KeyInput::Up { code } => self.set_released(code),
+        }
+    }
+
+    pub fn set_pressed(&mut self, code: &str, repeat: bool) {
+        let newly_pressed = self.pressed_keys.insert(code.into());
+        if newly_pressed && !repeat {
+            self.just_pressed.insert(code.into());
+        }
+    }
+
+    pub fn set_released(&mut self, code: &str) {
+        self.pressed_keys.remove(code);
+    }
+
+    /// update ごとに呼び出して「押された瞬間」を消費する
+    pub fn clear_just_pressed(&mut self) {
+        self.just_pressed.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.pressed_keys.clear();
+        self.just_pressed.clear();
+    }
+}
+
+impl Default for PointerState {
+    fn default() -> Self {
+        PointerState::new()
+    }
+}
+impl Default for KeyState {
+    fn default() -> Self {
+        KeyState::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_repeat_is_not_a_new_press() {
+        let mut keys = KeyState::new();
+        keys.apply(&KeyInput::Down {
+            code: "Space".to_string(),
+            repeat: false,
+        });
+        assert!(keys.is_just_pressed("Space"));
+        keys.clear_just_pressed();
+        keys.apply(&KeyInput::Down {
+            code: "Space".to_string(),
+            repeat: true,
+        });
+        assert!(keys.is_pressed("Space") && !keys.is_just_pressed("Space"));
+        keys.apply(&KeyInput::Up {
+            code: "Space".to_string(),
+        });
+        assert!(!keys.is_pressed("Space"));
+    }
+
+    #[test]
+    fn pointer_actions_become_a_tap() {
+        let mut pointer = PointerState::new();
+        pointer.apply(&PointerAction::Down {
+            id: 1,
+            x: 200,
+            y: 300,
+            time: 0.0,
+        });
+        pointer.apply(&PointerAction::Up {
+            id: 1,
+            x: 201,
+            y: 300,
+            time: 80.0,
+        });
+        pointer.tick(80.0);
+        assert!(pointer.is_tapped());
+        pointer.clear_gestures();
+        assert!(!pointer.is_tapped());
+    }
+}
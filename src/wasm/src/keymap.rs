@@ -0,0 +1,269 @@
+//! キー割り当て（操作 → キーコード）
+//!
+//! 各操作に `KeyboardEvent.code`（例: `Space`, `Digit1`）を1つ以上割り当てる。
+//! 変更した割り当ては `action=Code,Code;...` 形式の文字列で localStorage に保存する。
+//! 画面読み上げ用の操作ボタン（a11y）は割り当てに関係なく `A11y:flip` のようなコードで操作を伝える。
+
+use crate::input::KeyState;
+
+/// キーで行える操作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Flip,           // カードを裏返す
+    Again,          // 評価1: もう一度（後で再確認）
+    Hard,           // 評価2: 難しい（後で再確認）
+    Good,           // 評価3: 覚えた
+    Easy,           // 評価4: 簡単（覚えた）
+    SwipeLeft,      // 押している間カードを左へ送る
+    SwipeRight,     // 押している間カードを右へ送る
+    Undo,           // 直前のスワイプを取り消す
+    ReplayAudio,    // 読み上げをもう一度再生
+    OpenStats,      // 学習状況を表示
+    ToggleFurigana, // ルビ（ふりがな）の表示切り替え
+    WordFamily,     // ワードファミリー画面を開く・閉じる
+    OpenSettings,   // 設定画面を開く・閉じる
+    Confirm,        // 一覧などで決定
+    Cancel,         // 一覧などを閉じる
+    Up,             // 一覧の選択を上へ
+    Down,           // 一覧の選択を下へ
+}
+impl Action {
+    pub const ALL: [Action; 17] = [
+        Action::Flip,
+        Action::Again,
+        Action::Hard,
+        Action::Good,
+        Action::Easy,
+        Action::SwipeLeft,
+        Action::SwipeRight,
+        Action::Undo,
+        Action::ReplayAudio,
+        Action::OpenStats,
+        Action::ToggleFurigana,
+        Action::WordFamily,
+        Action::OpenSettings,
+        Action::Confirm,
+        Action::Cancel,
+        Action::Up,
+        Action::Down,
+    ];
+    /// 保存形式で使う名前
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Flip => "flip",
+            Action::Again => "again",
+            Action::Hard => "hard",
+            Action::Good => "good",
+            Action::Easy => "easy",
+            Action::SwipeLeft => "swipe_left",
+            Action::SwipeRight => "swipe_right",
+            Action::Undo => "undo",
+            Action::ReplayAudio => "replay_audio",
+            Action::OpenStats => "open_stats",
+            Action::ToggleFurigana => "toggle_furigana",
+            Action::WordFamily => "word_family",
+            Action::OpenSettings => "open_settings",
+            Action::Confirm => "confirm",
+            Action::Cancel => "cancel",
+            Action::Up => "up",
+            Action::Down => "down",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|a| a.name() == name)
+    }
+    /// 画面読み上げ用の操作ボタンが押したことにするコード（キー割り当てを変えても変わらない）
+    pub fn button_code(&self) -> String {
+        format!("A11y:{}", self.name())
+    }
+    /// 既定のキー（`Gamepad` で始まるものはゲームパッドのボタン・スティック、`DeviceShake` は端末を振る操作）
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Flip => &["Space", "GamepadA"],
+            Action::Again => &["Digit1", "Numpad1", "GamepadB"],
+            Action::Hard => &["Digit2", "Numpad2", "GamepadX"],
+            Action::Good => &["Digit3", "Numpad3", "GamepadY"],
+            Action::Easy => &["Digit4", "Numpad4"],
+            Action::SwipeLeft => &["ArrowLeft", "GamepadLeft", "GamepadL1", "GamepadStickLeft"],
+            Action::SwipeRight => &["ArrowRight", "GamepadRight", "GamepadR1", "GamepadStickRight"],
+            Action::Undo => &["KeyZ", "Backspace", "GamepadL2", "DeviceShake"],
+            Action::ReplayAudio => &["KeyP"],
+            Action::OpenStats => &["KeyS", "GamepadStart"],
+            Action::ToggleFurigana => &["KeyR", "GamepadSelect"],
+            Action::WordFamily => &["KeyF", "GamepadR2"],
+            Action::OpenSettings => &["KeyO"],
+            Action::Confirm => &["Enter"],
+            Action::Cancel => &["Escape", "GamepadR3"],
+            Action::Up => &["ArrowUp", "GamepadUp", "GamepadStickUp"],
+            Action::Down => &["ArrowDown", "GamepadDown", "GamepadStickDown"],
+        }
+    }
+}
+
+/// 操作ごとのキー割り当て
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyBindings {
+    keys: Vec<(Action, Vec<String>)>, // Action::ALL と同じ順番
+}
+impl KeyBindings {
+    /// 保存形式の文字列を読み込む（壊れた行や未知の操作は無視し、書かれていない操作は既定のまま）
+    pub fn parse(text: &str) -> Self {
+        let mut bindings = KeyBindings::default();
+        for entry in text.split(';') {
+            let Some((name, codes)) = entry.split_once('=') else {
+                continue;
+            };
+            let Some(action) = Action::from_name(name.trim()) else {
+                continue;
+            };
+            let codes: Vec<String> = codes
+                .split(',')
+                .map(|c| c.trim())
+                .filter(|c| !c.is_empty())
+                .map(|c| c.to_string())
+                .collect();
+            bindings.set_keys(action, codes);
+        }
+        bindings
+    }
+
+    /// 保存形式の文字列にする
+    pub fn serialize(&self) -> String {
+        self.keys
+            .iter()
+            .map(|(action, codes)| format!("{}={}", action.name(), codes.join(",")))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    /// 操作に割り当てられたキー
+    pub fn keys(&self, action: Action) -> &[String] {
+        self.keys
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, codes)| codes.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// 操作のキーを1つに置き換える（キーボード・ゲームパッド・端末の動きは別々に置き換わる）
+    /// 同じキーが他の操作に割り当てられていた場合はそちらから外す
+    pub fn rebind(&mut self, action: Action, code: &str) {
+        for (_, codes) in self.keys.iter_mut() {
+            codes.retain(|c| c != code);
+        }
+        let mut codes: Vec<String> = self
+            .keys(action)
+            .iter()
+            .filter(|c| input_source(c) != input_source(code))
+            .cloned()
+            .collect();
+        codes.push(code.to_string());
+        self.set_keys(action, codes);
+    }
+
+    /// 押されている間 true（カードを送る操作など）
+    pub fn is_pressed(&self, keystate: &KeyState, action: Action) -> bool {
+        self.keys(action).iter().any(|c| keystate.is_pressed(c))
+            || keystate.is_pressed(&action.button_code())
+    }
+
+    /// 押された瞬間の update でだけ true（キーリピートでは反応しない）
+    pub fn is_just_pressed(&self, keystate: &KeyState, action: Action) -> bool {
+        self.keys(action).iter().any(|c| keystate.is_just_pressed(c))
+            || keystate.is_just_pressed(&action.button_code())
+    }
+
+    fn set_keys(&mut self, action: Action, codes: Vec<String>) {
+        if let Some((_, keys)) = self.keys.iter_mut().find(|(a, _)| *a == action) {
+            *keys = codes;
+        }
+    }
+}
+/// キーコードの入力元（"Gamepad" / "Device" / "Keyboard"）
+fn input_source(code: &str) -> &'static str {
+    if code.starts_with("Gamepad") {
+        "Gamepad"
+    } else if code.starts_with("Device") {
+        "Device"
+    } else {
+        "Keyboard"
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            keys: Action::ALL
+                .into_iter()
+                .map(|a| (a, a.default_keys().iter().map(|c| c.to_string()).collect()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_cover_every_action() {
+        let bindings = KeyBindings::default();
+        for action in Action::ALL {
+            assert!(!bindings.keys(action).is_empty(), "{:?}", action);
+        }
+        assert_eq!(bindings.keys(Action::Good), ["Digit3", "Numpad3", "GamepadY"]);
+    }
+
+    #[test]
+    fn defaults_never_share_a_key() {
+        let bindings = KeyBindings::default();
+        let mut seen: Vec<&str> = Vec::new();
+        for action in Action::ALL {
+            for code in bindings.keys(action) {
+                assert!(!seen.contains(&code.as_str()), "{} is bound twice", code);
+                seen.push(code);
+            }
+        }
+    }
+
+    #[test]
+    fn rebind_moves_key_away_from_other_action() {
+        let mut bindings = KeyBindings::default();
+        bindings.rebind(Action::Flip, "KeyJ");
+        bindings.rebind(Action::Undo, "Space");
+        assert_eq!(bindings.keys(Action::Flip), ["GamepadA", "KeyJ"]);
+        assert_eq!(bindings.keys(Action::Undo), ["GamepadL2", "DeviceShake", "Space"]);
+
+        bindings.rebind(Action::Good, "KeyJ");
+        assert_eq!(bindings.keys(Action::Flip), ["GamepadA"]);
+
+        bindings.rebind(Action::Flip, "GamepadX");
+        assert_eq!(bindings.keys(Action::Flip), ["GamepadX"]);
+        assert_eq!(bindings.keys(Action::Hard), ["Digit2", "Numpad2"]);
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let mut bindings = KeyBindings::default();
+        bindings.rebind(Action::OpenStats, "Tab");
+        assert_eq!(KeyBindings::parse(&bindings.serialize()), bindings);
+    }
+
+    #[test]
+    fn button_code_works_even_when_unbound() {
+        let bindings = KeyBindings::parse("undo=");
+        let mut keystate = KeyState::new();
+        keystate.set_pressed(&Action::Undo.button_code(), false);
+        keystate.set_released(&Action::Undo.button_code());
+        assert!(bindings.is_just_pressed(&keystate, Action::Undo));
+        assert!(!bindings.is_just_pressed(&keystate, Action::Flip));
+    }
+
+    #[test]
+    fn parse_ignores_broken_entries() {
+        let bindings = KeyBindings::parse("flip=KeyK;unknown=KeyQ;garbage;undo=");
+        assert_eq!(bindings.keys(Action::Flip), ["KeyK"]);
+        assert!(bindings.keys(Action::Undo).is_empty());
+        assert_eq!(bindings.keys(Action::Good), ["Digit3", "Numpad3", "GamepadY"]);
+    }
+}
@@ -1,13 +1,36 @@
 #[macro_use]
 mod browser;
+mod a11y;
+mod animation;
+mod backend;
 mod common;
+mod decoration;
+mod deck;
+mod editor;
 mod engine;
+mod etymology;
 mod game;
+mod gamepad;
+mod gesture;
+mod input;
+mod keymap;
+mod replay;
+mod ruby;
+mod settings;
+mod theme;
 
-use engine::GameLoop;
+use engine::{GameLoop, LoopConfig};
 use game::GameStage;
+use replay::Recorder;
+use std::cell::RefCell;
+use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 
+thread_local! {
+    // ?record で起動したときの入力の記録（save_recording で取り出す）
+    static RECORDER: RefCell<Option<Rc<RefCell<Recorder>>>> = const { RefCell::new(None) };
+}
+
 #[wasm_bindgen()]
 pub fn main() -> Result<(), JsValue> {
     console_error_panic_hook::set_once();
@@ -15,10 +38,90 @@ pub fn main() -> Result<(), JsValue> {
     browser::spawn_local(async move {
         let game = GameStage::new();
 
-        GameLoop::start(game)
+        // ?tick=30 でティックレート、?debug で FPS/ティックのデバッグ表示、?theme=dark でテーマを指定できる
+        // ?record で入力を記録する
+        let tick_rate = browser::query_param("tick")
+            .and_then(|tick| tick.parse().ok())
+            .unwrap_or(common::TICK_RATE);
+        let recorder = browser::query_param("record")
+            .map(|_| Rc::new(RefCell::new(Recorder::new(tick_rate))));
+        RECORDER.with(|cell| cell.replace(recorder.clone()));
+        // 利用者の設定（動き・コントラスト・文字）と端末の設定をテーマと動きに反映する
+        let settings = game::load_settings();
+        let system = settings::SystemPreferences {
+            reduced_motion: browser::media_matches("(prefers-reduced-motion: reduce)"),
+            high_contrast: browser::media_matches("(prefers-contrast: more)"),
+        };
+        let config = LoopConfig {
+            tick_rate,
+            debug_overlay: browser::query_param("debug").is_some(),
+            theme: settings.apply(load_theme(&settings), system),
+            motion: settings.motion(system),
+            gesture: settings.gesture_config(),
+            system,
+            recorder,
+        };
+
+        GameLoop::start(game, config)
             .await
             .expect("Cloud not start game loop");
     });
 
     Ok(())
 }
+
+/// キー割り当てを変更して localStorage に保存する（次回起動時から有効）
+/// 例: `bind_key("flip", "KeyJ")`。action は keymap::Action::name の名前
+#[wasm_bindgen]
+pub fn bind_key(action: &str, code: &str) -> Result<(), JsValue> {
+    let action = keymap::Action::from_name(action)
+        .ok_or_else(|| JsValue::from_str(&format!("Unknown action: {}", action)))?;
+    let mut bindings = match browser::load_setting(common::KEY_BINDINGS_STORAGE_KEY) {
+        Ok(Some(text)) => keymap::KeyBindings::parse(&text),
+        _ => keymap::KeyBindings::default(),
+    };
+    bindings.rebind(action, code);
+    browser::save_setting(common::KEY_BINDINGS_STORAGE_KEY, &bindings.serialize())
+        .map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+/// テーマを選ぶ（URL の ?theme= → 保存した設定 → デッキの既定の順に、知っている名前を使う）
+fn load_theme(settings: &settings::Settings) -> theme::Theme {
+    [browser::query_param("theme"), Some(settings.theme.clone())]
+        .into_iter()
+        .flatten()
+        .chain(std::iter::once(common::DECK_THEME.to_string()))
+        .find_map(|name| theme::Theme::by_name(&name))
+        .unwrap_or_default()
+}
+
+/// テーマを変更して localStorage に保存する（次回起動時から有効）
+/// 例: `set_theme("dark")`。name は theme::Theme::NAMES の名前（設定画面のテーマと同じ）
+#[wasm_bindgen]
+pub fn set_theme(name: &str) -> Result<(), JsValue> {
+    set_setting("theme", name)
+}
+
+/// 利用者の設定を1つ変更して localStorage に保存する（次回起動時から有効。設定画面ならすぐに反映される）
+/// 例: `set_setting("reduced_motion", "on")`、`set_setting("text_scale", "1.25")`
+/// name は settings::Settings::NAMES の名前
+#[wasm_bindgen]
+pub fn set_setting(name: &str, value: &str) -> Result<(), JsValue> {
+    let mut settings = game::load_settings();
+    settings
+        .set(name, value)
+        .map_err(|err| JsValue::from_str(&err.to_string()))?;
+    browser::save_setting(common::SETTINGS_STORAGE_KEY, &settings.serialize())
+        .map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+/// 記録した入力を保存形式の文字列で返す（?record で起動していなければ None）
+/// static/main.js の `save_recording()` がファイルとしてダウンロードする
+#[wasm_bindgen]
+pub fn recording() -> Option<String> {
+    RECORDER.with(|cell| {
+        cell.borrow()
+            .as_ref()
+            .map(|recorder| recorder.borrow().serialize())
+    })
+}
@@ -0,0 +1,236 @@
+//! 入力の記録とリプレイ
+//!
+//! `?record` をつけて起動すると、届いた入力を「何回目の update の前に届いたか」（ティック）と一緒に記録する。
+//! コンソールの `save_recording()`（static/main.js）でテキストファイルとして保存できる（不具合の報告用）。
+//! テストでは `Replay` が同じ入力を同じティックで `Game::update` に渡し直すので、同じ操作を何度でも再現できる。
+//! 報告されたファイルは `tests/replays` に置き、リプレイしたあとの `Material` をテストで確かめる。
+//!
+//! 時間は仮想の時計（ティック × 1ティックの長さ）で進める。ポインタの時刻も記録するときに
+//! この時計に直すので、長押しやフリックの判定も実際の時刻やフレームレートに左右されない。
+//! ゲームは乱数を使っていないので、入力とティックだけで結果が決まる。
+//!
+//! 保存形式（1行に1つ、`#` で始まる行はコメント）:
+//!
+//! ```text
+//! tick_rate 60
+//! ticks 240
+//! 5 key down Space
+//! 8 key up Space
+//! 40 pointer down 1 225 300 666.7
+//! 90 clear
+//! ```
+
+use crate::engine::FixedStep;
+use crate::input::{InputEvent, KeyInput, PointerAction};
+#[cfg(test)]
+use crate::engine::Game;
+#[cfg(test)]
+use crate::input::{KeyState, PointerState};
+#[cfg(test)]
+use anyhow::{Result, anyhow};
+
+/// 入力の記録（GameLoop が毎フレーム入力とティックの数を渡す）
+pub struct Recorder {
+    tick_rate: f64,
+    step: f64,                       // 1ティックの長さ（ms）
+    ticks: u64,                      // これまでの update の回数
+    entries: Vec<(u64, InputEvent)>, // 入力と、その入力のあとの最初の update のティック
+}
+impl Recorder {
+    pub fn new(tick_rate: f64) -> Self {
+        Recorder {
+            tick_rate,
+            step: FixedStep::new(tick_rate).step(),
+            ticks: 0,
+            entries: Vec::new(),
+        }
+    }
+    /// 入力を1つ記録する（frame_time: 入力を受け取ったフレームの時刻 ms）
+    /// ポインタの時刻は、フレームとの差を保ったまま仮想の時計に直す
+    pub fn record(&mut self, input: &InputEvent, frame_time: f64) {
+        let clock = self.ticks as f64 * self.step;
+        let to_clock = |time: f64| clock + (time - frame_time);
+        let input = match *input {
+            InputEvent::Pointer(PointerAction::Down { id, x, y, time }) => {
+                InputEvent::Pointer(PointerAction::Down { id, x, y, time: to_clock(time) })
+            }
+            InputEvent::Pointer(PointerAction::Move { id, x, y, time }) => {
+                InputEvent::Pointer(PointerAction::Move { id, x, y, time: to_clock(time) })
+            }
+            InputEvent::Pointer(PointerAction::Up { id, x, y, time }) => {
+                InputEvent::Pointer(PointerAction::Up { id, x, y, time: to_clock(time) })
+            }
+            _ => input.clone(),
+        };
+        self.entries.push((self.ticks, input));
+    }
+    /// フレームの update の回数を進める
+    pub fn advance(&mut self, updates: u32) {
+        self.ticks += updates as u64;
+    }
+    /// 保存形式の文字列にする
+    pub fn serialize(&self) -> String {
+        let mut lines = vec![
+            format!("tick_rate {}", self.tick_rate),
+            format!("ticks {}", self.ticks),
+        ];
+        lines.extend(
+            self.entries
+                .iter()
+                .map(|(tick, input)| format!("{} {}", tick, serialize_input(input))),
+        );
+        lines.join("\n") + "\n"
+    }
+}
+
+/// 記録した入力の再生
+#[cfg(test)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub tick_rate: f64,
+    pub ticks: u64, // 再生する update の回数
+    entries: Vec<(u64, InputEvent)>,
+}
+#[cfg(test)]
+impl Replay {
+    /// 保存形式の文字列を読み込む（壊れた行があればその行番号を返す）
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut replay = Replay {
+            tick_rate: crate::common::TICK_RATE,
+            ticks: 0,
+            entries: Vec::new(),
+        };
+        let mut ticks = None;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let parsed = match words.as_slice() {
+                ["tick_rate", rate] => rate.parse().ok().map(|rate| replay.tick_rate = rate),
+                ["ticks", count] => count.parse().ok().map(|count| ticks = Some(count)),
+                [tick, rest @ ..] => tick
+                    .parse()
+                    .ok()
+                    .zip(parse_input(rest))
+                    .map(|entry| replay.entries.push(entry)),
+                [] => None,
+            };
+            parsed.ok_or_else(|| anyhow!("replay line {}: {}", number + 1, line))?;
+        }
+        // ticks の行がなければ（手で書いた入力など）最後の入力を使う update まで再生する
+        replay.entries.sort_by_key(|(tick, _)| *tick);
+        let last = replay.entries.last().map_or(0, |(tick, _)| tick + 1);
+        replay.ticks = ticks.unwrap_or(last);
+        Ok(replay)
+    }
+    /// 記録した入力を、記録したティックで game に渡しながら update を ticks 回呼ぶ
+    /// GameLoop の1ティック分と同じ順番（入力 → 長押しの判定 → update → エッジのクリア）で進める
+    pub fn run(&self, game: &mut dyn Game) {
+        let step = FixedStep::new(self.tick_rate).step();
+        let mut keystate = KeyState::new();
+        let mut pointerstate = PointerState::new();
+        let mut entries = self.entries.iter().peekable();
+        for tick in 0..self.ticks {
+            while let Some((_, input)) = entries.next_if(|(at, _)| *at <= tick) {
+                input.apply(&mut keystate, &mut pointerstate);
+            }
+            pointerstate.tick(tick as f64 * step);
+            game.update(step, &mut keystate, &mut pointerstate);
+            pointerstate.clear_gestures();
+            keystate.clear_just_pressed();
+        }
+    }
+}
+
+fn serialize_input(input: &InputEvent) -> String {
+    match input {
+        InputEvent::Key(KeyInput::Down { code, repeat: false }) => format!("key down {}", code),
+        InputEvent::Key(KeyInput::Down { code, repeat: true }) => format!("key repeat {}", code),
+        InputEvent::Key(KeyInput::Up { code }) => format!("key up {}", code),
+        InputEvent::Pointer(PointerAction::Down { id, x, y, time }) => {
+            format!("pointer down {} {} {} {}", id, x, y, time)
+        }
+        InputEvent::Pointer(PointerAction::Move { id, x, y, time }) => {
+            format!("pointer move {} {} {} {}", id, x, y, time)
+        }
+        InputEvent::Pointer(PointerAction::Up { id, x, y, time }) => {
+            format!("pointer up {} {} {} {}", id, x, y, time)
+        }
+        InputEvent::Pointer(PointerAction::Cancel { id }) => format!("pointer cancel {}", id),
+        InputEvent::Clear => "clear".to_string(),
+    }
+}
+
+#[cfg(test)]
+fn parse_input(words: &[&str]) -> Option<InputEvent> {
+    let pointer = |id: &str, x: &str, y: &str, time: &str| {
+        Some((id.parse().ok()?, x.parse().ok()?, y.parse().ok()?, time.parse().ok()?))
+    };
+    let input = match *words {
+        ["key", "down", code] => InputEvent::Key(KeyInput::Down {
+            code: code.to_string(),
+            repeat: false,
+        }),
+        ["key", "repeat", code] => InputEvent::Key(KeyInput::Down {
+            code: code.to_string(),
+            repeat: true,
+        }),
+        ["key", "up", code] => InputEvent::Key(KeyInput::Up {
+            code: code.to_string(),
+        }),
+        ["pointer", kind, id, x, y, time] => {
+            let (id, x, y, time) = pointer(id, x, y, time)?;
+            InputEvent::Pointer(match kind {
+                "down" => PointerAction::Down { id, x, y, time },
+                "move" => PointerAction::Move { id, x, y, time },
+                "up" => PointerAction::Up { id, x, y, time },
+                _ => return None,
+            })
+        }
+        ["pointer", "cancel", id] => InputEvent::Pointer(PointerAction::Cancel {
+            id: id.parse().ok()?,
+        }),
+        ["clear"] => InputEvent::Clear,
+        _ => return None,
+    };
+    Some(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_round_trips_and_uses_the_virtual_clock() {
+        let mut recorder = Recorder::new(50.0);
+        recorder.advance(3);
+        // フレームの 5ms 前に押された → 3ティック目の時計（60ms）の 5ms 前
+        recorder.record(
+            &InputEvent::Pointer(PointerAction::Down { id: 1, x: 200, y: 300, time: 1995.0 }),
+            2000.0,
+        );
+        recorder.record(
+            &InputEvent::Key(KeyInput::Down { code: "Space".to_string(), repeat: true }),
+            2000.0,
+        );
+        recorder.advance(2);
+        recorder.record(&InputEvent::Clear, 2040.0);
+
+        let text = recorder.serialize();
+        assert_eq!(
+            text,
+            "tick_rate 50\nticks 5\n3 pointer down 1 200 300 55\n3 key repeat Space\n5 clear\n"
+        );
+        let replay = Replay::parse(&text).unwrap();
+        assert_eq!((replay.tick_rate, replay.ticks), (50.0, 5));
+        assert_eq!(replay.entries, recorder.entries);
+    }
+
+    #[test]
+    fn broken_lines_are_reported() {
+        let err = Replay::parse("ticks 10\n# comment\n\n3 key sideways Space\n").unwrap_err();
+        assert!(err.to_string().contains("line 4"), "{}", err);
+    }
+}
@@ -0,0 +1,136 @@
+//! ルビ（ふりがな）記法のパーサ
+//!
+//! `丈夫{じょうぶ}` のように漢字の直後に `{読み}` を書くと、直前の漢字列が親文字になる。
+//! 漢字以外を親文字にしたい場合は `｜` または `|` で開始位置を明示する（例: `｜お茶{おちゃ}`）。
+
+/// ルビ付きテキストの1区間
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RubySegment {
+    pub base: String,            // 親文字
+    pub reading: Option<String>, // ルビ（読み）。なければ None
+}
+
+/// 親文字として自動的に取り込む文字（漢字・々・〆・ヶ）かどうか
+fn is_ruby_base_char(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}'   // CJK統合漢字
+        | '\u{3400}'..='\u{4DBF}' // CJK統合漢字拡張A
+        | '\u{F900}'..='\u{FAFF}' // CJK互換漢字
+        | '々' | '〆' | 'ヶ')
+}
+
+/// 通常テキストを区間列の末尾に追加（直前もルビなしなら結合する）
+fn push_plain(segments: &mut Vec<RubySegment>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match segments.last_mut() {
+        Some(last) if last.reading.is_none() => last.base.push_str(text),
+        _ => segments.push(RubySegment {
+            base: text.to_string(),
+            reading: None,
+        }),
+    }
+}
+
+/// ルビ記法を含むテキストを区間に分割する
+/// 閉じ括弧のない `{` や親文字のない `{...}` はそのまま文字として扱う
+pub fn parse_ruby(text: &str) -> Vec<RubySegment> {
+    let mut segments: Vec<RubySegment> = Vec::new();
+    let mut pending = String::new(); // まだ確定していない通常テキスト
+    let mut explicit_start: Option<(usize, usize)> = None; // `｜` の位置と親文字の開始位置（pending内のバイト位置）
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '|' | '｜' => {
+                let bar = pending.len();
+                pending.push(c);
+                explicit_start = Some((bar, pending.len()));
+            }
+            '{' => {
+                let rest: String = chars.clone().collect();
+                let Some(close) = rest.find('}') else {
+                    pending.push(c);
+                    continue;
+                };
+                let reading = &rest[..close];
+
+                // 親文字の範囲を決定（`｜` 自体は表示しない）
+                let (prefix_end, base_start) = match explicit_start.take() {
+                    Some(range) => range,
+                    None => {
+                        let start = pending
+                            .char_indices()
+                            .rev()
+                            .take_while(|&(_, ch)| is_ruby_base_char(ch))
+                            .last()
+                            .map(|(i, _)| i)
+                            .unwrap_or(pending.len());
+                        (start, start)
+                    }
+                };
+                if base_start == pending.len() || reading.is_empty() {
+                    // 親文字または読みがないのでルビとして扱わない
+                    pending.push(c);
+                    continue;
+                }
+
+                push_plain(&mut segments, &pending[..prefix_end]);
+                segments.push(RubySegment {
+                    base: pending[base_start..].to_string(),
+                    reading: Some(reading.to_string()),
+                });
+                pending.clear();
+
+                // 読みと閉じ括弧を読み飛ばす
+                for _ in 0..reading.chars().count() + 1 {
+                    chars.next();
+                }
+            }
+            _ => pending.push(c),
+        }
+    }
+    push_plain(&mut segments, &pending);
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(text: &str) -> RubySegment {
+        RubySegment {
+            base: text.to_string(),
+            reading: None,
+        }
+    }
+    fn ruby(base: &str, reading: &str) -> RubySegment {
+        RubySegment {
+            base: base.to_string(),
+            reading: Some(reading.to_string()),
+        }
+    }
+
+    #[test]
+    fn kanji_before_braces_become_the_base() {
+        assert_eq!(
+            parse_ruby("丈夫{じょうぶ}な靴{くつ}"),
+            [ruby("丈夫", "じょうぶ"), plain("な"), ruby("靴", "くつ")]
+        );
+    }
+
+    #[test]
+    fn explicit_bar_starts_the_base_and_is_not_shown() {
+        assert_eq!(parse_ruby("私の｜お茶{おちゃ}"), [plain("私の"), ruby("お茶", "おちゃ")]);
+        assert_eq!(parse_ruby("|Tea{ティー}です"), [ruby("Tea", "ティー"), plain("です")]);
+    }
+
+    #[test]
+    fn unclosed_or_empty_braces_stay_as_text() {
+        assert_eq!(parse_ruby("丈夫{じょうぶ な靴"), [plain("丈夫{じょうぶ な靴")]);
+        assert_eq!(parse_ruby("丈夫{}な靴{くつ}"), [plain("丈夫{}な"), ruby("靴", "くつ")]);
+        // 親文字のない読みもそのまま
+        assert_eq!(parse_ruby("な{な}"), [plain("な{な}")]);
+    }
+}
@@ -0,0 +1,505 @@
+//! 利用者の設定（セッションの枚数・出題の向き・後回しの間隔・テーマ・読み上げ・スワイプの感度、
+//! 動きを減らす・コントラストを上げる・文字の大きさ・読みやすいフォント）
+//!
+//! 動きとコントラストは端末の設定（`prefers-reduced-motion` / `prefers-contrast`）に従うのが既定で、
+//! ここで常にオン・オフにもできる。設定は
+//! `session_size=5;direction=front;…;text_scale=1.25;dyslexia_font=off` の形式で localStorage に保存する。
+//! `Item` は設定画面の1行（名前・今の値・値の変え方）。
+
+use crate::common::*;
+use crate::gesture::GestureConfig;
+use crate::theme::{Face, Theme};
+use anyhow::{Result, anyhow};
+
+/// 端末の設定に従うか、常にオン・オフにするか
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Preference {
+    #[default]
+    System,
+    On,
+    Off,
+}
+impl Preference {
+    pub const ALL: [Preference; 3] = [Preference::System, Preference::On, Preference::Off];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preference::System => "system",
+            Preference::On => "on",
+            Preference::Off => "off",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Preference> {
+        Preference::ALL.into_iter().find(|p| p.name() == name)
+    }
+    /// system: 端末で設定されているかどうか
+    pub fn resolve(&self, system: bool) -> bool {
+        match self {
+            Preference::System => system,
+            Preference::On => true,
+            Preference::Off => false,
+        }
+    }
+}
+
+/// カードの動かし方
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Motion {
+    #[default]
+    Full, // 傾けて飛ばす・遠近法で裏返す
+    Reduced, // その場で薄くする・表裏を重ねて切り替える（クロスフェード）
+}
+
+/// 「もう一度」で後回しにしたカードをどこに戻すか
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scheduler {
+    #[default]
+    Last, // 最後に回す
+    Soon, // {SCHEDULER_SOON_GAP} 枚後に回す（早めにもう一度見る）
+}
+impl Scheduler {
+    pub const ALL: [Scheduler; 2] = [Scheduler::Last, Scheduler::Soon];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scheduler::Last => "last",
+            Scheduler::Soon => "soon",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Scheduler> {
+        Scheduler::ALL.into_iter().find(|s| s.name() == name)
+    }
+    /// 残りが len 枚のとき、後回しにしたカードを入れる位置
+    pub fn requeue_position(&self, len: usize) -> usize {
+        match self {
+            Scheduler::Last => len,
+            Scheduler::Soon => len.min(SCHEDULER_SOON_GAP),
+        }
+    }
+}
+
+/// 端末（ブラウザ）の設定
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SystemPreferences {
+    pub reduced_motion: bool, // prefers-reduced-motion: reduce
+    pub high_contrast: bool,  // prefers-contrast: more
+}
+
+/// 利用者の設定
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub session_size: i32,      // 1回のセッションのカードの枚数（{SESSION_SIZE_MIN}〜{SESSION_SIZE_MAX}）
+    pub direction: Face,        // 最初に見せる面（Back なら英文から日本語を思い出す）
+    pub scheduler: Scheduler,   // 「もう一度」のカードを戻す位置
+    pub theme: String,          // テーマの名前（theme::Theme::NAMES のいずれか）
+    pub audio: bool,            // 裏面（英文）をめくったときに読み上げる
+    pub swipe_sensitivity: i32, // スワイプの感度（1〜{SWIPE_SENSITIVITY_MAX}、大きいほど短い距離で判定）
+    pub reduced_motion: Preference,
+    pub high_contrast: Preference, // オンなら high-contrast のテーマを使う
+    pub text_scale: f64,           // 文字の大きさの倍率（{TEXT_SCALE_MIN}〜{TEXT_SCALE_MAX}）
+    pub dyslexia_font: bool,       // ディスレクシア向けの読みやすいフォントを使う
+}
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            session_size: FLASH_CARD_NUMBERS,
+            direction: Face::Front,
+            scheduler: Scheduler::Last,
+            theme: DECK_THEME.to_string(),
+            audio: false,
+            swipe_sensitivity: SWIPE_SENSITIVITY_DEFAULT,
+            reduced_motion: Preference::System,
+            high_contrast: Preference::System,
+            text_scale: 1.0,
+            dyslexia_font: false,
+        }
+    }
+}
+impl Settings {
+    pub const NAMES: [&'static str; 10] = [
+        "session_size",
+        "direction",
+        "scheduler",
+        "theme",
+        "audio",
+        "swipe_sensitivity",
+        "reduced_motion",
+        "high_contrast",
+        "text_scale",
+        "dyslexia_font",
+    ];
+
+    /// 保存形式の文字列を読み込む（壊れた項目は無視し、書かれていない項目は既定のまま）
+    pub fn parse(text: &str) -> Self {
+        let mut settings = Settings::default();
+        for entry in text.split(';') {
+            if let Some((name, value)) = entry.split_once('=') {
+                let _ = settings.set(name.trim(), value.trim());
+            }
+        }
+        settings
+    }
+
+    /// 保存形式の文字列にする
+    pub fn serialize(&self) -> String {
+        Settings::NAMES
+            .iter()
+            .map(|name| format!("{}={}", name, self.get(name).unwrap_or_default()))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    /// 項目の値を保存形式の文字列で
+    pub fn get(&self, name: &str) -> Option<String> {
+        let on_off = |value: bool| if value { "on" } else { "off" }.to_string();
+        match name {
+            "session_size" => Some(self.session_size.to_string()),
+            "direction" => Some(
+                match self.direction {
+                    Face::Front => "front",
+                    Face::Back => "back",
+                }
+                .to_string(),
+            ),
+            "scheduler" => Some(self.scheduler.name().to_string()),
+            "theme" => Some(self.theme.clone()),
+            "audio" => Some(on_off(self.audio)),
+            "swipe_sensitivity" => Some(self.swipe_sensitivity.to_string()),
+            "reduced_motion" => Some(self.reduced_motion.name().to_string()),
+            "high_contrast" => Some(self.high_contrast.name().to_string()),
+            "text_scale" => Some(self.text_scale.to_string()),
+            "dyslexia_font" => Some(on_off(self.dyslexia_font)),
+            _ => None,
+        }
+    }
+
+    /// 項目を1つ変更する（枚数・感度・文字の大きさは範囲内に収める）
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        let invalid = || anyhow!("Invalid value for {}: {}", name, value);
+        let on_off = || match value {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(invalid()),
+        };
+        match name {
+            "session_size" => {
+                let size: i32 = value.parse().map_err(|_| invalid())?;
+                self.session_size = size.clamp(SESSION_SIZE_MIN, SESSION_SIZE_MAX);
+            }
+            "direction" => {
+                self.direction = match value {
+                    "front" => Face::Front,
+                    "back" => Face::Back,
+                    _ => return Err(invalid()),
+                }
+            }
+            "scheduler" => self.scheduler = Scheduler::from_name(value).ok_or_else(invalid)?,
+            "theme" => {
+                let theme = Theme::by_name(value).ok_or_else(|| {
+                    anyhow!("Unknown theme: {} (available: {})", value, Theme::NAMES.join(", "))
+                })?;
+                self.theme = theme.name.to_string();
+            }
+            "audio" => self.audio = on_off()?,
+            "swipe_sensitivity" => {
+                let level: i32 = value.parse().map_err(|_| invalid())?;
+                self.swipe_sensitivity = level.clamp(1, SWIPE_SENSITIVITY_MAX);
+            }
+            "reduced_motion" => {
+                self.reduced_motion = Preference::from_name(value).ok_or_else(invalid)?
+            }
+            "high_contrast" => {
+                self.high_contrast = Preference::from_name(value).ok_or_else(invalid)?
+            }
+            "text_scale" => {
+                let scale: f64 = value.parse().map_err(|_| invalid())?;
+                if !scale.is_finite() {
+                    return Err(invalid());
+                }
+                self.text_scale = scale.clamp(TEXT_SCALE_MIN, TEXT_SCALE_MAX);
+            }
+            "dyslexia_font" => self.dyslexia_font = on_off()?,
+            _ => {
+                return Err(anyhow!(
+                    "Unknown setting: {} (available: {})",
+                    name,
+                    Settings::NAMES.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn motion(&self, system: SystemPreferences) -> Motion {
+        if self.reduced_motion.resolve(system.reduced_motion) {
+            Motion::Reduced
+        } else {
+            Motion::Full
+        }
+    }
+
+    /// スワイプの感度に合わせたジェスチャーの判定（感度を1段上げると {SWIPE_SENSITIVITY_STEP}px 短い距離で判定）
+    pub fn gesture_config(&self) -> GestureConfig {
+        let level = (SWIPE_SENSITIVITY_DEFAULT - self.swipe_sensitivity) as f64;
+        GestureConfig {
+            swipe_distance: SWIPE_COMMIT_DISTANCE + level * SWIPE_SENSITIVITY_STEP,
+            ..GestureConfig::default()
+        }
+    }
+
+    /// 選んだテーマ（知らない名前なら既定のテーマ）に設定を反映する
+    pub fn theme(&self, system: SystemPreferences) -> Theme {
+        self.apply(Theme::by_name(&self.theme).unwrap_or_default(), system)
+    }
+
+    /// テーマに反映する（コントラストを上げるなら high-contrast に替え、文字の大きさとフォントを変える）
+    pub fn apply(&self, theme: Theme, system: SystemPreferences) -> Theme {
+        let mut theme = if self.high_contrast.resolve(system.high_contrast) {
+            Theme::high_contrast()
+        } else {
+            theme
+        };
+        theme.text_scale = self.text_scale;
+        if self.dyslexia_font {
+            theme.font_family = DYSLEXIA_FONT_FAMILY.to_string();
+        }
+        theme
+    }
+}
+
+/// 設定画面の行の見た目
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Control {
+    Toggle(bool), // オン・オフのスイッチ
+    Choice,       // 選択肢を順に切り替える（値の文字だけを表示）
+    Slider(f64),  // 範囲の中の位置（0.0〜1.0）
+    Button,       // 押すと別の画面に移る（デッキの編集・閉じる）
+}
+
+/// 設定画面の行
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Item {
+    SessionSize,
+    Direction,
+    Scheduler,
+    Theme,
+    Audio,
+    SwipeSensitivity,
+    ReducedMotion,
+    HighContrast,
+    TextScale,
+    DyslexiaFont,
+    EditDeck,
+    Close,
+}
+impl Item {
+    pub const ALL: [Item; 12] = [
+        Item::SessionSize,
+        Item::Direction,
+        Item::Scheduler,
+        Item::Theme,
+        Item::Audio,
+        Item::SwipeSensitivity,
+        Item::ReducedMotion,
+        Item::HighContrast,
+        Item::TextScale,
+        Item::DyslexiaFont,
+        Item::EditDeck,
+        Item::Close,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Item::SessionSize => "セッションの枚数",
+            Item::Direction => "出題の向き",
+            Item::Scheduler => "もう一度のカード",
+            Item::Theme => "テーマ",
+            Item::Audio => "英文の読み上げ",
+            Item::SwipeSensitivity => "スワイプの感度",
+            Item::ReducedMotion => "動きを減らす",
+            Item::HighContrast => "コントラストを上げる",
+            Item::TextScale => "文字の大きさ",
+            Item::DyslexiaFont => "読みやすいフォント",
+            Item::EditDeck => "デッキを編集",
+            Item::Close => "閉じる",
+        }
+    }
+
+    /// 表示・読み上げ用の今の値
+    pub fn value(&self, settings: &Settings) -> String {
+        let on_off = |value: bool| if value { "オン" } else { "オフ" }.to_string();
+        let preference = |preference: Preference| {
+            match preference {
+                Preference::System => "端末に従う",
+                Preference::On => "オン",
+                Preference::Off => "オフ",
+            }
+            .to_string()
+        };
+        match self {
+            Item::SessionSize => format!("{} 枚", settings.session_size),
+            Item::Direction => match settings.direction {
+                Face::Front => "表 → 裏".to_string(),
+                Face::Back => "裏 → 表".to_string(),
+            },
+            Item::Scheduler => match settings.scheduler {
+                Scheduler::Last => "最後に回す".to_string(),
+                Scheduler::Soon => format!("{} 枚後に回す", SCHEDULER_SOON_GAP),
+            },
+            Item::Theme => settings.theme.clone(),
+            Item::Audio => on_off(settings.audio),
+            Item::SwipeSensitivity => format!("{} / {}", settings.swipe_sensitivity, SWIPE_SENSITIVITY_MAX),
+            Item::ReducedMotion => preference(settings.reduced_motion),
+            Item::HighContrast => preference(settings.high_contrast),
+            Item::TextScale => format!("{:.0}%", settings.text_scale * 100.0),
+            Item::DyslexiaFont => on_off(settings.dyslexia_font),
+            Item::EditDeck | Item::Close => String::new(),
+        }
+    }
+
+    pub fn control(&self, settings: &Settings) -> Control {
+        let fraction = |value: f64, min: f64, max: f64| (value - min) / (max - min);
+        match self {
+            Item::SessionSize => Control::Slider(fraction(
+                settings.session_size as f64,
+                SESSION_SIZE_MIN as f64,
+                SESSION_SIZE_MAX as f64,
+            )),
+            Item::SwipeSensitivity => Control::Slider(fraction(
+                settings.swipe_sensitivity as f64,
+                1.0,
+                SWIPE_SENSITIVITY_MAX as f64,
+            )),
+            Item::TextScale => Control::Slider(fraction(settings.text_scale, TEXT_SCALE_MIN, TEXT_SCALE_MAX)),
+            Item::Audio => Control::Toggle(settings.audio),
+            Item::DyslexiaFont => Control::Toggle(settings.dyslexia_font),
+            Item::EditDeck | Item::Close => Control::Button,
+            _ => Control::Choice,
+        }
+    }
+
+    /// 値を1段階変える（delta: 1 で次、-1 で前）
+    /// スライダーは端で止まり、選択肢とスイッチは一周する
+    pub fn step(&self, settings: &mut Settings, delta: i32) {
+        fn cycle<T: Copy + PartialEq>(all: &[T], current: T, delta: i32) -> T {
+            let index = all.iter().position(|v| *v == current).unwrap_or(0) as i32;
+            all[(index + delta).rem_euclid(all.len() as i32) as usize]
+        }
+        match self {
+            Item::SessionSize => {
+                settings.session_size = (settings.session_size + delta).clamp(SESSION_SIZE_MIN, SESSION_SIZE_MAX)
+            }
+            Item::Direction => settings.direction = cycle(&[Face::Front, Face::Back], settings.direction, delta),
+            Item::Scheduler => settings.scheduler = cycle(&Scheduler::ALL, settings.scheduler, delta),
+            Item::Theme => {
+                let current = Theme::NAMES.iter().copied().find(|name| *name == settings.theme);
+                settings.theme = cycle(&Theme::NAMES, current.unwrap_or(DECK_THEME), delta).to_string();
+            }
+            Item::Audio => settings.audio = !settings.audio,
+            Item::SwipeSensitivity => {
+                settings.swipe_sensitivity = (settings.swipe_sensitivity + delta).clamp(1, SWIPE_SENSITIVITY_MAX)
+            }
+            Item::ReducedMotion => settings.reduced_motion = cycle(&Preference::ALL, settings.reduced_motion, delta),
+            Item::HighContrast => settings.high_contrast = cycle(&Preference::ALL, settings.high_contrast, delta),
+            Item::TextScale => {
+                let scale = settings.text_scale + TEXT_SCALE_STEP * delta as f64;
+                settings.text_scale = scale.clamp(TEXT_SCALE_MIN, TEXT_SCALE_MAX);
+            }
+            Item::DyslexiaFont => settings.dyslexia_font = !settings.dyslexia_font,
+            Item::EditDeck | Item::Close => {}
+        }
+    }
+
+    /// スライダーの位置（0.0〜1.0）から値を決める（スライダー以外は何もしない）
+    pub fn set_fraction(&self, settings: &mut Settings, fraction: f64) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let lerp = |min: f64, max: f64| min + (max - min) * fraction;
+        match self {
+            Item::SessionSize => {
+                settings.session_size = lerp(SESSION_SIZE_MIN as f64, SESSION_SIZE_MAX as f64).round() as i32
+            }
+            Item::SwipeSensitivity => {
+                settings.swipe_sensitivity = lerp(1.0, SWIPE_SENSITIVITY_MAX as f64).round() as i32
+            }
+            Item::TextScale => {
+                let steps = ((lerp(TEXT_SCALE_MIN, TEXT_SCALE_MAX) - TEXT_SCALE_MIN) / TEXT_SCALE_STEP).round();
+                settings.text_scale = TEXT_SCALE_MIN + steps * TEXT_SCALE_STEP;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_round_trip_and_broken_entries() {
+        let mut settings = Settings::default();
+        settings.set("reduced_motion", "on").unwrap();
+        settings.set("text_scale", "1.25").unwrap();
+        settings.set("dyslexia_font", "on").unwrap();
+        settings.set("direction", "back").unwrap();
+        settings.set("theme", " dark ").unwrap();
+        assert_eq!(
+            settings.serialize(),
+            "session_size=5;direction=back;scheduler=last;theme=dark;audio=off;swipe_sensitivity=3;\
+             reduced_motion=on;high_contrast=system;text_scale=1.25;dyslexia_font=on"
+        );
+        assert_eq!(Settings::parse(&settings.serialize()), settings);
+
+        let parsed = Settings::parse(
+            "text_scale=9;high_contrast=maybe;unknown=1;garbage;session_size=0;theme=neon",
+        );
+        assert_eq!(parsed.text_scale, TEXT_SCALE_MAX);
+        assert_eq!(parsed.session_size, SESSION_SIZE_MIN);
+        assert_eq!(parsed.theme, DECK_THEME);
+        assert_eq!(parsed.high_contrast, Preference::System);
+        assert!(settings.set("text_scale", "NaN").is_err());
+        assert!(settings.set("volume", "1").is_err());
+    }
+
+    #[test]
+    fn system_preferences_apply_unless_overridden() {
+        let system = SystemPreferences {
+            reduced_motion: true,
+            high_contrast: true,
+        };
+        let settings = Settings::default();
+        assert_eq!(settings.motion(system), Motion::Reduced);
+        assert_eq!(settings.apply(Theme::dark(), system).name, "high-contrast");
+
+        let settings = Settings::parse("reduced_motion=off;high_contrast=off;text_scale=1.5;dyslexia_font=on");
+        assert_eq!(settings.motion(system), Motion::Full);
+        let theme = settings.apply(Theme::dark(), system);
+        assert_eq!(theme.name, "dark");
+        assert_eq!(theme.font(20.0), format!("30px {}", DYSLEXIA_FONT_FAMILY));
+    }
+
+    #[test]
+    fn items_step_within_their_ranges() {
+        let mut settings = Settings::default();
+        Item::SessionSize.step(&mut settings, -10);
+        assert_eq!(settings.session_size, SESSION_SIZE_MIN);
+        Item::SessionSize.set_fraction(&mut settings, 1.0);
+        assert_eq!(settings.session_size, SESSION_SIZE_MAX);
+        assert_eq!(Item::SessionSize.control(&settings), Control::Slider(1.0));
+
+        // 選択肢は一周する
+        Item::Theme.step(&mut settings, -1);
+        assert_eq!(settings.theme, Theme::NAMES[Theme::NAMES.len() - 1]);
+        Item::HighContrast.step(&mut settings, 1);
+        assert_eq!(Item::HighContrast.value(&settings), "オン");
+
+        // 文字の大きさはスライダーの位置を {TEXT_SCALE_STEP} 刻みに丸める
+        Item::TextScale.set_fraction(&mut settings, 0.45);
+        assert_eq!(Item::TextScale.value(&settings), "125%");
+
+        // 感度を上げると短い距離でスワイプになる
+        Item::SwipeSensitivity.step(&mut settings, 2);
+        assert_eq!(settings.swipe_sensitivity, SWIPE_SENSITIVITY_MAX);
+        assert!(settings.gesture_config().swipe_distance < SWIPE_COMMIT_DISTANCE);
+        assert_eq!(Settings::default().gesture_config(), GestureConfig::default());
+    }
+}
@@ -0,0 +1,187 @@
+//! 見た目のテーマ（カードの表裏の配色・フォント・装飾・背景）
+//!
+//! 組み込みのテーマは `celtic`（既定）・`minimal`・`dark`・`high-contrast`。
+//! デッキの既定のテーマ（`DECK_THEME`）を、URL の `?theme=` か保存した設定で上書きできる。
+
+use crate::decoration::Decorations;
+
+/// カードのどちらの面か
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Face {
+    Front, // 表面（日本語）
+    Back,  // 裏面（英語・語源）
+}
+
+/// 面ごとの配色
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    pub card: String,      // カードの地の色
+    pub panel: String,     // カードと同じ大きさのウィンドウ（学習状況・ワードファミリー）の地の色
+    pub ink: (u8, u8, u8), // 文字と装飾の線の色（装飾は透明度を変えて重ねる）
+    pub accent: String,    // プログレスカウンター・選択中の行・ボタンなどの強調色
+}
+impl Palette {
+    /// 文字・装飾の色を透明度つきで
+    pub fn ink(&self, alpha: f64) -> String {
+        let (r, g, b) = self.ink;
+        format!("rgba({}, {}, {}, {})", r, g, b, alpha)
+    }
+}
+
+/// 見た目のテーマ
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: &'static str,
+    pub front: Palette,
+    pub back: Palette,
+    pub font_family: String,
+    pub text_scale: f64, // 文字の大きさの倍率（利用者の設定、settings で変える）
+    pub decorations: Decorations, // 表裏の既定の装飾（デッキ・タグの指定がなければこれを使う）
+    pub background: Option<String>, // None ならページ（CSS）の背景をそのまま見せる
+}
+impl Theme {
+    pub const NAMES: [&'static str; 4] = ["celtic", "minimal", "dark", "high-contrast"];
+
+    pub fn by_name(name: &str) -> Option<Theme> {
+        match name.trim() {
+            "celtic" => Some(Theme::celtic()),
+            "minimal" => Some(Theme::minimal()),
+            "dark" => Some(Theme::dark()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// 緑の表面・青の裏面にケルト文様（これまでの見た目）
+    pub fn celtic() -> Self {
+        Theme {
+            name: "celtic",
+            front: Palette {
+                card: "#008000ff".to_string(),
+                panel: "#008000ff".to_string(),
+                ink: (255, 255, 255),
+                accent: "#72F285".to_string(),
+            },
+            back: Palette {
+                card: "#4169e1".to_string(),
+                panel: "#1e3a5f".to_string(),
+                ink: (255, 255, 255),
+                accent: "#4169e1".to_string(),
+            },
+            font_family: "MyFont".to_string(),
+            text_scale: 1.0,
+            decorations: Decorations::celtic(),
+            background: None,
+        }
+    }
+
+    /// 明るい地に濃い文字、装飾は枠線だけ
+    pub fn minimal() -> Self {
+        Theme {
+            name: "minimal",
+            front: Palette {
+                card: "#fafafa".to_string(),
+                panel: "#fafafa".to_string(),
+                ink: (33, 33, 33),
+                accent: "#2e7d32".to_string(),
+            },
+            back: Palette {
+                card: "#e8eef6".to_string(),
+                panel: "#e8eef6".to_string(),
+                ink: (33, 33, 33),
+                accent: "#1565c0".to_string(),
+            },
+            font_family: "'Noto Sans JP', sans-serif".to_string(),
+            text_scale: 1.0,
+            decorations: Decorations::plain(),
+            background: Some("#f0f0f0".to_string()),
+        }
+    }
+
+    /// 暗い地に落ち着いた色（夜の学習用）
+    pub fn dark() -> Self {
+        Theme {
+            name: "dark",
+            front: Palette {
+                card: "#1f2a1f".to_string(),
+                panel: "#1f2a1f".to_string(),
+                ink: (220, 230, 220),
+                accent: "#72F285".to_string(),
+            },
+            back: Palette {
+                card: "#1b2433".to_string(),
+                panel: "#141b26".to_string(),
+                ink: (210, 220, 235),
+                accent: "#5b8def".to_string(),
+            },
+            font_family: "MyFont".to_string(),
+            text_scale: 1.0,
+            decorations: Decorations::celtic(),
+            background: Some("#0b0b0f".to_string()),
+        }
+    }
+
+    /// 黒地に白・白地に黒で、強調は黄色と青
+    pub fn high_contrast() -> Self {
+        Theme {
+            name: "high-contrast",
+            front: Palette {
+                card: "#000000".to_string(),
+                panel: "#000000".to_string(),
+                ink: (255, 255, 255),
+                accent: "#ffff00".to_string(),
+            },
+            back: Palette {
+                card: "#ffffff".to_string(),
+                panel: "#ffffff".to_string(),
+                ink: (0, 0, 0),
+                accent: "#0000ff".to_string(),
+            },
+            font_family: "'Noto Sans JP', sans-serif".to_string(),
+            text_scale: 1.0,
+            decorations: Decorations::plain(),
+            background: Some("#000000".to_string()),
+        }
+    }
+
+    pub fn palette(&self, face: Face) -> &Palette {
+        match face {
+            Face::Front => &self.front,
+            Face::Back => &self.back,
+        }
+    }
+
+    /// canvas の font 指定（例: "18px MyFont"）。px に文字の大きさの倍率をかける
+    pub fn font(&self, px: f64) -> String {
+        format!("{}px {}", px * self.text_scale, self.font_family)
+    }
+}
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::celtic()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_name_resolves_to_its_theme() {
+        for name in Theme::NAMES {
+            assert_eq!(Theme::by_name(name).map(|t| t.name), Some(name));
+        }
+        assert_eq!(Theme::by_name(" dark "), Some(Theme::dark()));
+        assert_eq!(Theme::by_name("sepia"), None);
+    }
+
+    #[test]
+    fn faces_have_distinct_palettes() {
+        for name in Theme::NAMES {
+            let theme = Theme::by_name(name).unwrap();
+            assert_ne!(theme.palette(Face::Front).card, theme.palette(Face::Back).card, "{}", name);
+        }
+        assert_eq!(Theme::celtic().front.ink(0.5), "rgba(255, 255, 255, 0.5)");
+        assert_eq!(Theme::celtic().font(18.0), "18px MyFont");
+    }
+}
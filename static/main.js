@@ -0,0 +1,24 @@
+// wasm-pack の出力先（js/）はビルドのたびに作り直されて git で無視されるので、読み込み用のスクリプトはここに置く
+import init, { main, bind_key, set_theme, set_setting, recording } from "../js/wasm.js";
+
+// コンソールからキー割り当てを変更できるようにする（例: bind_key("flip", "KeyJ")）
+window.bind_key = bind_key;
+// テーマも同様に変更できる（例: set_theme("dark")）
+window.set_theme = set_theme;
+// 動き・コントラスト・文字の設定も同様（例: set_setting("reduced_motion", "on")）
+window.set_setting = set_setting;
+// ?record をつけて起動すると入力を記録し、save_recording() でファイルとして保存できる（不具合の報告用）
+window.save_recording = () => {
+  const text = recording();
+  if (text === undefined) {
+    console.warn("?record をつけて起動してください");
+    return;
+  }
+  const link = document.createElement("a");
+  link.href = URL.createObjectURL(new Blob([text], { type: "text/plain" }));
+  link.download = "card.replay";
+  link.click();
+  URL.revokeObjectURL(link.href);
+};
+
+init().then(() => main());